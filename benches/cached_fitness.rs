@@ -0,0 +1,57 @@
+//! Evaluating children changed by one move with and without the period fitness cache, run with
+//! `cargo bench --features testing --bench cached_fitness`
+
+use std::time::Instant;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use planner::algorithm::{
+    calculate_fitness, calculate_fitness_cached,
+    config::AlgorithmConfig,
+    create_first_population,
+    datatypes::{Adaptation, Individual, TupleIndex},
+    testing,
+};
+
+fn main() {
+    let config = AlgorithmConfig {
+        population_size: 200,
+        number_of_periods: 40,
+        ..AlgorithmConfig::default()
+    };
+    let tuples = TupleIndex::from(testing::generate_synthetic_problem(1_000, 60, 40, 40, 1));
+    let mut population = create_first_population(&config, &tuples);
+    for individual in population.iter_mut() {
+        calculate_fitness_cached(&config, individual, &tuples);
+    }
+
+    // the single move of a mutation
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut children: Vec<Individual> = population
+        .iter()
+        .map(|individual| {
+            let mut child = individual.clone();
+            let from = (0..config.number_of_periods)
+                .find(|period| !child.chromosomes[*period].genes.is_empty())
+                .unwrap();
+            child.move_gene(from, 0, rng.gen_range(0..config.number_of_periods));
+            child
+        })
+        .collect();
+
+    let start = Instant::now();
+    let full: Vec<Adaptation> = children
+        .iter()
+        .map(|child| calculate_fitness(&config, child, &tuples))
+        .collect();
+    println!("fitness, full: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let cached: Vec<Adaptation> = children
+        .iter_mut()
+        .map(|child| calculate_fitness_cached(&config, child, &tuples))
+        .collect();
+    println!("fitness, cached: {:?}", start.elapsed());
+
+    assert_eq!(full, cached);
+}
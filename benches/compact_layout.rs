@@ -0,0 +1,85 @@
+//! Fitness, gene moves and crossover on the nested and the compact layout, run with
+//! `cargo bench --features testing,compact-genes --bench compact_layout`
+
+use std::time::Instant;
+
+use itertools::Itertools;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use planner::algorithm::{
+    calculate_fitness,
+    compact::CompactIndividual,
+    config::AlgorithmConfig,
+    create_first_population,
+    datatypes::{Adaptation, Locks, TupleIndex},
+    partially_mapped_crossover, testing,
+};
+
+fn main() {
+    let config = AlgorithmConfig {
+        population_size: 20,
+        number_of_periods: 40,
+        ..AlgorithmConfig::default()
+    };
+    let tuples = TupleIndex::from(testing::generate_synthetic_problem(1_000, 60, 40, 40, 1));
+    let nested = create_first_population(&config, &tuples);
+    let compact: Vec<CompactIndividual> = nested.iter().map(CompactIndividual::from).collect();
+
+    let start = Instant::now();
+    let nested_fitness: Vec<Adaptation> = nested
+        .iter()
+        .map(|individual| calculate_fitness(&config, individual, &tuples))
+        .collect();
+    println!("fitness, nested: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let compact_fitness: Vec<Adaptation> = compact
+        .iter()
+        .map(|individual| calculate_fitness(&config, individual, &tuples))
+        .collect();
+    println!("fitness, compact: {:?}", start.elapsed());
+
+    assert_eq!(nested_fitness, compact_fitness);
+
+    // the same sequence of moves a mutation would do
+    let mut rng = StdRng::seed_from_u64(1);
+    let moves: Vec<(usize, usize)> = (0..100_000)
+        .map(|_| {
+            (
+                rng.gen_range(0..config.number_of_periods),
+                rng.gen_range(0..config.number_of_periods),
+            )
+        })
+        .collect();
+
+    let mut nested_individual = nested[0].clone();
+    let start = Instant::now();
+    for (from, to) in &moves {
+        if !nested_individual.chromosomes[*from].genes.is_empty() {
+            nested_individual.move_gene(*from, 0, *to);
+        }
+    }
+    println!("gene moves, nested: {:?}", start.elapsed());
+
+    let mut compact_individual = compact[0].clone();
+    let start = Instant::now();
+    for (from, to) in &moves {
+        if !compact_individual.period(*from).is_empty() {
+            compact_individual.move_gene(*from, 0, *to);
+        }
+    }
+    println!("gene moves, compact: {:?}", start.elapsed());
+
+    let locks = Locks::new();
+    let start = Instant::now();
+    for (left, right) in nested.iter().tuple_combinations() {
+        partially_mapped_crossover(left, right, 0.5, 0.0, &locks, &mut rng);
+    }
+    println!("crossover, nested: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    for (left, right) in compact.iter().tuple_combinations() {
+        CompactIndividual::partially_mapped_crossover(left, right, 0.5, 0.0, &locks, &mut rng);
+    }
+    println!("crossover, compact: {:?}", start.elapsed());
+}
@@ -0,0 +1,41 @@
+//! Time of evolving one generation of an unseeded run, run with
+//! `cargo bench --features testing --bench generation_time`
+
+use std::time::Instant;
+
+use planner::algorithm::{
+    calculate_fitness_cached,
+    config::AlgorithmConfig,
+    create_first_population,
+    datatypes::{sort_by_adaptation, Locks, TupleIndex},
+    evolve_one_generation, testing,
+};
+
+fn main() {
+    let config = AlgorithmConfig {
+        population_size: 400,
+        number_of_periods: 40,
+        ..AlgorithmConfig::default()
+    };
+    let tuples = TupleIndex::from(testing::generate_synthetic_problem(1_000, 60, 40, 40, 1));
+    let mut population = create_first_population(&config, &tuples);
+    for individual in population.iter_mut() {
+        calculate_fitness_cached(&config, individual, &tuples);
+    }
+    sort_by_adaptation(&mut population);
+
+    let generations = 20;
+    let start = Instant::now();
+    for generation in 0..generations {
+        population = evolve_one_generation(
+            &config,
+            &population,
+            generation,
+            &tuples,
+            &Locks::new(),
+            None,
+            None,
+        );
+    }
+    println!("generation: {:?}", start.elapsed() / generations as u32);
+}
@@ -0,0 +1,42 @@
+//! Bytes sent with and without compressing the MPI payloads and the time it costs, run with
+//! `cargo bench --features testing --bench payload_compression`
+
+use std::time::Instant;
+
+use planner::{
+    algorithm::{config::AlgorithmConfig, create_first_population, testing},
+    mpi_utils::{compress_payload, decompress_payload, serialize_vec},
+};
+
+fn main() {
+    let config = AlgorithmConfig {
+        population_size: 100,
+        number_of_periods: 40,
+        ..AlgorithmConfig::default()
+    };
+    let tuples = testing::generate_synthetic_problem(1_000, 60, 40, 40, 1);
+    let population = create_first_population(&config, &tuples);
+
+    let start = Instant::now();
+    let (_, serialized_data) = serialize_vec(&population).unwrap();
+    println!(
+        "serialize: {:?}, {} bytes",
+        start.elapsed(),
+        serialized_data.len()
+    );
+
+    let start = Instant::now();
+    let compressed = compress_payload(&serialized_data);
+    println!(
+        "compress: {:?}, {} bytes ({:.1}%)",
+        start.elapsed(),
+        compressed.len(),
+        100.0 * compressed.len() as f64 / serialized_data.len() as f64
+    );
+
+    let start = Instant::now();
+    let decompressed = decompress_payload(&compressed).unwrap();
+    println!("decompress: {:?}", start.elapsed());
+
+    assert_eq!(decompressed, serialized_data);
+}
@@ -0,0 +1,70 @@
+//! Allocations of serializing the population of a generation compared with cloning it first, as
+//! the split and gather did before, run with
+//! `cargo bench --features testing --bench serialize_allocations`
+//!
+//! The counting allocator is the global allocator of this binary only, so the library tests keep
+//! the system allocator.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use planner::{
+    algorithm::{config::AlgorithmConfig, create_first_population, testing},
+    mpi_utils::serialize_vec,
+};
+
+/// Counts the allocations of the bench, see [`count_allocations`]
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Allocations and bytes allocated while running `f`
+fn count_allocations<R>(f: impl FnOnce() -> R) -> (usize, usize, R) {
+    let (allocations, bytes) = (
+        ALLOCATIONS.load(Ordering::Relaxed),
+        ALLOCATED_BYTES.load(Ordering::Relaxed),
+    );
+    let result = f();
+    (
+        ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes,
+        result,
+    )
+}
+
+fn main() {
+    let config = AlgorithmConfig {
+        population_size: 100,
+        number_of_periods: 40,
+        ..AlgorithmConfig::default()
+    };
+    let tuples = testing::generate_synthetic_problem(1_000, 60, 40, 40, 1);
+    let population = create_first_population(&config, &tuples);
+
+    let (allocations, bytes, borrowed) = count_allocations(|| serialize_vec(&population).unwrap());
+    println!("borrowed: {} allocations, {} bytes", allocations, bytes);
+
+    let (allocations, bytes, cloned) =
+        count_allocations(|| serialize_vec(&population.to_vec()).unwrap());
+    println!("cloned: {} allocations, {} bytes", allocations, bytes);
+
+    assert_eq!(borrowed, cloned);
+}
@@ -0,0 +1,164 @@
+//! Saving the population of a run, so a crashed run doesn't lose its progress
+
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{
+    config::AlgorithmConfig,
+    datatypes::{IndividualImportError, Population},
+};
+
+#[derive(Debug, Error)]
+pub enum CheckpointError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("Checkpoint has {found} individuals, but the configuration expects {expected}")]
+    PopulationSizeMismatch { expected: usize, found: usize },
+    #[error(transparent)]
+    PeriodCountMismatch(#[from] IndividualImportError),
+}
+
+/// Population of a run after `generation` generations, see
+/// [`crate::algorithm::config::AlgorithmConfig::checkpoint_interval`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub generation: usize,
+    pub population: Population,
+}
+
+/// File the checkpoint is written to before it replaces the one at `path`
+fn temporary_path(path: &Path) -> PathBuf {
+    let mut temporary = OsString::from(path.as_os_str());
+    temporary.push(".tmp");
+    PathBuf::from(temporary)
+}
+
+impl Checkpoint {
+    /// Write the checkpoint as JSON
+    ///
+    /// The checkpoint is written next to `path` first and then renamed over it, so a crash while
+    /// writing leaves the previous checkpoint intact.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CheckpointError> {
+        let path = path.as_ref();
+        let temporary = temporary_path(path);
+
+        let mut writer = BufWriter::new(File::create(&temporary)?);
+        serde_json::to_writer(&mut writer, self)?;
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+        drop(writer);
+
+        fs::rename(&temporary, path)?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Checkpoint, CheckpointError> {
+        let file = File::open(path)?;
+        Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+    }
+
+    /// Check that the run can be resumed with `config`, which has to agree with the checkpointed
+    /// population on its size and number of periods
+    pub fn validate(&self, config: &AlgorithmConfig) -> Result<(), CheckpointError> {
+        if self.population.len() != config.population_size {
+            return Err(CheckpointError::PopulationSizeMismatch {
+                expected: config.population_size,
+                found: self.population.len(),
+            });
+        }
+        for individual in &self.population {
+            individual.validate_period_count(config.number_of_periods)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{
+        create_first_population,
+        testing::{generate_synthetic_problem, TempFile},
+    };
+
+    fn config() -> AlgorithmConfig {
+        AlgorithmConfig {
+            population_size: 8,
+            number_of_periods: 4,
+            random_seed: Some(5),
+            ..AlgorithmConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        let config = config();
+        let tuples = generate_synthetic_problem(20, 4, 4, 4, 2);
+        let checkpoint = Checkpoint {
+            generation: 17,
+            population: create_first_population(&config, &tuples),
+        };
+
+        let path = TempFile::new("checkpoint", "json");
+        checkpoint.save(&path).unwrap();
+        // saving again replaces the checkpoint
+        checkpoint.save(&path).unwrap();
+        let loaded = Checkpoint::load(&path).unwrap();
+
+        assert!(!temporary_path(&path).exists());
+        assert_eq!(loaded.generation, 17);
+        assert_eq!(
+            serde_json::to_string(&loaded.population).unwrap(),
+            serde_json::to_string(&checkpoint.population).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_validate_against_config() {
+        let config = config();
+        let checkpoint = Checkpoint {
+            generation: 3,
+            population: create_first_population(
+                &config,
+                &generate_synthetic_problem(20, 4, 4, 4, 2),
+            ),
+        };
+        assert!(checkpoint.validate(&config).is_ok());
+
+        let resized = AlgorithmConfig {
+            population_size: 10,
+            ..config
+        };
+        assert!(matches!(
+            checkpoint.validate(&resized),
+            Err(CheckpointError::PopulationSizeMismatch {
+                expected: 10,
+                found: 8
+            })
+        ));
+
+        let more_periods = AlgorithmConfig {
+            number_of_periods: 5,
+            ..config
+        };
+        assert!(matches!(
+            checkpoint.validate(&more_periods),
+            Err(CheckpointError::PeriodCountMismatch(
+                IndividualImportError::PeriodCountMismatch {
+                    expected: 5,
+                    found: 4
+                }
+            ))
+        ));
+    }
+}
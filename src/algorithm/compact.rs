@@ -0,0 +1,254 @@
+//! Compact layout of an individual, enabled by the `compact-genes` feature
+//!
+//! The layout provides fitness, gene moves and crossover, but the algorithm still evolves
+//! [`Individual`]s. Convert with [`From`] to evaluate or recombine compact individuals.
+
+use itertools::Itertools;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    datatypes::{Adaptation, Chromosome, Gene, Individual, Locks, Timetable},
+    partially_mapped_targets,
+};
+
+/// Individual with the genes of all periods stored in a single vector
+///
+/// Genes of the period at index `i` are `genes[bounds[i]..bounds[i + 1]]`. One allocation per
+/// individual instead of one per period keeps a large population smaller and the fitness
+/// calculation, which goes over all genes of a period, cache friendly. Period ids are their
+/// indices, as in [`crate::algorithm::create_first_population`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompactIndividual {
+    pub adaptation: Adaptation,
+    pub hard_adaptation: Adaptation,
+    genes: Vec<Gene>,
+    bounds: Vec<usize>,
+}
+
+impl CompactIndividual {
+    pub fn number_of_periods(&self) -> usize {
+        self.bounds.len() - 1
+    }
+
+    /// Genes of the period at `index`
+    pub fn period(&self, index: usize) -> &[Gene] {
+        &self.genes[self.bounds[index]..self.bounds[index + 1]]
+    }
+
+    /// Move the gene at `gene_index` of the period at `from` to the end of the period at `to`
+    ///
+    /// Same as [`Individual::move_gene`], the genes between both periods are shifted by one
+    /// place instead of reallocating any period.
+    pub fn move_gene(&mut self, from: usize, gene_index: usize, to: usize) {
+        let position = self.bounds[from] + gene_index;
+        assert!(position < self.bounds[from + 1]);
+
+        if from <= to {
+            self.genes[position..self.bounds[to + 1]].rotate_left(1);
+            for bound in &mut self.bounds[from + 1..=to] {
+                *bound -= 1;
+            }
+        } else {
+            self.genes[self.bounds[to + 1]..=position].rotate_right(1);
+            for bound in &mut self.bounds[to + 1..=from] {
+                *bound += 1;
+            }
+        }
+    }
+
+    /// [`crate::algorithm::partially_mapped_crossover`] of two compact individuals
+    ///
+    /// The genes of every period of the child are counted first, so the child is written into a
+    /// single vector without growing any period.
+    pub fn partially_mapped_crossover(
+        left: &CompactIndividual,
+        right: &CompactIndividual,
+        left_share: f64,
+        bias: f32,
+        locks: &Locks,
+        rng: &mut impl Rng,
+    ) -> CompactIndividual {
+        let targets = partially_mapped_targets(left, right, left_share, bias, locks, rng);
+
+        let mut bounds = vec![0; left.bounds.len()];
+        for (_, target) in &targets {
+            bounds[target + 1] += 1;
+        }
+        for index in 1..bounds.len() {
+            bounds[index] += bounds[index - 1];
+        }
+
+        let mut genes = vec![Gene::default(); targets.len()];
+        let mut next = bounds.clone();
+        for (gene, target) in targets {
+            genes[next[target]] = gene;
+            next[target] += 1;
+        }
+
+        CompactIndividual {
+            adaptation: Adaptation::default(),
+            hard_adaptation: Adaptation::default(),
+            genes,
+            bounds,
+        }
+    }
+}
+
+impl Timetable for CompactIndividual {
+    fn period_genes(&self) -> impl Iterator<Item = &[Gene]> {
+        self.bounds
+            .iter()
+            .tuple_windows()
+            .map(|(start, end)| &self.genes[*start..*end])
+    }
+}
+
+impl From<&Individual> for CompactIndividual {
+    fn from(individual: &Individual) -> Self {
+        let mut bounds = Vec::with_capacity(individual.chromosomes.len() + 1);
+        bounds.push(0);
+
+        let mut genes = Vec::new();
+        for period in &individual.chromosomes {
+            genes.extend_from_slice(&period.genes);
+            bounds.push(genes.len());
+        }
+
+        CompactIndividual {
+            adaptation: individual.adaptation,
+            hard_adaptation: individual.hard_adaptation,
+            genes,
+            bounds,
+        }
+    }
+}
+
+impl From<&CompactIndividual> for Individual {
+    fn from(compact: &CompactIndividual) -> Self {
+        Individual {
+            adaptation: compact.adaptation,
+            hard_adaptation: compact.hard_adaptation,
+            ..Individual::with_chromosomes(
+                compact
+                    .period_genes()
+                    .enumerate()
+                    .map(|(id, genes)| Chromosome {
+                        id: id.try_into().unwrap(),
+                        genes: genes.to_vec(),
+                    })
+                    .collect(),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::algorithm::{
+        calculate_fitness, config::AlgorithmConfig, datatypes::TupleIndex,
+        partially_mapped_crossover, testing::generate_synthetic_problem,
+    };
+
+    fn nested_individual() -> Individual {
+        Individual::with_chromosomes(
+            [vec![1, 2], vec![], vec![3, 4, 5], vec![6]]
+                .into_iter()
+                .enumerate()
+                .map(|(id, genes)| Chromosome {
+                    id: id as i32,
+                    genes,
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_conversion_round_trip() {
+        let nested = nested_individual();
+        let compact = CompactIndividual::from(&nested);
+
+        assert_eq!(compact.number_of_periods(), 4);
+        assert_eq!(compact.period(1), &[] as &[Gene]);
+        assert_eq!(compact.period(2), &[3, 4, 5]);
+        assert_eq!(
+            Individual::from(&compact)
+                .period_genes()
+                .collect::<Vec<_>>(),
+            nested.period_genes().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_move_gene_matches_nested_layout() {
+        for (from, gene_index, to) in [(0, 0, 3), (3, 0, 0), (2, 1, 1), (2, 2, 2), (0, 1, 1)] {
+            let mut nested = nested_individual();
+            let mut compact = CompactIndividual::from(&nested);
+
+            nested.move_gene(from, gene_index, to);
+            compact.move_gene(from, gene_index, to);
+
+            assert_eq!(
+                compact.period_genes().collect::<Vec<_>>(),
+                nested.period_genes().collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_crossover_matches_nested_layout() {
+        let left = nested_individual();
+        let mut right = nested_individual();
+        right.chromosomes.reverse();
+        let locks = Locks::from([(4, 0)]);
+
+        for seed in 0..20 {
+            let nested = partially_mapped_crossover(
+                &left,
+                &right,
+                0.5,
+                0.0,
+                &locks,
+                &mut StdRng::seed_from_u64(seed),
+            );
+            let compact = CompactIndividual::partially_mapped_crossover(
+                &CompactIndividual::from(&left),
+                &CompactIndividual::from(&right),
+                0.5,
+                0.0,
+                &locks,
+                &mut StdRng::seed_from_u64(seed),
+            );
+
+            assert_eq!(
+                compact.period_genes().collect::<Vec<_>>(),
+                nested.period_genes().collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_fitness_matches_nested_layout() {
+        let config = AlgorithmConfig {
+            subject_spacing_weight: 1 as Adaptation,
+            ..AlgorithmConfig::default()
+        };
+        let tuples = TupleIndex::from(generate_synthetic_problem(60, 5, 4, 8, 11));
+        let nested = crate::algorithm::create_first_population(
+            &AlgorithmConfig {
+                population_size: 1,
+                ..config
+            },
+            &tuples,
+        )
+        .remove(0);
+
+        assert_eq!(
+            calculate_fitness(&config, &CompactIndividual::from(&nested), &tuples),
+            calculate_fitness(&config, &nested, &tuples)
+        );
+    }
+}
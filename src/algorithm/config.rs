@@ -1,15 +1,58 @@
 use std::{fs::File, path::Path};
 
-use mpi::traits::Equivalence;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use super::{
+    datatypes::{Adaptation, ConflictType},
+    selection_weight, DEFAULT_SELECTION_DECAY, DEFAULT_SELECTION_OFFSET,
+};
+
 #[derive(Error, Debug)]
 pub enum ConfigLoadError {
     #[error("Configuration file not found")]
     FileNotFound(#[from] std::io::Error),
     #[error(transparent)]
     JsonError(#[from] serde_json::Error),
+    #[error(transparent)]
+    YamlError(#[from] serde_yaml::Error),
+    #[error("{name} has to be positive, got {decay}")]
+    NonPositiveSelectionDecay { name: &'static str, decay: f64 },
+    #[error(
+        "selection weight of rank {rank} is {weight} with decay {decay} and offset {offset}, the \
+         two fittest individuals need a positive finite weight to be selectable"
+    )]
+    UnselectableParents {
+        rank: usize,
+        weight: f64,
+        decay: f64,
+        offset: f64,
+    },
+    #[error("fitness weight {name} can't be negative, got {weight}")]
+    NegativeFitnessWeight {
+        name: &'static str,
+        weight: Adaptation,
+    },
+    #[error("{name} is a probability and has to be between 0 and 1, got {value}")]
+    ProbabilityOutOfRange { name: &'static str, value: f32 },
+    #[error(
+        "generation_gap is the replaced share of the population, it has to be in (0, 1], got {0}"
+    )]
+    GenerationGapOutOfRange(f32),
+    #[error("crossover_fitness_bias has to be finite and at least 0, got {0}")]
+    InvalidCrossoverBias(f32),
+    #[error("{0} can't be 0")]
+    ZeroSize(&'static str),
+    #[error("population_size has to be at least 2 to have parents to mate, got {0}")]
+    PopulationTooSmall(usize),
+    #[error(
+        "islands need at least 2 individuals each, population_size {population_size} is too \
+         small for {nodes} nodes"
+    )]
+    IslandsTooSmall {
+        population_size: usize,
+        nodes: usize,
+    },
 }
 
 /// Configuration for the genetic algorithm
@@ -17,7 +60,7 @@ pub enum ConfigLoadError {
 /// * Chromosome - a period of time with a list of genes (classes that are
 ///   happening at that time)
 /// * Gene - an id of tuple consisting of teacher, subject, room and class
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Equivalence)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 #[serde(default)]
 pub struct AlgorithmConfig {
     /// How many generations maximum to run
@@ -29,17 +72,599 @@ pub struct AlgorithmConfig {
     /// How many genes are in each chromosome. Chromosome length
     pub number_of_periods: usize,
 
-    /// The probability of mutation occurring
+    /// The probability of mutation occurring, the base rate with `adaptive_mutation`
     pub mutation_probability: f32,
+
+    /// Raise the mutation probability while the population lacks diversity, see
+    /// [`crate::algorithm::adaptive_mutation_probability`]
+    pub adaptive_mutation: Option<AdaptiveMutation>,
+
+    /// The probability of a child being mated from two parents. Otherwise it is a copy of the
+    /// first selected parent, which is still mutated
+    pub crossover_probability: f32,
+
+    /// How the periods of the parents are mated
+    pub crossover_method: CrossoverMethod,
+
+    /// How strongly the fitter parent is favoured when choosing the crossover mating point, at
+    /// least `0.0`. `0.0` keeps the mating point uniform, higher values let the fitter parent pass on a
+    /// larger share of each period's genes
+    pub crossover_fitness_bias: f32,
+
+    /// Penalty for every pair of classes of the same subject scheduled in the same or adjacent
+    /// periods. `0` disables the spacing objective
+    pub subject_spacing_weight: Adaptation,
+
+    /// Penalty for every period that has any classes, so timetables leaving whole periods free
+    /// score better. `0` disables the compaction objective
+    pub compaction_weight: Adaptation,
+
+    /// Penalty for every room beyond the first one a teacher teaches in during a day. `0`
+    /// disables the room stability objective
+    pub room_change_weight: Adaptation,
+
+    /// Penalty for every room in a period attended by more students ([`Tuple::size`]) than it
+    /// has seats ([`Tuple::capacity`]). Rooms with an unknown capacity are never overfull. `0`
+    /// disables the capacity objective
+    ///
+    /// [`Tuple::size`]: crate::algorithm::datatypes::Tuple::size
+    /// [`Tuple::capacity`]: crate::algorithm::datatypes::Tuple::capacity
+    pub room_capacity_weight: Adaptation,
+
+    /// How many consecutive periods make up a day. When unset the whole timetable is one day
+    pub periods_per_day: Option<usize>,
+
+    /// Length of a period in minutes, used to place periods on a calendar
+    pub period_minutes: u32,
+
+    /// Minutes after midnight the first period of every day starts at, `480` is 8:00
+    pub day_start_minutes: u32,
+
+    /// Penalties of the conflicts between classes in the same period
+    pub fitness_weights: FitnessWeights,
+
+    /// Which conflicts are hard, timetables are ranked by their hard penalties first
+    pub hard_constraints: HardConstraints,
+
+    /// How a mutating period chooses the genes it gives away
+    pub mutation_method: MutationMethod,
+
+    /// How many genes a mutating period gives away at the start of the run
+    pub max_mutation_step: usize,
+
+    /// How the number of genes moved by a mutation decreases towards a single gene
+    pub mutation_cooling: CoolingSchedule,
+
+    /// Hard adaptation below which a child is dead, soft penalties never kill a child. A dead child is created again, at most
+    /// `max_dead_retries` times, after which the individual it would replace is carried over.
+    /// When unset every child lives
+    pub dead_threshold: Option<Adaptation>,
+
+    /// How many times a dead child is created again before giving up on it
+    pub max_dead_retries: usize,
+
+    /// Minimum improvement of a child over the parent it takes most genes from, measured on the
+    /// hard adaptation or on the soft one when both hard adaptations are equal. A child improving
+    /// less is replaced by a copy of that parent, turning the step into hill climbing. When unset
+    /// every child is accepted
+    pub min_child_improvement: Option<Adaptation>,
+
+    /// Fraction of the population replaced by new children every generation, in `(0, 1]`.
+    /// The rest are the best individuals carried over from the previous generation
+    pub generation_gap: f32,
+
+    /// Maximum number of classes in a period that crossover repair tries to keep to. Unlimited
+    /// when unset
+    pub period_capacity: Option<usize>,
+
+    /// How many random periods crossover repair tries before it falls back to the least
+    /// occupied one
+    pub max_repair_attempts: usize,
+
+    /// How parents are selected for crossover
+    pub selection_method: SelectionMethod,
+
+    /// Decay `a` of the selection weights `e^(-a * rank + b)`, see
+    /// [`crate::algorithm::rand_parents`]. Higher values favour the fittest individuals more.
+    /// Has to be positive
+    pub selection_decay: f64,
+
+    /// Offset `b` of the selection weights `e^(-a * rank + b)`. It scales all weights equally, so
+    /// it only decides from which rank on the weights underflow to zero
+    pub selection_offset: f64,
+
+    /// Change the selection pressure over the run. When unset the pressure stays constant
+    pub selection_annealing: Option<SelectionAnnealing>,
+
+    /// Seed making the run reproducible with the same tuples and number of nodes. When unset
+    /// every run is different
+    pub random_seed: Option<u64>,
+
+    /// Shuffle the population before splitting it across the nodes, using an order derived from
+    /// `random_seed` and the generation number, so reruns with the same seed and number of nodes
+    /// give every node the same work
+    pub deterministic_chunking: bool,
+
+    /// [`crate::algorithm::population_diversity`] below which `immigrant_count` random
+    /// individuals replace the weakest ones before the generation is evolved. `0.0` never brings
+    /// in immigrants
+    pub diversity_threshold: f64,
+
+    /// How many random immigrants replace the weakest individuals of a population lacking
+    /// diversity. Islands don't take immigrants, they exchange migrants instead
+    pub immigrant_count: usize,
+
+    /// Minimum [`crate::algorithm::genome_distance`] between any two individuals of the first
+    /// population. `0` accepts any random individual
+    pub min_initial_distance: usize,
+
+    /// How many times an individual too similar to the rest of the first population is
+    /// regenerated before it is accepted anyway
+    pub max_initial_attempts: usize,
+
+    /// Stop this many generations after the best timetable first has no hard conflicts, to
+    /// improve its soft objectives a little. When unset the run goes on until a perfect timetable
+    /// or `max_generations`
+    pub polish_generations: Option<usize>,
+
+    /// Stop when the best adaptation hasn't strictly improved for this many consecutive
+    /// generations. `0` never stops on stagnation
+    pub stagnation_limit: usize,
+
+    /// Stop after the first generation that ends this many seconds after the run started. The
+    /// best timetable found until then is still reported
+    pub time_budget_secs: Option<u64>,
+
+    /// Save the whole population every this many generations to the file given with
+    /// `--checkpoint`. `0` never saves it
+    pub checkpoint_interval: usize,
+
+    /// Evolve every node's part of the population separately, as an island, and only gather the
+    /// best `k` individuals of every node each generation. When unset the whole population is
+    /// gathered and selection is global
+    pub gather_best_k: Option<usize>,
+
+    /// Evolve every node's part of the population separately, as an island, and send the best
+    /// `migration_count` individuals to the next node of a ring every `migration_interval`
+    /// generations. Only the best individual of every node (`gather_best_k` when set) is gathered
+    /// each generation
+    pub island_mode: bool,
+
+    /// Generations between migrations in `island_mode`. `0` never migrates
+    pub migration_interval: usize,
+
+    /// How many of its best individuals an island sends to its neighbour in every migration
+    pub migration_count: usize,
+
+    /// Compress the population with deflate before scattering and gathering it. Saves bandwidth
+    /// between nodes on large problems for a little CPU time on both ends
+    pub compress_mpi: bool,
+
+    /// Don't stop before this many distinct timetables without hard conflicts are found, even
+    /// when one of them is perfect
+    pub required_solutions: Option<RequiredSolutions>,
+}
+
+/// Penalty subtracted from the fitness for every class in each kind of conflict with another
+/// class of its period, see [`ConflictType`]. Every conflict is counted from both of its classes
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct FitnessWeights {
+    pub teacher_multi_class: Adaptation,
+    pub room_clash: Adaptation,
+    pub same_teacher_same_subject: Adaptation,
+    pub same_teacher_different_subject: Adaptation,
+    /// Classes of the same student group, see [`Tuple::group`]
+    ///
+    /// [`Tuple::group`]: crate::algorithm::datatypes::Tuple::group
+    pub group_clash: Adaptation,
+}
+
+impl FitnessWeights {
+    /// Penalty of every occurrence of the conflict
+    pub fn penalty(&self, conflict_type: ConflictType) -> Adaptation {
+        match conflict_type {
+            ConflictType::TeacherMultiClass => self.teacher_multi_class,
+            ConflictType::RoomClash => self.room_clash,
+            ConflictType::SameTeacherSameSubject => self.same_teacher_same_subject,
+            ConflictType::SameTeacherDifferentSubject => self.same_teacher_different_subject,
+            ConflictType::GroupClash => self.group_clash,
+        }
+    }
+
+    /// Fail on the first negative weight, which would reward a conflict
+    pub fn validate(&self) -> Result<(), ConfigLoadError> {
+        let weights = [
+            ("teacher_multi_class", self.teacher_multi_class),
+            ("room_clash", self.room_clash),
+            ("same_teacher_same_subject", self.same_teacher_same_subject),
+            (
+                "same_teacher_different_subject",
+                self.same_teacher_different_subject,
+            ),
+            ("group_clash", self.group_clash),
+        ];
+
+        match weights
+            .into_iter()
+            .find(|(_, weight)| *weight < Adaptation::default())
+        {
+            Some((name, weight)) => Err(ConfigLoadError::NegativeFitnessWeight { name, weight }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for FitnessWeights {
+    /// Default penalties, see [`ConflictType::penalty`]
+    fn default() -> Self {
+        FitnessWeights {
+            teacher_multi_class: ConflictType::TeacherMultiClass.penalty(),
+            room_clash: ConflictType::RoomClash.penalty(),
+            same_teacher_same_subject: ConflictType::SameTeacherSameSubject.penalty(),
+            same_teacher_different_subject: ConflictType::SameTeacherDifferentSubject.penalty(),
+            group_clash: ConflictType::GroupClash.penalty(),
+        }
+    }
+}
+
+/// Conflicts that are hard constraints, see [`Score`]. The rest count as soft, like the soft
+/// objectives. Every conflict is hard by default, see [`ConflictType::is_hard`]
+///
+/// [`Score`]: crate::algorithm::datatypes::Score
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct HardConstraints {
+    pub teacher_multi_class: bool,
+    pub room_clash: bool,
+    pub same_teacher_same_subject: bool,
+    pub same_teacher_different_subject: bool,
+    pub group_clash: bool,
+}
+
+impl HardConstraints {
+    pub fn is_hard(&self, conflict_type: ConflictType) -> bool {
+        match conflict_type {
+            ConflictType::TeacherMultiClass => self.teacher_multi_class,
+            ConflictType::RoomClash => self.room_clash,
+            ConflictType::SameTeacherSameSubject => self.same_teacher_same_subject,
+            ConflictType::SameTeacherDifferentSubject => self.same_teacher_different_subject,
+            ConflictType::GroupClash => self.group_clash,
+        }
+    }
+}
+
+impl Default for HardConstraints {
+    fn default() -> Self {
+        HardConstraints {
+            teacher_multi_class: ConflictType::TeacherMultiClass.is_hard(),
+            room_clash: ConflictType::RoomClash.is_hard(),
+            same_teacher_same_subject: ConflictType::SameTeacherSameSubject.is_hard(),
+            same_teacher_different_subject: ConflictType::SameTeacherDifferentSubject.is_hard(),
+            group_clash: ConflictType::GroupClash.is_hard(),
+        }
+    }
+}
+
+/// Selection pressure changing linearly from `start_decay` in the first generation to `end_decay`
+/// in the last one. See [`crate::algorithm::rand_parents`] for how the decay is used
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct SelectionAnnealing {
+    /// Decay of the selection weights in the first generation, low values explore
+    pub start_decay: f64,
+    /// Decay of the selection weights in the last generation, high values exploit
+    pub end_decay: f64,
+}
+
+/// Mutation probability rising from [`AlgorithmConfig::mutation_probability`] up to
+/// `max_probability` as the spread of the adaptations falls below `diversity_threshold`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct AdaptiveMutation {
+    pub max_probability: f32,
+    /// Difference between the best and the mean adaptation the population is considered diverse
+    /// enough at
+    pub diversity_threshold: f64,
+}
+
+/// Number of distinct timetables without hard conflicts to find before the run stops
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct RequiredSolutions {
+    pub count: usize,
+    /// Minimum [`crate::algorithm::genome_distance`] between any two of the timetables, at
+    /// least `1`
+    pub min_distance: usize,
+}
+
+/// Way of choosing parents from the population sorted from the fittest individual, see
+/// [`crate::algorithm::rand_parents`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SelectionMethod {
+    /// Weight of a rank decreases exponentially, by the selection decay
+    ExponentialRank,
+    /// The fittest of `size` individuals drawn uniformly, at least 1. Sizes above the population
+    /// size draw as many individuals as there are
+    Tournament { size: usize },
+    /// Weight of a rank decreases linearly, from the population size for the fittest individual
+    /// to 1 for the weakest one
+    RankLinear,
+}
+
+/// Way of mating a period of the mother with the same period of the father, see
+/// [`crate::algorithm::crossover`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CrossoverMethod {
+    /// Genes left of a mating point come from the mother, the rest from the father
+    SinglePoint,
+    /// Every gene position comes from either parent with an even chance, which mixes the parents
+    /// more than a single mating point
+    Uniform,
+    /// Every gene keeps the period one of the parents puts it in, see
+    /// [`crate::algorithm::partially_mapped_crossover`]. Nothing is lost or duplicated, so no
+    /// gene is moved at random by the repair, but the period sizes drift as nothing keeps them
+    PartiallyMapped,
+}
+
+/// Way of choosing the gene a mutation moves to another period, see
+/// [`crate::algorithm::mutate`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum MutationMethod {
+    /// Any gene of the period
+    Move,
+    /// A gene in conflict with another gene of the period, any gene when there are no conflicts
+    ConflictDirected,
+    /// Any gene of the period, exchanged for any gene of the other period, so the period sizes
+    /// don't change. Nothing is exchanged with an empty period
+    Swap,
+}
+
+/// Schedule lowering a value from its starting point over the generations
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CoolingSchedule {
+    /// Don't change the value
+    Constant,
+    /// Decrease the value linearly
+    Linear,
+    /// Decrease the value exponentially
+    Exponential,
+}
+
+/// Whether the file has a `.yaml` or `.yml` extension
+pub fn is_yaml(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| {
+        extension.eq_ignore_ascii_case("yaml") || extension.eq_ignore_ascii_case("yml")
+    })
 }
 
 impl AlgorithmConfig {
     /// Load the configuration from a JSON file
     pub fn from_json(path: impl AsRef<Path>) -> Result<AlgorithmConfig, ConfigLoadError> {
         let mut file = File::open(path)?;
-        let config = serde_json::from_reader(&mut file)?;
+        let config: AlgorithmConfig = serde_json::from_reader(&mut file)?;
+
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Load the configuration from a YAML file, with the same fields as the JSON one
+    pub fn from_yaml(path: impl AsRef<Path>) -> Result<AlgorithmConfig, ConfigLoadError> {
+        let file = File::open(path)?;
+        let config: AlgorithmConfig = serde_yaml::from_reader(file)?;
+
+        config.validate()?;
+
         Ok(config)
     }
+
+    /// Load the configuration as YAML for files with a `.yaml` or `.yml` extension and as JSON
+    /// otherwise
+    pub fn from_file(path: impl AsRef<Path>) -> Result<AlgorithmConfig, ConfigLoadError> {
+        if is_yaml(path.as_ref()) {
+            AlgorithmConfig::from_yaml(path)
+        } else {
+            AlgorithmConfig::from_json(path)
+        }
+    }
+
+    /// Builder starting from the default configuration, see [`AlgorithmConfigBuilder`]
+    pub fn builder() -> AlgorithmConfigBuilder {
+        AlgorithmConfigBuilder::default()
+    }
+
+    /// Check the values serde can't, see [`ConfigLoadError`]
+    pub fn validate(&self) -> Result<(), ConfigLoadError> {
+        // annealing interpolates between its endpoints, so checking them covers every generation
+        let decays = [
+            ("selection_decay", Some(self.selection_decay)),
+            (
+                "selection_annealing.start_decay",
+                self.selection_annealing
+                    .map(|annealing| annealing.start_decay),
+            ),
+            (
+                "selection_annealing.end_decay",
+                self.selection_annealing
+                    .map(|annealing| annealing.end_decay),
+            ),
+        ];
+        for (name, decay) in decays {
+            let Some(decay) = decay else {
+                continue;
+            };
+            if decay <= 0.0 || decay.is_nan() {
+                return Err(ConfigLoadError::NonPositiveSelectionDecay { name, decay });
+            }
+            if self.selection_method == SelectionMethod::ExponentialRank {
+                check_selectable(decay, self.selection_offset)?;
+            }
+        }
+
+        let probabilities = [
+            ("mutation_probability", self.mutation_probability),
+            ("crossover_probability", self.crossover_probability),
+            (
+                "adaptive_mutation.max_probability",
+                self.adaptive_mutation
+                    .map_or(0.0, |adaptive| adaptive.max_probability),
+            ),
+        ];
+        if let Some((name, value)) = probabilities
+            .into_iter()
+            .find(|(_, value)| !(0.0..=1.0).contains(value))
+        {
+            return Err(ConfigLoadError::ProbabilityOutOfRange { name, value });
+        }
+        if !(self.crossover_fitness_bias >= 0.0 && self.crossover_fitness_bias.is_finite()) {
+            return Err(ConfigLoadError::InvalidCrossoverBias(
+                self.crossover_fitness_bias,
+            ));
+        }
+        if !(self.generation_gap > 0.0 && self.generation_gap <= 1.0) {
+            return Err(ConfigLoadError::GenerationGapOutOfRange(
+                self.generation_gap,
+            ));
+        }
+
+        let sizes = [
+            ("population_size", self.population_size),
+            ("number_of_periods", self.number_of_periods),
+            ("max_generations", self.max_generations),
+        ];
+        if let Some((name, _)) = sizes.into_iter().find(|(_, size)| *size == 0) {
+            return Err(ConfigLoadError::ZeroSize(name));
+        }
+        if self.population_size < 2 {
+            return Err(ConfigLoadError::PopulationTooSmall(self.population_size));
+        }
+        if self.selection_method == (SelectionMethod::Tournament { size: 0 }) {
+            return Err(ConfigLoadError::ZeroSize("selection_method.size"));
+        }
+
+        self.fitness_weights.validate()
+    }
+
+    /// Check that the population can be split across `nodes` nodes. With `island_mode` or
+    /// `gather_best_k` every node evolves its part alone, which needs two parents
+    pub fn validate_for_nodes(&self, nodes: usize) -> Result<(), ConfigLoadError> {
+        let islands = self.island_mode || self.gather_best_k.is_some();
+        if islands && self.population_size < 2 * nodes {
+            return Err(ConfigLoadError::IslandsTooSmall {
+                population_size: self.population_size,
+                nodes,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Fail when the exponential selection weight of rank 0 or 1 underflows to zero (or isn't a
+/// number), which leaves no two parents to choose
+fn check_selectable(decay: f64, offset: f64) -> Result<(), ConfigLoadError> {
+    for rank in 0..2 {
+        let weight = selection_weight(decay, offset, rank);
+        if !(weight > 0.0 && weight.is_finite()) {
+            return Err(ConfigLoadError::UnselectableParents {
+                rank,
+                weight,
+                decay,
+                offset,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Setter of every field, optional fields take the value itself
+macro_rules! setters {
+    (
+        required { $($field:ident: $type:ty,)* }
+        optional { $($optional:ident: $optional_type:ty,)* }
+    ) => {
+        $(
+            pub fn $field(mut self, $field: $type) -> Self {
+                self.config.$field = $field;
+                self
+            }
+        )*
+        $(
+            pub fn $optional(mut self, $optional: $optional_type) -> Self {
+                self.config.$optional = Some($optional);
+                self
+            }
+        )*
+    };
+}
+
+/// Chainable way of creating an [`AlgorithmConfig`] in code
+///
+/// Fields that aren't set keep their [`Default`] values, e.g.
+/// `AlgorithmConfig::builder().population_size(100).max_generations(50).build()`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AlgorithmConfigBuilder {
+    config: AlgorithmConfig,
+}
+
+impl AlgorithmConfigBuilder {
+    setters! {
+        required {
+            max_generations: usize,
+            population_size: usize,
+            number_of_periods: usize,
+            mutation_probability: f32,
+            crossover_probability: f32,
+            crossover_fitness_bias: f32,
+            subject_spacing_weight: Adaptation,
+            compaction_weight: Adaptation,
+            room_change_weight: Adaptation,
+            room_capacity_weight: Adaptation,
+            period_minutes: u32,
+            day_start_minutes: u32,
+            fitness_weights: FitnessWeights,
+            hard_constraints: HardConstraints,
+            crossover_method: CrossoverMethod,
+            mutation_method: MutationMethod,
+            max_mutation_step: usize,
+            mutation_cooling: CoolingSchedule,
+            max_dead_retries: usize,
+            generation_gap: f32,
+            max_repair_attempts: usize,
+            selection_method: SelectionMethod,
+            selection_decay: f64,
+            selection_offset: f64,
+            deterministic_chunking: bool,
+            diversity_threshold: f64,
+            immigrant_count: usize,
+            min_initial_distance: usize,
+            max_initial_attempts: usize,
+            stagnation_limit: usize,
+            checkpoint_interval: usize,
+            island_mode: bool,
+            migration_interval: usize,
+            migration_count: usize,
+            compress_mpi: bool,
+        }
+        optional {
+            periods_per_day: usize,
+            dead_threshold: Adaptation,
+            min_child_improvement: Adaptation,
+            period_capacity: usize,
+            selection_annealing: SelectionAnnealing,
+            adaptive_mutation: AdaptiveMutation,
+            random_seed: u64,
+            polish_generations: usize,
+            time_budget_secs: u64,
+            gather_best_k: usize,
+            required_solutions: RequiredSolutions,
+        }
+    }
+
+    /// The configuration, if it passes [`AlgorithmConfig::validate`]
+    pub fn build(self) -> Result<AlgorithmConfig, ConfigLoadError> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
 }
 
 impl Default for AlgorithmConfig {
@@ -50,6 +675,315 @@ impl Default for AlgorithmConfig {
             population_size: 10_000,
             number_of_periods: 8,
             mutation_probability: 0.05,
+            adaptive_mutation: None,
+            crossover_probability: 1.0,
+            crossover_fitness_bias: 0.0,
+            subject_spacing_weight: Adaptation::default(),
+            compaction_weight: Adaptation::default(),
+            room_change_weight: Adaptation::default(),
+            room_capacity_weight: Adaptation::default(),
+            periods_per_day: None,
+            period_minutes: 45,
+            day_start_minutes: 8 * 60,
+            fitness_weights: FitnessWeights::default(),
+            hard_constraints: HardConstraints::default(),
+            crossover_method: CrossoverMethod::SinglePoint,
+            mutation_method: MutationMethod::Move,
+            max_mutation_step: 1,
+            mutation_cooling: CoolingSchedule::Constant,
+            dead_threshold: None,
+            max_dead_retries: 3,
+            min_child_improvement: None,
+            generation_gap: 1.0,
+            period_capacity: None,
+            max_repair_attempts: 10,
+            selection_method: SelectionMethod::ExponentialRank,
+            selection_decay: DEFAULT_SELECTION_DECAY,
+            selection_offset: DEFAULT_SELECTION_OFFSET,
+            selection_annealing: None,
+            random_seed: None,
+            deterministic_chunking: false,
+            diversity_threshold: 0.0,
+            immigrant_count: 0,
+            min_initial_distance: 0,
+            max_initial_attempts: 10,
+            polish_generations: None,
+            stagnation_limit: 0,
+            time_budget_secs: None,
+            checkpoint_interval: 0,
+            gather_best_k: None,
+            island_mode: false,
+            migration_interval: 10,
+            migration_count: 2,
+            compress_mpi: false,
+            required_solutions: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::testing::TempFile;
+
+    #[test]
+    fn test_builder_fills_unset_fields_from_default() {
+        let config = AlgorithmConfig::builder()
+            .population_size(100)
+            .max_generations(50)
+            .random_seed(7)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            config,
+            AlgorithmConfig {
+                population_size: 100,
+                max_generations: 50,
+                random_seed: Some(7),
+                ..AlgorithmConfig::default()
+            }
+        );
+        assert_eq!(
+            AlgorithmConfig::builder().build().unwrap(),
+            AlgorithmConfig::default()
+        );
+    }
+
+    #[test]
+    fn test_yaml_config_matches_json() {
+        let json_path = TempFile::with_contents(
+            "config",
+            "json",
+            r#"{
+                "population_size": 120,
+                "mutation_method": "Swap",
+                "random_seed": 11,
+                "adaptive_mutation": {"max_probability": 0.5, "diversity_threshold": 3.0},
+                "fitness_weights": {"room_clash": 7}
+            }"#,
+        );
+        let yaml_path = TempFile::with_contents(
+            "config",
+            "yml",
+            "population_size: 120\n\
+             mutation_method: Swap\n\
+             random_seed: 11\n\
+             adaptive_mutation:\n  max_probability: 0.5\n  diversity_threshold: 3.0\n\
+             fitness_weights:\n  room_clash: 7\n",
+        );
+
+        let from_json = AlgorithmConfig::from_file(&json_path).unwrap();
+        let from_yaml = AlgorithmConfig::from_file(&yaml_path).unwrap();
+        assert_eq!(from_yaml, from_json);
+        assert_eq!(from_yaml.population_size, 120);
+
+        std::fs::write(&yaml_path, "selection_decay: -1.0\n").unwrap();
+        assert!(matches!(
+            AlgorithmConfig::from_yaml(&yaml_path),
+            Err(ConfigLoadError::NonPositiveSelectionDecay { .. })
+        ));
+        std::fs::write(&yaml_path, "population_size: [\n").unwrap();
+        assert!(matches!(
+            AlgorithmConfig::from_yaml(&yaml_path),
+            Err(ConfigLoadError::YamlError(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_rejects_each_invalid_field() {
+        let invalid = |config: AlgorithmConfig| config.validate().unwrap_err();
+        let default = AlgorithmConfig::default();
+
+        assert!(matches!(
+            invalid(AlgorithmConfig {
+                population_size: 1,
+                ..default
+            }),
+            ConfigLoadError::PopulationTooSmall(1)
+        ));
+        assert!(matches!(
+            invalid(AlgorithmConfig {
+                number_of_periods: 0,
+                ..default
+            }),
+            ConfigLoadError::ZeroSize("number_of_periods")
+        ));
+        assert!(matches!(
+            invalid(AlgorithmConfig {
+                max_generations: 0,
+                ..default
+            }),
+            ConfigLoadError::ZeroSize("max_generations")
+        ));
+        assert!(matches!(
+            invalid(AlgorithmConfig {
+                mutation_probability: f32::NAN,
+                ..default
+            }),
+            ConfigLoadError::ProbabilityOutOfRange {
+                name: "mutation_probability",
+                ..
+            }
+        ));
+        assert!(matches!(
+            invalid(AlgorithmConfig {
+                adaptive_mutation: Some(AdaptiveMutation {
+                    max_probability: 2.0,
+                    diversity_threshold: 1.0
+                }),
+                ..default
+            }),
+            ConfigLoadError::ProbabilityOutOfRange {
+                name: "adaptive_mutation.max_probability",
+                ..
+            }
+        ));
+        assert!(default.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_generation_gap_outside_unit_interval() {
+        for generation_gap in [0.0, -3.0, 7.0, f32::NAN] {
+            let config = AlgorithmConfig {
+                generation_gap,
+                ..AlgorithmConfig::default()
+            };
+            assert!(matches!(
+                config.validate(),
+                Err(ConfigLoadError::GenerationGapOutOfRange(_))
+            ));
+        }
+
+        for generation_gap in [0.1, 1.0] {
+            let config = AlgorithmConfig {
+                generation_gap,
+                ..AlgorithmConfig::default()
+            };
+            assert!(config.validate().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_invalid_crossover_bias() {
+        for crossover_fitness_bias in [-0.5, f32::NAN, f32::INFINITY] {
+            let config = AlgorithmConfig {
+                crossover_fitness_bias,
+                ..AlgorithmConfig::default()
+            };
+            assert!(matches!(
+                config.validate(),
+                Err(ConfigLoadError::InvalidCrossoverBias(_))
+            ));
+        }
+
+        let biased = AlgorithmConfig {
+            crossover_fitness_bias: 2.0,
+            ..AlgorithmConfig::default()
+        };
+        assert!(biased.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_unselectable_parents() {
+        let invalid = |config: AlgorithmConfig| config.validate().unwrap_err();
+        let default = AlgorithmConfig::default();
+
+        // e^(-800 + 2) underflows, only the fittest individual could be selected
+        assert!(matches!(
+            invalid(AlgorithmConfig {
+                selection_decay: 800.0,
+                ..default
+            }),
+            ConfigLoadError::UnselectableParents { rank: 1, .. }
+        ));
+        assert!(matches!(
+            invalid(AlgorithmConfig {
+                selection_offset: -800.0,
+                ..default
+            }),
+            ConfigLoadError::UnselectableParents { rank: 0, .. }
+        ));
+        assert!(matches!(
+            invalid(AlgorithmConfig {
+                selection_offset: f64::NAN,
+                ..default
+            }),
+            ConfigLoadError::UnselectableParents { rank: 0, .. }
+        ));
+        let annealing = |start_decay, end_decay| AlgorithmConfig {
+            selection_annealing: Some(SelectionAnnealing {
+                start_decay,
+                end_decay,
+            }),
+            ..default
+        };
+        assert!(matches!(
+            invalid(annealing(-0.1, 1.0)),
+            ConfigLoadError::NonPositiveSelectionDecay {
+                name: "selection_annealing.start_decay",
+                ..
+            }
+        ));
+        // a steep end of the run is as bad as a steep start
+        assert!(matches!(
+            invalid(annealing(0.1, 800.0)),
+            ConfigLoadError::UnselectableParents { rank: 1, .. }
+        ));
+        assert!(annealing(0.1, 1.0).validate().is_ok());
+
+        assert!(matches!(
+            invalid(AlgorithmConfig {
+                selection_method: SelectionMethod::Tournament { size: 0 },
+                ..default
+            }),
+            ConfigLoadError::ZeroSize("selection_method.size")
+        ));
+
+        // weights aren't used by the other selection methods
+        assert!(AlgorithmConfig {
+            selection_decay: 800.0,
+            selection_method: SelectionMethod::RankLinear,
+            ..default
         }
+        .validate()
+        .is_ok());
+    }
+
+    #[test]
+    fn test_builder_validates() {
+        assert!(matches!(
+            AlgorithmConfig::builder().mutation_probability(1.5).build(),
+            Err(ConfigLoadError::ProbabilityOutOfRange {
+                name: "mutation_probability",
+                ..
+            })
+        ));
+        assert!(matches!(
+            AlgorithmConfig::builder()
+                .crossover_probability(-0.1)
+                .build(),
+            Err(ConfigLoadError::ProbabilityOutOfRange {
+                name: "crossover_probability",
+                ..
+            })
+        ));
+        assert!(matches!(
+            AlgorithmConfig::builder().population_size(0).build(),
+            Err(ConfigLoadError::ZeroSize("population_size"))
+        ));
+        assert!(matches!(
+            AlgorithmConfig::builder().number_of_periods(0).build(),
+            Err(ConfigLoadError::ZeroSize("number_of_periods"))
+        ));
+        assert!(matches!(
+            AlgorithmConfig::builder().selection_decay(0.0).build(),
+            Err(ConfigLoadError::NonPositiveSelectionDecay { .. })
+        ));
+        assert!(AlgorithmConfig::builder()
+            .mutation_probability(1.0)
+            .crossover_probability(0.0)
+            .build()
+            .is_ok());
     }
 }
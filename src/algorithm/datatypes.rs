@@ -1,15 +1,72 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::{fs::File, path::Path};
 
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use super::config::{AlgorithmConfig, FitnessWeights, HardConstraints};
+
 #[derive(Debug, Error)]
 pub enum TuplesLoadError {
     #[error("Configuration file not found")]
     Io(#[from] std::io::Error),
     #[error(transparent)]
     Csv(#[from] csv::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("Line {line} has an invalid id: {value:?}")]
+    InvalidId { line: u64, value: String },
+    #[error("Line {line} has {columns} columns, but Id, Label, Room and Teacher are required")]
+    ShortRow { line: u64, columns: usize },
+    #[error("Tuple {id} has an invalid {column}: {value:?}")]
+    InvalidNumber {
+        id: i32,
+        column: &'static str,
+        value: String,
+    },
+    #[error("Tuple id {id} is used more than once")]
+    DuplicateId { id: i32 },
+}
+
+#[derive(Debug, Error)]
+pub enum LocksLoadError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("Tuple {id} is pinned, but there is no such tuple")]
+    UnknownTuple { id: i32 },
+    #[error(
+        "Tuple {id} is pinned to period {period}, but there are only {number_of_periods} periods"
+    )]
+    PeriodOutOfRange {
+        id: i32,
+        period: usize,
+        number_of_periods: usize,
+    },
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum IndividualImportError {
+    #[error("Individual has {found} periods, but the configuration expects {expected}")]
+    PeriodCountMismatch { expected: usize, found: usize },
+}
+
+/// Difference between the genes of an individual and the tuples, see
+/// [`crate::algorithm::validate_completeness`]
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CompletenessError {
+    #[error("Tuple {0} is not scheduled")]
+    Missing(i32),
+    #[error("Tuple {id} is scheduled {count} times")]
+    Duplicated { id: i32, count: usize },
+    #[error("Gene {0} doesn't match any tuple")]
+    Unknown(Gene),
 }
 
 /// Tuple
@@ -32,6 +89,17 @@ pub struct Tuple {
     pub label: String,
     pub room: String,
     pub teacher: String,
+    /// Number of seats in the room, `0` when unknown. Tuples in the same room are expected to
+    /// agree on it
+    #[serde(default)]
+    pub capacity: u32,
+    /// Number of students attending the class, `0` when unknown
+    #[serde(default)]
+    pub size: u32,
+    /// Student group attending the class, which can't be in two classes at once. Empty when the
+    /// class isn't checked for group clashes
+    #[serde(default)]
+    pub group: String,
 }
 
 impl Display for Tuple {
@@ -45,36 +113,429 @@ impl Display for Tuple {
 }
 
 impl Tuple {
+    /// Load the tuples from a CSV file with `Id,Label,Room,Teacher` columns, optionally followed
+    /// by `Group`, `Capacity` and `Size` columns in any order, found by their header
+    ///
+    /// Missing or empty optional values leave the class ungrouped and its capacity and size `0`,
+    /// which disables the checks that need them.
+    ///
+    /// Rows without the four required columns or with an id that isn't an `i32` are rejected with
+    /// their line number.
     pub fn from_csv(path: impl AsRef<Path>) -> Result<Vec<Tuple>, TuplesLoadError> {
+        Tuple::from_csv_with(path, CsvOptions::default())
+    }
+
+    /// [`Tuple::from_csv`] with another delimiter or without the header row
+    ///
+    /// Without a header the optional columns can't be found by name, so they have to follow the
+    /// required ones as `Group`, `Capacity` and `Size`.
+    pub fn from_csv_with(
+        path: impl AsRef<Path>,
+        options: CsvOptions,
+    ) -> Result<Vec<Tuple>, TuplesLoadError> {
         let file = File::open(path)?;
-        let mut reader = csv::Reader::from_reader(file);
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .delimiter(options.delimiter)
+            .has_headers(options.has_headers)
+            .from_reader(file);
+
+        let (group_column, capacity_column, size_column) = if options.has_headers {
+            let headers = reader.headers()?.clone();
+            let column = |name: &str| {
+                headers
+                    .iter()
+                    .skip(4)
+                    .position(|header| header.trim().eq_ignore_ascii_case(name))
+                    .map(|position| position + 4)
+            };
+            (column("group"), column("capacity"), column("size"))
+        } else {
+            (Some(4), Some(5), Some(6))
+        };
 
         let mut tuples = Vec::new();
 
         for result in reader.records() {
             let record = result?;
+            let line = record.position().map_or(0, |position| position.line());
+            if record.len() < 4 {
+                return Err(TuplesLoadError::ShortRow {
+                    line,
+                    columns: record.len(),
+                });
+            }
+            let id = record[0]
+                .trim()
+                .parse()
+                .map_err(|_| TuplesLoadError::InvalidId {
+                    line,
+                    value: record[0].to_string(),
+                })?;
+            let optional = |index: Option<usize>| {
+                index
+                    .and_then(|index| record.get(index))
+                    .map(str::trim)
+                    .unwrap_or_default()
+            };
+            let count = |index: Option<usize>, column: &'static str| match optional(index) {
+                "" => Ok(0),
+                value => value.parse().map_err(|_| TuplesLoadError::InvalidNumber {
+                    id,
+                    column,
+                    value: value.to_string(),
+                }),
+            };
+
             let tuple = Tuple {
-                id: record[0].parse().unwrap(),
+                id,
                 label: record[1].to_string(),
                 room: record[2].to_string(),
                 teacher: record[3].to_string(),
+                capacity: count(capacity_column, "capacity")?,
+                size: count(size_column, "size")?,
+                group: optional(group_column).to_string(),
             };
             tuples.push(tuple);
         }
 
+        validate_tuples(&tuples)?;
         Ok(tuples)
     }
+
+    /// Load the tuples from a JSON array of objects with the fields of [`Tuple`], the optional
+    /// ones may be left out
+    pub fn from_json(path: impl AsRef<Path>) -> Result<Vec<Tuple>, TuplesLoadError> {
+        let file = File::open(path)?;
+        let tuples: Vec<Tuple> = serde_json::from_reader(std::io::BufReader::new(file))?;
+        validate_tuples(&tuples)?;
+        Ok(tuples)
+    }
+
+    /// Load the tuples in the given format, see [`TuplesFormat::from_path`] to infer it. The
+    /// `csv_options` only apply to CSV files
+    pub fn load(
+        path: impl AsRef<Path>,
+        format: TuplesFormat,
+        csv_options: CsvOptions,
+    ) -> Result<Vec<Tuple>, TuplesLoadError> {
+        match format {
+            TuplesFormat::Csv => Tuple::from_csv_with(path, csv_options),
+            TuplesFormat::Json => Tuple::from_json(path),
+        }
+    }
+}
+
+/// Check that every tuple has its own id, which the genes refer to
+///
+/// Gaps between the ids are allowed, see [`crate::algorithm::lint::LintWarning::IdGap`].
+pub fn validate_tuples(tuples: &[Tuple]) -> Result<(), TuplesLoadError> {
+    let mut ids = HashSet::with_capacity(tuples.len());
+    match tuples.iter().find(|tuple| !ids.insert(tuple.id)) {
+        Some(tuple) => Err(TuplesLoadError::DuplicateId { id: tuple.id }),
+        None => Ok(()),
+    }
+}
+
+/// Layout of a tuples CSV file, comma separated with a header row by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvOptions {
+    pub delimiter: u8,
+    pub has_headers: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: b',',
+            has_headers: true,
+        }
+    }
+}
+
+/// Format of a tuples file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TuplesFormat {
+    Csv,
+    Json,
+}
+
+impl TuplesFormat {
+    /// JSON for files with a `.json` extension, CSV otherwise
+    pub fn from_path(path: impl AsRef<Path>) -> TuplesFormat {
+        match path.as_ref().extension() {
+            Some(extension) if extension.eq_ignore_ascii_case("json") => TuplesFormat::Json,
+            _ => TuplesFormat::Csv,
+        }
+    }
+}
+
+/// Tuples with a lookup by id, built once and shared by the whole run
+///
+/// Dereferences to the tuples in their original order, so it can be passed wherever a slice of
+/// tuples is expected. Indexing by a gene panics when no tuple has that id, like indexing a
+/// [`HashMap`].
+#[derive(Debug, Clone, Default)]
+pub struct TupleIndex {
+    tuples: Vec<Tuple>,
+    positions: HashMap<i32, usize>,
+}
+
+impl TupleIndex {
+    pub fn from_tuples(tuples: &[Tuple]) -> TupleIndex {
+        TupleIndex::from(tuples.to_vec())
+    }
+
+    pub fn get(&self, id: i32) -> Option<&Tuple> {
+        self.positions
+            .get(&id)
+            .map(|position| &self.tuples[*position])
+    }
+}
+
+impl From<Vec<Tuple>> for TupleIndex {
+    fn from(tuples: Vec<Tuple>) -> Self {
+        let positions = tuples
+            .iter()
+            .enumerate()
+            .map(|(position, tuple)| (tuple.id, position))
+            .collect();
+
+        TupleIndex { tuples, positions }
+    }
+}
+
+impl FromIterator<Tuple> for TupleIndex {
+    fn from_iter<I: IntoIterator<Item = Tuple>>(tuples: I) -> Self {
+        TupleIndex::from(tuples.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl<'a> IntoIterator for &'a TupleIndex {
+    type Item = &'a Tuple;
+    type IntoIter = std::slice::Iter<'a, Tuple>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.tuples.iter()
+    }
+}
+
+impl std::ops::Deref for TupleIndex {
+    type Target = [Tuple];
+
+    fn deref(&self) -> &[Tuple] {
+        &self.tuples
+    }
+}
+
+impl std::ops::Index<&Gene> for TupleIndex {
+    type Output = Tuple;
+
+    fn index(&self, id: &Gene) -> &Tuple {
+        self.get(*id).expect("no tuple with the id of the gene")
+    }
+}
+
+/// Rooms some teachers are restricted to, like a lab or a music room with instruments
+///
+/// Teachers without an entry may use any room.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RoomRestrictions {
+    allowed_rooms: HashMap<String, HashSet<String>>,
+}
+
+impl RoomRestrictions {
+    /// Load the restrictions from a CSV file with a `Teacher,Room` row for every allowed room
+    pub fn from_csv(path: impl AsRef<Path>) -> Result<RoomRestrictions, TuplesLoadError> {
+        let file = File::open(path)?;
+        let mut reader = csv::Reader::from_reader(file);
+
+        let mut restrictions = RoomRestrictions::default();
+
+        for result in reader.records() {
+            let record = result?;
+            restrictions.allow(&record[0], &record[1]);
+        }
+
+        Ok(restrictions)
+    }
+
+    /// Add `room` to the rooms `teacher` is restricted to
+    pub fn allow(&mut self, teacher: &str, room: &str) {
+        self.allowed_rooms
+            .entry(teacher.to_string())
+            .or_default()
+            .insert(room.to_string());
+    }
+
+    pub fn allows(&self, teacher: &str, room: &str) -> bool {
+        self.allowed_rooms
+            .get(teacher)
+            .is_none_or(|rooms| rooms.contains(room))
+    }
+
+    /// Tuples placing their teacher in a room the teacher isn't allowed to use
+    ///
+    /// Rooms are fixed per tuple, so these can't be fixed by the algorithm and are rejected when
+    /// validating the input.
+    pub fn violations<'a>(&self, tuples: &'a [Tuple]) -> Vec<&'a Tuple> {
+        tuples
+            .iter()
+            .filter(|tuple| !self.allows(&tuple.teacher, &tuple.room))
+            .collect()
+    }
 }
 
 /// Gene is [`Tuple::id`]. Used internally to minimize the size of the data being sent/copied. For example,
 /// crossover can operate only on the ids of the tuples.
 pub type Gene = i32;
 
+/// Adaptation (fitness) of an individual. It is a penalty, `0` is a timetable without conflicts
+///
+/// An integer unless the `float-fitness` feature is enabled, then it is an `f64` so objectives can
+/// have fractional weights without rounding.
+#[cfg(not(feature = "float-fitness"))]
+pub type Adaptation = i32;
+#[cfg(feature = "float-fitness")]
+pub type Adaptation = f64;
+
+/// Total order of adaptations, NaN safe when the adaptation is a float
+#[cfg(not(feature = "float-fitness"))]
+pub fn compare_adaptation(first: &Adaptation, second: &Adaptation) -> Ordering {
+    first.cmp(second)
+}
+
+/// Total order of adaptations, NaN safe when the adaptation is a float
+///
+/// NaN is ordered below every other adaptation, so a broken individual is never the fittest.
+#[cfg(feature = "float-fitness")]
+pub fn compare_adaptation(first: &Adaptation, second: &Adaptation) -> Ordering {
+    match (first.is_nan(), second.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => first.total_cmp(second),
+    }
+}
+
+/// Adaptation converted to `f64` for calculations that need a float in both representations
+#[allow(clippy::unnecessary_cast)]
+pub fn adaptation_to_f64(adaptation: Adaptation) -> f64 {
+    adaptation as f64
+}
+
+/// Sort the population from the fittest individual, comparing their [`Individual::score`]
+pub fn sort_by_adaptation(population: &mut [Individual]) {
+    population.sort_by(|a, b| compare_score(&b.score(), &a.score()));
+}
+
+/// Fitness split into the penalties of hard and soft constraints, see
+/// [`crate::algorithm::calculate_score`] and [`crate::algorithm::config::HardConstraints`]
+///
+/// Scores compare the hard penalties first and the soft ones only between equally hard
+/// timetables, so a single hard violation ranks below any number of soft ones.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Score {
+    pub hard: Adaptation,
+    pub soft: Adaptation,
+}
+
+impl Score {
+    /// The single adaptation the score adds up to
+    pub fn total(&self) -> Adaptation {
+        self.hard + self.soft
+    }
+
+    /// How much better this score is than `other` in the first part where they differ, hard
+    /// penalties first. Negative when this score is worse
+    pub fn improvement_over(&self, other: &Score) -> Adaptation {
+        if compare_adaptation(&self.hard, &other.hard).is_ne() {
+            self.hard - other.hard
+        } else {
+            self.soft - other.soft
+        }
+    }
+}
+
+/// Lexicographic order of scores, hard penalties first, see [`Score`]
+pub fn compare_score(first: &Score, second: &Score) -> Ordering {
+    compare_adaptation(&first.hard, &second.hard)
+        .then_with(|| compare_adaptation(&first.soft, &second.soft))
+}
+
+/// Genes that have to stay in a fixed period, mapped to the index of that period
+pub type Locks = HashMap<Gene, usize>;
+
+/// Load pinned tuples from a JSON object mapping tuple ids to the index of their period, e.g.
+/// `{"12": 3}` keeps tuple 12 in the fourth period
+pub fn load_locks(path: impl AsRef<Path>) -> Result<Locks, LocksLoadError> {
+    let file = File::open(path)?;
+    Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+}
+
+/// Check that every pinned tuple exists and is pinned to one of the `number_of_periods` periods
+pub fn validate_locks(
+    locks: &Locks,
+    tuples: &[Tuple],
+    number_of_periods: usize,
+) -> Result<(), LocksLoadError> {
+    let ids: HashSet<i32> = tuples.iter().map(|tuple| tuple.id).collect();
+
+    for (id, period) in locks.iter().sorted() {
+        if !ids.contains(id) {
+            return Err(LocksLoadError::UnknownTuple { id: *id });
+        }
+        if *period >= number_of_periods {
+            return Err(LocksLoadError::PeriodOutOfRange {
+                id: *id,
+                period: *period,
+                number_of_periods,
+            });
+        }
+    }
+
+    Ok(())
+}
+
 /// Individual is a timetable. It has adaptation value and a list of chromosomes = periods.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Individual {
-    pub adaptation: i32,
+    pub adaptation: Adaptation,
+    /// Part of `adaptation` from hard constraints, individuals rank by it first, see
+    /// [`Individual::score`]
+    #[serde(default)]
+    pub hard_adaptation: Adaptation,
     pub chromosomes: Vec<Chromosome>,
+    /// Cached fitness of every period, see [`crate::algorithm::calculate_fitness_cached`]
+    ///
+    /// Not serialized, so an individual takes the same number of bytes over MPI and in
+    /// checkpoints whether it was evaluated or not. A received individual starts with every period
+    /// dirty.
+    #[serde(skip)]
+    pub period_fitness: Vec<CachedPeriodFitness>,
+}
+
+/// Fitness of a single period remembered between evaluations
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CachedPeriodFitness {
+    /// [`Chromosome::genes_hash`] of the period when the fitness was calculated
+    pub genes_hash: u64,
+    pub fitness: Adaptation,
+    /// Part of `fitness` from hard constraints
+    pub hard: Adaptation,
+    /// Set when the period changed, the fitness has to be recalculated
+    pub dirty: bool,
+}
+
+impl Default for CachedPeriodFitness {
+    fn default() -> Self {
+        CachedPeriodFitness {
+            genes_hash: 0,
+            fitness: Adaptation::default(),
+            hard: Adaptation::default(),
+            dirty: true,
+        }
+    }
 }
 
 impl Individual {
@@ -91,20 +552,217 @@ impl Individual {
             ..Self::default()
         }
     }
+
+    /// [`Individual::adaptation`] split into its hard and soft parts, as evaluated by
+    /// [`crate::algorithm::calculate_fitness_cached`]
+    pub fn score(&self) -> Score {
+        Score {
+            hard: self.hard_adaptation,
+            soft: self.adaptation - self.hard_adaptation,
+        }
+    }
+
+    /// Exchange the gene at `first_index` of the period at `first` with the gene at
+    /// `second_index` of the period at `second`, marking the cached fitness of both periods dirty
+    pub fn swap_genes(
+        &mut self,
+        (first, first_index): (usize, usize),
+        (second, second_index): (usize, usize),
+    ) {
+        let first_gene = self.chromosomes[first].genes[first_index];
+        self.chromosomes[first].genes[first_index] = self.chromosomes[second].genes[second_index];
+        self.chromosomes[second].genes[second_index] = first_gene;
+
+        for index in [first, second] {
+            if let Some(cached) = self.period_fitness.get_mut(index) {
+                cached.dirty = true;
+            }
+        }
+    }
+
+    /// Move the gene at `gene_index` of the period at `from` to the period at `to`, marking the
+    /// cached fitness of both periods dirty
+    pub fn move_gene(&mut self, from: usize, gene_index: usize, to: usize) {
+        let gene = self.chromosomes[from].genes.remove(gene_index);
+        self.chromosomes[to].genes.push(gene);
+
+        for index in [from, to] {
+            if let Some(cached) = self.period_fitness.get_mut(index) {
+                cached.dirty = true;
+            }
+        }
+    }
+
+    /// Whether every locked gene is placed in the period it is locked to
+    pub fn respects_locks(&self, locks: &Locks) -> bool {
+        locks.iter().all(|(gene, period_index)| {
+            self.chromosomes
+                .get(*period_index)
+                .is_some_and(|period| period.genes.contains(gene))
+        })
+    }
+
+    /// Check that an individual coming from outside of the algorithm (a file, a checkpoint) has
+    /// the number of periods the algorithm is configured for
+    pub fn validate_period_count(
+        &self,
+        number_of_periods: usize,
+    ) -> Result<(), IndividualImportError> {
+        if self.chromosomes.len() != number_of_periods {
+            return Err(IndividualImportError::PeriodCountMismatch {
+                expected: number_of_periods,
+                found: self.chromosomes.len(),
+            });
+        }
+
+        Ok(())
+    }
 }
 
 impl Default for Individual {
     fn default() -> Self {
         Individual {
-            adaptation: -1000,
+            adaptation: -1000 as Adaptation,
+            hard_adaptation: Adaptation::default(),
             chromosomes: Vec::new(),
+            period_fitness: Vec::new(),
         }
     }
 }
 
+/// Read access to the genes of every period, independent of how an individual stores them
+pub trait Timetable {
+    /// Genes of every period, in the order of the periods
+    fn period_genes(&self) -> impl Iterator<Item = &[Gene]>;
+}
+
+impl Timetable for Individual {
+    fn period_genes(&self) -> impl Iterator<Item = &[Gene]> {
+        self.chromosomes
+            .iter()
+            .map(|period| period.genes.as_slice())
+    }
+}
+
 /// Population is a list of timetables = individuals.
 pub type Population = Vec<Individual>;
 
+/// Outcome of a whole run of the algorithm
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunResult {
+    /// The fittest individual found
+    pub best: Individual,
+    /// How many generations were run
+    pub generations: usize,
+    /// Best adaptation of the initial, randomly generated population
+    pub baseline_adaptation: Adaptation,
+    /// How many times the fitness was calculated, by all nodes together
+    pub evaluations: u64,
+    /// Wall time of the run, from the first population to the last generation
+    pub elapsed: Duration,
+}
+
+impl RunResult {
+    /// Improvement of the best individual over the random baseline, in percent
+    ///
+    /// Adaptation is a penalty (the closer to 0 the better), so the result is the fraction of the
+    /// baseline penalty that was removed. A baseline without any penalty can't be improved and
+    /// reports 0%.
+    pub fn improvement_over_baseline(&self) -> f64 {
+        if self.baseline_adaptation == Adaptation::default() {
+            return 0.0;
+        }
+
+        adaptation_to_f64(self.best.adaptation - self.baseline_adaptation)
+            / adaptation_to_f64(self.baseline_adaptation).abs()
+            * 100.0
+    }
+
+    /// Individuals evaluated per second of wall time
+    pub fn evaluations_per_second(&self) -> f64 {
+        self.evaluations as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// Generations run per second of wall time
+    pub fn generations_per_second(&self) -> f64 {
+        self.generations as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+/// Values of the parameters that change over the run, as used in one generation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EffectiveParameters {
+    /// Probability of a period mutating
+    pub mutation_probability: f32,
+    /// Number of genes moved out of a mutating period
+    pub mutation_step: usize,
+    /// Decay of the parent selection weights
+    pub selection_decay: f64,
+    /// Fraction of the population replaced by children
+    pub generation_gap: f32,
+}
+
+impl Display for EffectiveParameters {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "mutation probability {:.3}, mutation step {}, selection decay {:.3}, generation gap {:.3}",
+            self.mutation_probability, self.mutation_step, self.selection_decay, self.generation_gap
+        )
+    }
+}
+
+/// Provenance of a run, written next to its results so they can be tied to the exact inputs
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RunMetadata {
+    /// Version of the planner that produced the results
+    pub version: String,
+    /// Configuration in effect, after adapting it to the number of nodes
+    pub config: AlgorithmConfig,
+    pub random_seed: Option<u64>,
+    /// Number of MPI processes
+    pub number_of_nodes: usize,
+    /// FNV-1a hash of the contents of the tuples file
+    pub tuples_hash: u64,
+    /// Seconds since the Unix epoch when the run finished
+    pub timestamp: u64,
+}
+
+impl RunMetadata {
+    pub fn new(
+        config: &AlgorithmConfig,
+        number_of_nodes: usize,
+        tuples_file: &[u8],
+        finished_at: SystemTime,
+    ) -> Self {
+        RunMetadata {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            config: *config,
+            random_seed: config.random_seed,
+            number_of_nodes,
+            tuples_hash: fnv1a_hash(tuples_file),
+            timestamp: finished_at
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Write the metadata as pretty printed JSON
+    pub fn write_json(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+/// 64-bit FNV-1a hash, stable across platforms and Rust versions unlike [`DefaultHasher`]
+fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
 /// Chromosome is a period. Period contains a start and end time, but for algorithm purposes,
 /// we are only interested in their number.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -120,16 +778,301 @@ impl Chromosome {
             genes: Vec::new(),
         }
     }
+
+    /// Hash of the set of genes, independent of their order
+    pub fn genes_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        let mut genes = self.genes.clone();
+        genes.sort_unstable();
+        genes.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Category of a conflict between two tuples placed in the same period
+///
+/// Shared by everything that needs to name a conflict, so fitness calculation and reporting
+/// always agree on the categories and their penalties.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ConflictType {
+    /// The same teacher has more than one class in the same room
+    TeacherMultiClass,
+    /// Different teachers occupy the same room
+    RoomClash,
+    /// The same teacher teaches the same subject more than once
+    SameTeacherSameSubject,
+    /// The same teacher teaches different subjects
+    SameTeacherDifferentSubject,
+    /// The same student group ([`Tuple::group`]) attends more than one class
+    GroupClash,
+}
+
+impl ConflictType {
+    /// Default penalty subtracted from the fitness for every occurrence of the conflict, see
+    /// [`crate::algorithm::config::FitnessWeights`]
+    pub fn penalty(&self) -> Adaptation {
+        let penalty = match self {
+            ConflictType::TeacherMultiClass => 10,
+            ConflictType::RoomClash => 20,
+            ConflictType::SameTeacherSameSubject => 10,
+            ConflictType::SameTeacherDifferentSubject => 20,
+            ConflictType::GroupClash => 20,
+        };
+        penalty as Adaptation
+    }
+
+    /// Hard conflicts make a timetable impossible to follow (someone has to be in two places at
+    /// once). All current categories are hard by default, soft objectives aren't conflicts between
+    /// tuples. The config can make them soft, see [`crate::algorithm::config::HardConstraints`]
+    pub fn is_hard(&self) -> bool {
+        match self {
+            ConflictType::TeacherMultiClass
+            | ConflictType::RoomClash
+            | ConflictType::SameTeacherSameSubject
+            | ConflictType::SameTeacherDifferentSubject
+            | ConflictType::GroupClash => true,
+        }
+    }
+}
+
+/// Fitness split by the kind of penalty, see [`crate::algorithm::calculate_fitness_detailed`]
+///
+/// Every field is the (non-positive) contribution to the fitness, so they add up to
+/// [`FitnessBreakdown::total`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct FitnessBreakdown {
+    /// [`ConflictType::TeacherMultiClass`]
+    pub teacher_multi_class: Adaptation,
+    /// [`ConflictType::RoomClash`]
+    pub room_clash: Adaptation,
+    /// [`ConflictType::SameTeacherSameSubject`]
+    pub same_teacher_same_subject: Adaptation,
+    /// [`ConflictType::SameTeacherDifferentSubject`]
+    pub same_teacher_different_subject: Adaptation,
+    /// [`ConflictType::GroupClash`]
+    pub group_clash: Adaptation,
+    /// All soft objectives enabled in the config together
+    pub soft_objectives: Adaptation,
+}
+
+impl FitnessBreakdown {
+    pub fn total(&self) -> Adaptation {
+        self.teacher_multi_class
+            + self.room_clash
+            + self.same_teacher_same_subject
+            + self.same_teacher_different_subject
+            + self.group_clash
+            + self.soft_objectives
+    }
+
+    /// Split the total into the conflicts `hard_constraints` makes hard and the rest, the soft
+    /// objectives are always soft
+    pub fn score(&self, hard_constraints: &HardConstraints) -> Score {
+        let hard = [
+            (ConflictType::TeacherMultiClass, self.teacher_multi_class),
+            (ConflictType::RoomClash, self.room_clash),
+            (
+                ConflictType::SameTeacherSameSubject,
+                self.same_teacher_same_subject,
+            ),
+            (
+                ConflictType::SameTeacherDifferentSubject,
+                self.same_teacher_different_subject,
+            ),
+            (ConflictType::GroupClash, self.group_clash),
+        ]
+        .into_iter()
+        .filter(|(conflict_type, _)| hard_constraints.is_hard(*conflict_type))
+        .map(|(_, penalty)| penalty)
+        .sum();
+
+        Score {
+            hard,
+            soft: self.total() - hard,
+        }
+    }
+
+    /// Subtract `penalty` for each of `count` conflicts of the given type
+    pub fn add_conflicts(
+        &mut self,
+        conflict_type: ConflictType,
+        count: usize,
+        penalty: Adaptation,
+    ) {
+        let field = match conflict_type {
+            ConflictType::TeacherMultiClass => &mut self.teacher_multi_class,
+            ConflictType::RoomClash => &mut self.room_clash,
+            ConflictType::SameTeacherSameSubject => &mut self.same_teacher_same_subject,
+            ConflictType::SameTeacherDifferentSubject => &mut self.same_teacher_different_subject,
+            ConflictType::GroupClash => &mut self.group_clash,
+        };
+        *field -= count as Adaptation * penalty;
+    }
+}
+
+impl std::ops::AddAssign for FitnessBreakdown {
+    fn add_assign(&mut self, other: FitnessBreakdown) {
+        self.teacher_multi_class += other.teacher_multi_class;
+        self.room_clash += other.room_clash;
+        self.same_teacher_same_subject += other.same_teacher_same_subject;
+        self.same_teacher_different_subject += other.same_teacher_different_subject;
+        self.group_clash += other.group_clash;
+        self.soft_objectives += other.soft_objectives;
+    }
+}
+
+/// How often a soft objective is violated, see [`crate::algorithm::soft_objective_penalties`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SoftObjectivePenalty {
+    pub name: &'static str,
+    pub occurrences: usize,
+    /// Penalty subtracted from the fitness for every occurrence
+    pub weight: Adaptation,
+}
+
+/// A conflict between two tuples placed in the same period
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Conflict {
+    pub conflict_type: ConflictType,
+    /// [`Chromosome::id`] of the period
+    pub period: i32,
+    /// Ids of the conflicting tuples, the smaller one first
+    pub tuples: (i32, i32),
+}
+
+/// A [`Conflict`] with the resource both tuples compete for, see
+/// [`crate::algorithm::violations`]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Violation {
+    /// [`ConflictType::TeacherMultiClass`]
+    TeacherRepeatedInRoom {
+        period: i32,
+        teacher: String,
+        room: String,
+        tuples: (i32, i32),
+    },
+    /// [`ConflictType::RoomClash`]
+    RoomDoubleBooked {
+        period: i32,
+        room: String,
+        tuples: (i32, i32),
+    },
+    /// [`ConflictType::SameTeacherSameSubject`]
+    SubjectRepeated {
+        period: i32,
+        teacher: String,
+        label: String,
+        tuples: (i32, i32),
+    },
+    /// [`ConflictType::SameTeacherDifferentSubject`]
+    TeacherDoubleBooked {
+        period: i32,
+        teacher: String,
+        tuples: (i32, i32),
+    },
+    /// [`ConflictType::GroupClash`]
+    GroupDoubleBooked {
+        period: i32,
+        group: String,
+        tuples: (i32, i32),
+    },
+}
+
+impl Violation {
+    /// Name the resource of the conflict, `first` is the tuple with the smaller id
+    pub fn new(conflict: &Conflict, first: &Tuple) -> Violation {
+        let (period, tuples) = (conflict.period, conflict.tuples);
+        match conflict.conflict_type {
+            ConflictType::TeacherMultiClass => Violation::TeacherRepeatedInRoom {
+                period,
+                teacher: first.teacher.clone(),
+                room: first.room.clone(),
+                tuples,
+            },
+            ConflictType::RoomClash => Violation::RoomDoubleBooked {
+                period,
+                room: first.room.clone(),
+                tuples,
+            },
+            ConflictType::SameTeacherSameSubject => Violation::SubjectRepeated {
+                period,
+                teacher: first.teacher.clone(),
+                label: first.label.clone(),
+                tuples,
+            },
+            ConflictType::SameTeacherDifferentSubject => Violation::TeacherDoubleBooked {
+                period,
+                teacher: first.teacher.clone(),
+                tuples,
+            },
+            ConflictType::GroupClash => Violation::GroupDoubleBooked {
+                period,
+                group: first.group.clone(),
+                tuples,
+            },
+        }
+    }
+
+    pub fn conflict_type(&self) -> ConflictType {
+        match self {
+            Violation::TeacherRepeatedInRoom { .. } => ConflictType::TeacherMultiClass,
+            Violation::RoomDoubleBooked { .. } => ConflictType::RoomClash,
+            Violation::SubjectRepeated { .. } => ConflictType::SameTeacherSameSubject,
+            Violation::TeacherDoubleBooked { .. } => ConflictType::SameTeacherDifferentSubject,
+            Violation::GroupDoubleBooked { .. } => ConflictType::GroupClash,
+        }
+    }
+
+    /// [`Chromosome::id`] of the period
+    pub fn period(&self) -> i32 {
+        match self {
+            Violation::TeacherRepeatedInRoom { period, .. }
+            | Violation::RoomDoubleBooked { period, .. }
+            | Violation::SubjectRepeated { period, .. }
+            | Violation::TeacherDoubleBooked { period, .. }
+            | Violation::GroupDoubleBooked { period, .. } => *period,
+        }
+    }
+
+    /// Ids of the conflicting tuples, the smaller one first
+    pub fn tuples(&self) -> (i32, i32) {
+        match self {
+            Violation::TeacherRepeatedInRoom { tuples, .. }
+            | Violation::RoomDoubleBooked { tuples, .. }
+            | Violation::SubjectRepeated { tuples, .. }
+            | Violation::TeacherDoubleBooked { tuples, .. }
+            | Violation::GroupDoubleBooked { tuples, .. } => *tuples,
+        }
+    }
+
+    /// Fitness lost to the violation, it is counted once from each of its tuples
+    pub fn penalty(&self, weights: &FitnessWeights) -> Adaptation {
+        2 as Adaptation * weights.penalty(self.conflict_type())
+    }
+}
+
+impl Display for ConflictType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let description = match self {
+            ConflictType::TeacherMultiClass => "teacher has multiple classes in one room",
+            ConflictType::RoomClash => "room is occupied by different teachers",
+            ConflictType::SameTeacherSameSubject => "teacher teaches the same subject twice",
+            ConflictType::SameTeacherDifferentSubject => "teacher teaches different subjects",
+            ConflictType::GroupClash => "student group attends multiple classes",
+        };
+        write!(f, "{}", description)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::algorithm::testing::{self, TempFile};
 
     #[test]
     fn test_default_value_of_individuals() {
         let invidual = Individual::default();
-        assert_eq!(invidual.adaptation, -1000);
+        assert_eq!(invidual.adaptation, -1000 as Adaptation);
         assert_eq!(invidual.chromosomes.len(), 0);
     }
 
@@ -140,7 +1083,382 @@ mod tests {
             genes: vec![1, 2, 3],
         }];
         let individual = Individual::with_chromosomes(chromosomes);
-        assert_eq!(individual.adaptation, -1000);
+        assert_eq!(individual.adaptation, -1000 as Adaptation);
         assert_eq!(individual.chromosomes.len(), 1);
     }
+
+    #[test]
+    fn test_period_fitness_cache_is_not_serialized() {
+        let mut individual = Individual::with_chromosomes((0..3).map(Chromosome::new).collect());
+        let cold = bincode::serialize(&individual).unwrap();
+
+        individual.period_fitness = vec![
+            CachedPeriodFitness {
+                genes_hash: 1,
+                fitness: -10 as Adaptation,
+                hard: -10 as Adaptation,
+                dirty: false,
+            };
+            3
+        ];
+        let warm = bincode::serialize(&individual).unwrap();
+
+        assert_eq!(warm, cold);
+        let received: Individual = bincode::deserialize(&warm).unwrap();
+        assert!(received.period_fitness.is_empty());
+    }
+
+    #[test]
+    fn test_validate_period_count() {
+        let individual = Individual::with_chromosomes((0..3).map(Chromosome::new).collect());
+
+        assert_eq!(individual.validate_period_count(3), Ok(()));
+
+        let error = individual.validate_period_count(8).unwrap_err();
+        assert_eq!(
+            error,
+            IndividualImportError::PeriodCountMismatch {
+                expected: 8,
+                found: 3
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Individual has 3 periods, but the configuration expects 8"
+        );
+    }
+
+    #[test]
+    fn test_improvement_over_baseline() {
+        let result = |baseline_adaptation: i32, adaptation: i32| RunResult {
+            best: Individual {
+                adaptation: adaptation as Adaptation,
+                ..Individual::default()
+            },
+            generations: 10,
+            baseline_adaptation: baseline_adaptation as Adaptation,
+            evaluations: 0,
+            elapsed: Duration::from_secs(1),
+        };
+
+        assert_eq!(result(-200, -50).improvement_over_baseline(), 75.0);
+        assert_eq!(result(-200, 0).improvement_over_baseline(), 100.0);
+        assert_eq!(result(-200, -200).improvement_over_baseline(), 0.0);
+        assert_eq!(result(0, 0).improvement_over_baseline(), 0.0);
+    }
+
+    #[test]
+    fn test_tuples_from_csv_with_optional_columns() {
+        let path = TempFile::with_contents(
+            "tuples",
+            "csv",
+            "Id,Label,Room,Teacher,Group,Capacity,Size\n1,Math,A,Smith,1a,30,25\n2,Art,B,Jones,,,\n3,Music,C,Brown\n",
+        );
+
+        let tuples = Tuple::from_csv(&path).unwrap();
+        assert_eq!(
+            tuples
+                .iter()
+                .map(|t| (t.capacity, t.size))
+                .collect::<Vec<_>>(),
+            vec![(30, 25), (0, 0), (0, 0)]
+        );
+        assert_eq!(tuples[0].group, "1a");
+        assert_eq!(tuples[1].group, "");
+
+        // optional columns are found by their header
+        std::fs::write(&path, "Id,Label,Room,Teacher,Size\n1,Math,A,Smith,25\n").unwrap();
+        let tuples = Tuple::from_csv(&path).unwrap();
+        assert_eq!((tuples[0].capacity, tuples[0].size), (0, 25));
+
+        std::fs::write(
+            &path,
+            "Id,Label,Room,Teacher,Capacity\n1,Math,A,Smith,many\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            Tuple::from_csv(&path),
+            Err(TuplesLoadError::InvalidNumber {
+                id: 1,
+                column: "capacity",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_load_and_validate_locks() {
+        let path = TempFile::with_contents("pinned", "json", r#"{"1": 0, "3": 2}"#);
+        let locks = load_locks(&path).unwrap();
+        assert_eq!(locks, Locks::from([(1, 0), (3, 2)]));
+
+        let tuples: Vec<Tuple> = (1..=3)
+            .map(|id| Tuple {
+                id,
+                ..Tuple::default()
+            })
+            .collect();
+        assert!(validate_locks(&locks, &tuples, 3).is_ok());
+        assert!(matches!(
+            validate_locks(&locks, &tuples, 2),
+            Err(LocksLoadError::PeriodOutOfRange {
+                id: 3,
+                period: 2,
+                number_of_periods: 2
+            })
+        ));
+        assert!(matches!(
+            validate_locks(&locks, &tuples[..2], 3),
+            Err(LocksLoadError::UnknownTuple { id: 3 })
+        ));
+    }
+
+    #[test]
+    fn test_duplicate_tuple_ids_are_rejected() {
+        let path = TempFile::with_contents(
+            "duplicates",
+            "csv",
+            "Id,Label,Room,Teacher\n1,Math,A,Smith\n2,Art,B,Jones\n1,Music,C,Brown\n",
+        );
+        assert!(matches!(
+            Tuple::from_csv(&path),
+            Err(TuplesLoadError::DuplicateId { id: 1 })
+        ));
+
+        let json_path = TempFile::with_contents(
+            "duplicates",
+            "json",
+            r#"[{"id": 7, "label": "Math", "room": "A", "teacher": "Smith"},
+            {"id": 7, "label": "Art", "room": "B", "teacher": "Jones"}]"#,
+        );
+        assert!(matches!(
+            Tuple::from_json(&json_path),
+            Err(TuplesLoadError::DuplicateId { id: 7 })
+        ));
+
+        // gaps are fine
+        std::fs::write(
+            &path,
+            "Id,Label,Room,Teacher\n1,Math,A,Smith\n5,Art,B,Jones\n",
+        )
+        .unwrap();
+        assert_eq!(Tuple::from_csv(&path).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_tuples_csv_with_other_layouts() {
+        let path = TempFile::new("layouts", "csv");
+        let tuples = |content: &str, options| {
+            std::fs::write(&path, content).unwrap();
+            Tuple::from_csv_with(&path, options).unwrap()
+        };
+
+        let expected = tuples(
+            "Id,Label,Room,Teacher,Size\n1,Math,A,\"Smith; J\",25\n2,Art,B,Jones,\n",
+            CsvOptions::default(),
+        );
+        let semicolons = CsvOptions {
+            delimiter: b';',
+            ..CsvOptions::default()
+        };
+        assert_eq!(
+            tuples(
+                "Id;Label;Room;Teacher;Size\n1;Math;A;\"Smith; J\";25\n2;Art;B;Jones;\n",
+                semicolons
+            ),
+            expected
+        );
+
+        let headerless = CsvOptions {
+            has_headers: false,
+            ..CsvOptions::default()
+        };
+        let loaded = tuples("1,Math,A,Smith,1a,30,25\n2,Art,B,Jones\n", headerless);
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(
+            (loaded[0].group.as_str(), loaded[0].capacity, loaded[0].size),
+            ("1a", 30, 25)
+        );
+        assert_eq!((loaded[1].id, loaded[1].teacher.as_str()), (2, "Jones"));
+    }
+
+    #[test]
+    fn test_malformed_tuples_csv_reports_the_line() {
+        let path = TempFile::new("malformed", "csv");
+
+        std::fs::write(
+            &path,
+            "Id,Label,Room,Teacher\n1,Math,A,Smith\nx2,Art,B,Jones\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            Tuple::from_csv(&path),
+            Err(TuplesLoadError::InvalidId { line: 3, value }) if value == "x2"
+        ));
+
+        std::fs::write(&path, "Id,Label,Room,Teacher\n99999999999,Math,A,Smith\n").unwrap();
+        assert!(matches!(
+            Tuple::from_csv(&path),
+            Err(TuplesLoadError::InvalidId { line: 2, .. })
+        ));
+
+        std::fs::write(&path, "Id,Label,Room,Teacher\n1,Math,A,Smith\n2,Art,B\n").unwrap();
+        assert!(matches!(
+            Tuple::from_csv(&path),
+            Err(TuplesLoadError::ShortRow {
+                line: 3,
+                columns: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn test_tuples_from_json_match_csv() {
+        let csv_path = TempFile::with_contents(
+            "same-tuples",
+            "csv",
+            "Id,Label,Room,Teacher,Group,Capacity,Size\n1,Math,A,Smith,1a,30,25\n2,Art,B,Jones,,,\n",
+        );
+        let json_path = TempFile::with_contents(
+            "same-tuples",
+            "json",
+            r#"[
+                {"id": 1, "label": "Math", "room": "A", "teacher": "Smith", "group": "1a",
+                 "capacity": 30, "size": 25},
+                {"id": 2, "label": "Art", "room": "B", "teacher": "Jones"}
+            ]"#,
+        );
+
+        assert_eq!(TuplesFormat::from_path(&csv_path), TuplesFormat::Csv);
+        assert_eq!(TuplesFormat::from_path(&json_path), TuplesFormat::Json);
+        let load = |path| Tuple::load(path, TuplesFormat::from_path(path), CsvOptions::default());
+        let from_csv = load(&csv_path).unwrap();
+        let from_json = load(&json_path).unwrap();
+        assert_eq!(from_csv, from_json);
+
+        std::fs::write(&json_path, r#"[{"id": "one"}]"#).unwrap();
+        assert!(matches!(
+            Tuple::from_json(&json_path),
+            Err(TuplesLoadError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn test_tuple_index_matches_linear_scan() {
+        let tuples = crate::algorithm::testing::generate_synthetic_problem(50, 6, 5, 6, 7);
+        let index = TupleIndex::from_tuples(&tuples);
+
+        assert_eq!(index.len(), tuples.len());
+        for id in -1..=51 {
+            assert_eq!(index.get(id), tuples.iter().find(|tuple| tuple.id == id));
+        }
+        for tuple in &tuples {
+            assert_eq!(&index[&tuple.id], tuple);
+        }
+        assert!(std::iter::zip(&index, &tuples).all(|(indexed, tuple)| indexed == tuple));
+    }
+
+    #[test]
+    fn test_room_restrictions() {
+        let tuple = |id: i32, room: &str, teacher: &str| testing::tuple(id, "Music", room, teacher);
+        let mut restrictions = RoomRestrictions::default();
+        restrictions.allow("Smith", "R");
+
+        let in_own_room = [tuple(1, "R", "Smith"), tuple(2, "A", "Jones")];
+        assert!(restrictions.violations(&in_own_room).is_empty());
+
+        let elsewhere = [tuple(1, "A", "Smith"), tuple(2, "R", "Jones")];
+        assert_eq!(restrictions.violations(&elsewhere), vec![&elsewhere[0]]);
+    }
+
+    #[test]
+    fn test_throughput() {
+        let result = RunResult {
+            best: Individual::default(),
+            generations: 10,
+            baseline_adaptation: Adaptation::default(),
+            evaluations: 5_000,
+            elapsed: Duration::from_millis(2_500),
+        };
+
+        assert!((result.evaluations_per_second() - 2_000.0).abs() < 1e-9);
+        assert!((result.generations_per_second() - 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_respects_locks() {
+        let individual = Individual::with_chromosomes(vec![
+            Chromosome {
+                id: 0,
+                genes: vec![1, 2],
+            },
+            Chromosome {
+                id: 1,
+                genes: vec![3],
+            },
+        ]);
+
+        assert!(individual.respects_locks(&Locks::from([(1, 0), (3, 1)])));
+        assert!(!individual.respects_locks(&Locks::from([(3, 0)])));
+        assert!(!individual.respects_locks(&Locks::from([(1, 5)])));
+    }
+
+    #[test]
+    fn test_sort_by_adaptation_puts_fittest_first() {
+        let mut population: Population = [-30, 0, -10]
+            .into_iter()
+            .map(|adaptation| Individual {
+                adaptation: adaptation as Adaptation,
+                ..Individual::default()
+            })
+            .collect();
+
+        sort_by_adaptation(&mut population);
+
+        assert_eq!(
+            population.iter().map(|i| i.adaptation).collect::<Vec<_>>(),
+            [0, -10, -30].map(|a| a as Adaptation)
+        );
+    }
+
+    #[cfg(feature = "float-fitness")]
+    #[test]
+    fn test_sort_by_adaptation_handles_nan() {
+        let mut population: Population = [-1.5, f64::NAN, -0.5]
+            .into_iter()
+            .map(|adaptation| Individual {
+                adaptation,
+                ..Individual::default()
+            })
+            .collect();
+
+        sort_by_adaptation(&mut population);
+
+        assert_eq!(population[0].adaptation, -0.5);
+        assert_eq!(population[1].adaptation, -1.5);
+        assert!(population[2].adaptation.is_nan());
+    }
+
+    #[test]
+    fn test_run_metadata_reflects_the_run() {
+        let config = AlgorithmConfig {
+            random_seed: Some(42),
+            population_size: 64,
+            ..AlgorithmConfig::default()
+        };
+        let finished_at = UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+
+        let metadata = RunMetadata::new(&config, 4, b"id,label,room,teacher\n", finished_at);
+
+        assert_eq!(metadata.random_seed, Some(42));
+        assert_eq!(metadata.config, config);
+        assert_eq!(metadata.number_of_nodes, 4);
+        assert_eq!(metadata.timestamp, 1_700_000_000);
+        assert_eq!(metadata.version, env!("CARGO_PKG_VERSION"));
+        assert_ne!(
+            metadata.tuples_hash,
+            RunMetadata::new(&config, 4, b"id,label,room,teacher\n1", finished_at).tuples_hash
+        );
+        assert_eq!(fnv1a_hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
 }
@@ -0,0 +1,419 @@
+//! Running the whole algorithm in a single process
+
+use rayon::prelude::*;
+use tracing::{info, info_span};
+
+use super::{
+    adaptive_mutation_probability, calculate_fitness_cached,
+    checkpoint::{Checkpoint, CheckpointError},
+    config::AlgorithmConfig,
+    create_first_population_with_locks,
+    datatypes::{sort_by_adaptation, Adaptation, Individual, Locks, Population, Tuple, TupleIndex},
+    evolve_one_generation, integrate_migrants, random_immigrants,
+    report::GenerationStats,
+    stopping::StopCondition,
+};
+
+/// State of a run evolving the whole population in this process
+///
+/// The population is always evaluated and sorted from the fittest individual, so
+/// [`GeneticAlgorithm::best`] is its first individual. Stops like the distributed run does, see
+/// [`StopCondition`].
+#[derive(Debug, Clone)]
+pub struct GeneticAlgorithm {
+    config: AlgorithmConfig,
+    tuples: TupleIndex,
+    population: Population,
+    locks: Locks,
+    generation: usize,
+    stop_condition: StopCondition,
+    stopped: bool,
+    history: Vec<GenerationStats>,
+}
+
+impl GeneticAlgorithm {
+    /// Create and evaluate the first population
+    pub fn new(config: AlgorithmConfig, tuples: Vec<Tuple>) -> Self {
+        Self::new_with_locks(config, tuples, Locks::new())
+    }
+
+    /// [`GeneticAlgorithm::new`] keeping the genes in `locks` in the periods they are pinned to
+    /// for the whole run
+    pub fn new_with_locks(config: AlgorithmConfig, tuples: Vec<Tuple>, locks: Locks) -> Self {
+        let population = create_first_population_with_locks(&config, &tuples, &locks);
+        Self {
+            locks,
+            ..Self::with_population(config, TupleIndex::from(tuples), population, 0)
+        }
+    }
+
+    /// Continue a run from a checkpoint, see [`Checkpoint::validate`]
+    pub fn resume(
+        config: AlgorithmConfig,
+        tuples: Vec<Tuple>,
+        checkpoint: Checkpoint,
+    ) -> Result<Self, CheckpointError> {
+        Self::resume_with_locks(config, tuples, checkpoint, Locks::new())
+    }
+
+    /// [`GeneticAlgorithm::resume`] keeping the genes in `locks` in the periods they are pinned
+    /// to, the checkpoint should come from a run with the same locks
+    pub fn resume_with_locks(
+        config: AlgorithmConfig,
+        tuples: Vec<Tuple>,
+        checkpoint: Checkpoint,
+        locks: Locks,
+    ) -> Result<Self, CheckpointError> {
+        checkpoint.validate(&config)?;
+        Ok(Self {
+            locks,
+            ..Self::with_population(
+                config,
+                TupleIndex::from(tuples),
+                checkpoint.population,
+                checkpoint.generation,
+            )
+        })
+    }
+
+    fn with_population(
+        config: AlgorithmConfig,
+        tuples: TupleIndex,
+        mut population: Population,
+        generation: usize,
+    ) -> Self {
+        population.par_iter_mut().for_each(|individual| {
+            individual.adaptation = calculate_fitness_cached(&config, individual, &tuples);
+        });
+        sort_by_adaptation(&mut population);
+
+        GeneticAlgorithm {
+            stop_condition: StopCondition::new(&config),
+            config,
+            tuples,
+            population,
+            locks: Locks::new(),
+            generation,
+            stopped: false,
+            history: Vec::new(),
+        }
+    }
+
+    /// Current population and generation, to resume the run from later
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            generation: self.generation,
+            population: self.population.clone(),
+        }
+    }
+
+    /// Evolve one generation, returns whether the run should stop after it
+    ///
+    /// A population lacking diversity takes random immigrants first, see [`random_immigrants`]
+    pub fn step(&mut self) -> bool {
+        let _generation = info_span!("generation", generation = self.generation + 1).entered();
+        let immigrants = random_immigrants(
+            &self.config,
+            &self.population,
+            self.generation,
+            &self.tuples,
+            &self.locks,
+        );
+        if !immigrants.is_empty() {
+            info!(count = immigrants.len(), "random immigrants");
+            integrate_migrants(&mut self.population, immigrants);
+        }
+        let generation_config = AlgorithmConfig {
+            mutation_probability: adaptive_mutation_probability(&self.config, &self.population),
+            ..self.config
+        };
+        self.population = evolve_one_generation(
+            &generation_config,
+            &self.population,
+            self.generation,
+            &self.tuples,
+            &self.locks,
+            None,
+            None,
+        );
+        self.stopped =
+            self.stop_condition
+                .should_stop(self.generation, &self.population, &self.tuples);
+        self.generation += 1;
+        self.history
+            .extend(GenerationStats::new(self.generation, &self.population));
+        info!(best_fitness = %self.best_fitness(), "generation complete");
+
+        self.stopped
+    }
+
+    /// Evolve until [`AlgorithmConfig::max_generations`] generations have run or the stop
+    /// condition is met, returns the best individual
+    pub fn run(&mut self) -> &Individual {
+        while !self.stopped && self.generation < self.config.max_generations {
+            self.step();
+        }
+
+        self.best()
+    }
+
+    /// Fittest individual of the current population
+    pub fn best(&self) -> &Individual {
+        &self.population[0]
+    }
+
+    pub fn best_fitness(&self) -> Adaptation {
+        self.best().adaptation
+    }
+
+    /// Number of generations evolved so far
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Current population, sorted from the fittest individual
+    pub fn population(&self) -> &Population {
+        &self.population
+    }
+
+    /// Statistics of every generation evolved so far, see [`GenerationStats`]
+    pub fn history(&self) -> &[GenerationStats] {
+        &self.history
+    }
+
+    pub fn config(&self) -> &AlgorithmConfig {
+        &self.config
+    }
+
+    pub fn tuples(&self) -> &TupleIndex {
+        &self.tuples
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{
+        calculate_fitness,
+        datatypes::compare_adaptation,
+        testing::{self, TempFile},
+        validate_completeness,
+    };
+    use std::sync::{Arc, Mutex};
+    use tracing::{
+        field::{Field, Visit},
+        Event, Subscriber,
+    };
+    use tracing_subscriber::{
+        layer::{Context, SubscriberExt},
+        Layer,
+    };
+
+    fn algorithm(max_generations: usize) -> GeneticAlgorithm {
+        let config = AlgorithmConfig {
+            population_size: 30,
+            number_of_periods: 5,
+            max_generations,
+            random_seed: Some(3),
+            ..AlgorithmConfig::default()
+        };
+        GeneticAlgorithm::new(config, testing::generate_synthetic_problem(40, 6, 5, 6, 3))
+    }
+
+    #[test]
+    fn test_step_keeps_population_invariants() {
+        let mut algorithm = algorithm(100);
+
+        for generation in 1..=10 {
+            algorithm.step();
+            assert_eq!(algorithm.generation(), generation);
+
+            let population = algorithm.population();
+            assert_eq!(population.len(), 30);
+            assert!(population.windows(2).all(|pair| {
+                compare_adaptation(&pair[0].adaptation, &pair[1].adaptation).is_ge()
+            }));
+            for individual in population {
+                assert_eq!(individual.chromosomes.len(), 5);
+                assert_eq!(
+                    validate_completeness(individual, algorithm.tuples()),
+                    Ok(())
+                );
+                assert_eq!(
+                    individual.adaptation,
+                    calculate_fitness(algorithm.config(), individual, algorithm.tuples())
+                );
+            }
+            assert_eq!(algorithm.best_fitness(), population[0].adaptation);
+
+            let stats = algorithm.history().last().unwrap();
+            assert_eq!(algorithm.history().len(), generation);
+            assert_eq!(stats.generation, generation);
+            assert_eq!(stats.max, algorithm.best_fitness());
+        }
+    }
+
+    /// Messages of the events recorded while it is the subscriber
+    #[derive(Clone, Default)]
+    struct EventMessages(Arc<Mutex<Vec<String>>>);
+
+    impl<S: Subscriber> Layer<S> for EventMessages {
+        fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+            struct Message(String);
+            impl Visit for Message {
+                fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                    if field.name() == "message" {
+                        self.0 = format!("{:?}", value);
+                    }
+                }
+            }
+
+            let mut message = Message(String::new());
+            event.record(&mut message);
+            self.0.lock().unwrap().push(message.0);
+        }
+    }
+
+    #[test]
+    fn test_step_emits_generation_complete_event() {
+        let messages = EventMessages::default();
+        let subscriber = tracing_subscriber::registry().with(messages.clone());
+
+        tracing::subscriber::with_default(subscriber, || {
+            let mut algorithm = algorithm(100);
+            algorithm.step();
+            algorithm.step();
+        });
+
+        let messages = messages.0.lock().unwrap();
+        assert_eq!(
+            messages
+                .iter()
+                .filter(|message| *message == "generation complete")
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_pinned_tuples_stay_in_their_periods() {
+        let base = algorithm(10);
+        let tuples = base.tuples.to_vec();
+        let locks = Locks::from([(tuples[0].id, 4), (tuples[5].id, 0)]);
+        let mut algorithm = GeneticAlgorithm::new_with_locks(
+            AlgorithmConfig {
+                mutation_probability: 0.5,
+                ..base.config
+            },
+            tuples,
+            locks.clone(),
+        );
+
+        algorithm.run();
+        assert!(algorithm
+            .population()
+            .iter()
+            .all(|individual| individual.respects_locks(&locks)));
+    }
+
+    #[test]
+    fn test_resumed_run_keeps_pinned_tuples() {
+        let base = algorithm(3);
+        let tuples = base.tuples.to_vec();
+        let locks = Locks::from([(tuples[0].id, 4), (tuples[5].id, 0)]);
+        let config = AlgorithmConfig {
+            mutation_probability: 0.5,
+            ..base.config
+        };
+        let mut pinned = GeneticAlgorithm::new_with_locks(config, tuples.clone(), locks.clone());
+        pinned.run();
+
+        let mut resumed = GeneticAlgorithm::resume_with_locks(
+            AlgorithmConfig {
+                max_generations: 10,
+                ..config
+            },
+            tuples,
+            pinned.checkpoint(),
+            locks.clone(),
+        )
+        .unwrap();
+        resumed.run();
+
+        assert!(resumed.generation() > pinned.generation());
+        assert!(resumed
+            .population()
+            .iter()
+            .all(|individual| individual.respects_locks(&locks)));
+    }
+
+    #[test]
+    fn test_run_stops_at_max_generations() {
+        let mut algorithm = algorithm(4);
+        let best = algorithm.run().clone();
+
+        // a perfect timetable stops the run early
+        assert!(algorithm.generation() == 4 || best.adaptation == Adaptation::default());
+        assert_eq!(best.adaptation, algorithm.best_fitness());
+
+        // running again doesn't evolve past the limit
+        algorithm.run();
+        assert!(algorithm.generation() <= 4);
+    }
+
+    #[test]
+    fn test_time_budget_stops_the_run_early() {
+        let base = algorithm(1_000);
+        let mut algorithm = GeneticAlgorithm::new(
+            AlgorithmConfig {
+                time_budget_secs: Some(0),
+                ..base.config
+            },
+            base.tuples.to_vec(),
+        );
+
+        algorithm.run();
+        assert_eq!(algorithm.generation(), 1);
+    }
+
+    #[test]
+    fn test_resume_from_checkpoint_written_mid_run() {
+        let mut algorithm = algorithm(8);
+        for _ in 0..3 {
+            algorithm.step();
+        }
+
+        let path = TempFile::new("resume", "json");
+        algorithm.checkpoint().save(&path).unwrap();
+        let checkpoint = Checkpoint::load(&path).unwrap();
+
+        let mut resumed =
+            GeneticAlgorithm::resume(*algorithm.config(), algorithm.tuples().to_vec(), checkpoint)
+                .unwrap();
+        assert_eq!(resumed.generation(), 3);
+        assert_eq!(resumed.best_fitness(), algorithm.best_fitness());
+        assert_eq!(
+            serde_json::to_string(resumed.population()).unwrap(),
+            serde_json::to_string(algorithm.population()).unwrap()
+        );
+
+        // the resumed run only evolves the generations left
+        resumed.run();
+        assert!(resumed.generation() <= 8);
+        assert!(resumed.history().len() <= 5);
+    }
+
+    #[test]
+    fn test_resume_rejects_mismatched_config() {
+        let algorithm = algorithm(8);
+        let config = AlgorithmConfig {
+            population_size: 31,
+            ..*algorithm.config()
+        };
+
+        assert!(matches!(
+            GeneticAlgorithm::resume(config, algorithm.tuples().to_vec(), algorithm.checkpoint()),
+            Err(CheckpointError::PopulationSizeMismatch { .. })
+        ));
+    }
+}
@@ -0,0 +1,522 @@
+//! Writing the best timetable in formats other programs can read, and reading it back
+
+use std::{
+    fmt::Write,
+    fs::File,
+    io::BufReader,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{
+    config::AlgorithmConfig,
+    datatypes::{Chromosome, Gene, Individual, Tuple, TupleIndex, TuplesFormat},
+};
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("Gene {0} doesn't match any tuple")]
+    UnknownGene(Gene),
+    #[error("Period {0} is not one of the configured periods")]
+    UnknownPeriod(i32),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+/// Class of an exported timetable, one row of the CSV written by [`export_timetable_csv`]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TimetableRow {
+    /// [`crate::algorithm::datatypes::Chromosome::id`] of the period the class is held in
+    pub period_id: i32,
+    pub tuple_id: i32,
+    pub label: String,
+    pub room: String,
+    pub teacher: String,
+}
+
+/// Tuple of a gene, see [`ExportError::UnknownGene`]
+fn resolve(gene: Gene, tuples: &TupleIndex) -> Result<&Tuple, ExportError> {
+    tuples.get(gene).ok_or(ExportError::UnknownGene(gene))
+}
+
+/// Every class of the individual with its tuple, in the order of the periods and their genes
+pub fn timetable_rows(
+    individual: &Individual,
+    tuples: &TupleIndex,
+) -> Result<Vec<TimetableRow>, ExportError> {
+    individual
+        .chromosomes
+        .iter()
+        .flat_map(|chromosome| chromosome.genes.iter().map(|gene| (chromosome.id, *gene)))
+        .map(|(period_id, gene)| {
+            let tuple = resolve(gene, tuples)?;
+            Ok(TimetableRow {
+                period_id,
+                tuple_id: tuple.id,
+                label: tuple.label.clone(),
+                room: tuple.room.clone(),
+                teacher: tuple.teacher.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Write the timetable as a CSV file with a row for every class, see [`TimetableRow`]
+///
+/// Fails before creating the file when a gene doesn't match any tuple.
+pub fn export_timetable_csv(
+    individual: &Individual,
+    tuples: &TupleIndex,
+    path: impl AsRef<Path>,
+) -> Result<(), ExportError> {
+    let rows = timetable_rows(individual, tuples)?;
+    let mut writer = csv::Writer::from_path(path)?;
+
+    for row in &rows {
+        writer.serialize(row)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Period of a [`ResolvedTimetable`] with the full tuples of its classes
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ResolvedPeriod {
+    /// [`crate::algorithm::datatypes::Chromosome::id`] of the period
+    pub id: i32,
+    pub classes: Vec<Tuple>,
+}
+
+/// Timetable with its genes resolved to tuples, the structure written by
+/// [`export_timetable_json`]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ResolvedTimetable {
+    pub periods: Vec<ResolvedPeriod>,
+}
+
+impl ResolvedTimetable {
+    /// Resolve every gene of the individual, keeping the periods in order, empty ones included
+    pub fn new(individual: &Individual, tuples: &TupleIndex) -> Result<Self, ExportError> {
+        let periods = individual
+            .chromosomes
+            .iter()
+            .map(|chromosome| {
+                Ok(ResolvedPeriod {
+                    id: chromosome.id,
+                    classes: chromosome
+                        .genes
+                        .iter()
+                        .map(|gene| resolve(*gene, tuples).cloned())
+                        .collect::<Result<_, _>>()?,
+                })
+            })
+            .collect::<Result<_, ExportError>>()?;
+
+        Ok(ResolvedTimetable { periods })
+    }
+}
+
+/// Write the timetable as pretty printed JSON, see [`ResolvedTimetable`]
+///
+/// Fails before creating the file when a gene doesn't match any tuple.
+pub fn export_timetable_json(
+    individual: &Individual,
+    tuples: &TupleIndex,
+    path: impl AsRef<Path>,
+) -> Result<(), ExportError> {
+    let timetable = ResolvedTimetable::new(individual, tuples)?;
+    serde_json::to_writer_pretty(File::create(path)?, &timetable)?;
+    Ok(())
+}
+
+/// Read back a timetable written by [`export_timetable_csv`] or [`export_timetable_json`], JSON
+/// when the file has a `.json` extension
+///
+/// The individual has `number_of_periods` periods, a class in any other period or of an unknown
+/// tuple is rejected. Its adaptation is left to be evaluated.
+pub fn load_timetable(
+    path: impl AsRef<Path>,
+    tuples: &TupleIndex,
+    number_of_periods: usize,
+) -> Result<Individual, ExportError> {
+    let path = path.as_ref();
+    let classes: Vec<(i32, Gene)> = match TuplesFormat::from_path(path) {
+        TuplesFormat::Json => {
+            let timetable: ResolvedTimetable =
+                serde_json::from_reader(BufReader::new(File::open(path)?))?;
+            timetable
+                .periods
+                .iter()
+                .flat_map(|period| period.classes.iter().map(|class| (period.id, class.id)))
+                .collect()
+        }
+        TuplesFormat::Csv => csv::Reader::from_path(path)?
+            .deserialize::<TimetableRow>()
+            .map(|row| row.map(|row| (row.period_id, row.tuple_id)))
+            .collect::<Result<_, _>>()?,
+    };
+
+    let mut chromosomes: Vec<Chromosome> =
+        (0..number_of_periods as i32).map(Chromosome::new).collect();
+    for (period_id, gene) in classes {
+        resolve(gene, tuples)?;
+        usize::try_from(period_id)
+            .ok()
+            .and_then(|index| chromosomes.get_mut(index))
+            .ok_or(ExportError::UnknownPeriod(period_id))?
+            .genes
+            .push(gene);
+    }
+
+    Ok(Individual::with_chromosomes(chromosomes))
+}
+
+const MINUTES_PER_DAY: i64 = 24 * 60;
+
+/// Days since 1970-01-01 of a `YYYY-MM-DD` date, `None` when it isn't a valid date
+pub fn parse_date(date: &str) -> Option<i64> {
+    let mut parts = date.trim().splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+
+    let days = days_from_civil(year, month, day);
+    ((1..=12).contains(&month) && day >= 1 && civil_from_days(days) == (year, month, day))
+        .then_some(days)
+}
+
+/// Days since 1970-01-01 of the day `time` falls on, in UTC
+pub fn day_of(time: SystemTime) -> i64 {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    (seconds / (MINUTES_PER_DAY as u64 * 60)) as i64
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date, see
+/// <https://howardhinnant.github.io/date_algorithms.html#days_from_civil>
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month as i64 + 9) % 12) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Inverse of [`days_from_civil`]
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// iCalendar date and time of the minute `minutes` after 1970-01-01 00:00, e.g.
+/// `20240902T080000`
+fn ics_date_time(minutes: i64) -> String {
+    let (year, month, day) = civil_from_days(minutes.div_euclid(MINUTES_PER_DAY));
+    let minute_of_day = minutes.rem_euclid(MINUTES_PER_DAY);
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}00",
+        year,
+        month,
+        day,
+        minute_of_day / 60,
+        minute_of_day % 60
+    )
+}
+
+/// Escape the characters iCalendar gives a meaning in text values
+fn ics_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Minutes after 1970-01-01 00:00 the period with `period_id` starts at
+///
+/// Days have [`AlgorithmConfig::periods_per_day`] periods, the first one starting on `first_day`.
+/// Periods are [`AlgorithmConfig::period_minutes`] long from
+/// [`AlgorithmConfig::day_start_minutes`] on, so when a day has more periods than fit before
+/// midnight the last ones continue on the next day.
+fn period_start(config: &AlgorithmConfig, first_day: i64, period_id: i32) -> i64 {
+    let period_id = i64::from(period_id);
+    let (day, slot) = match config.periods_per_day {
+        Some(periods) => {
+            let periods = periods.max(1) as i64;
+            (period_id / periods, period_id % periods)
+        }
+        None => (0, period_id),
+    };
+
+    (first_day + day) * MINUTES_PER_DAY
+        + i64::from(config.day_start_minutes)
+        + slot * i64::from(config.period_minutes)
+}
+
+/// Timetable as an iCalendar document with a `VEVENT` for every class
+///
+/// The label of the class is the summary, its room the location and its teacher the
+/// description. Times are local times without a time zone, `first_day` is in days since
+/// 1970-01-01 (see [`parse_date`]) and `stamp` is when the document is created.
+pub fn timetable_ics(
+    config: &AlgorithmConfig,
+    individual: &Individual,
+    tuples: &TupleIndex,
+    first_day: i64,
+    stamp: SystemTime,
+) -> Result<String, ExportError> {
+    let stamp_minutes = stamp
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() / 60) as i64;
+
+    let mut ics = String::new();
+    ics.push_str("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Planner//Timetable//EN\r\n");
+
+    for row in timetable_rows(individual, tuples)? {
+        let start = period_start(config, first_day, row.period_id);
+        let end = start + i64::from(config.period_minutes);
+
+        write!(
+            ics,
+            "BEGIN:VEVENT\r\n\
+             UID:{}-{}@planner\r\n\
+             DTSTAMP:{}Z\r\n\
+             DTSTART:{}\r\n\
+             DTEND:{}\r\n\
+             SUMMARY:{}\r\n\
+             LOCATION:{}\r\n\
+             DESCRIPTION:Teacher: {}\r\n\
+             END:VEVENT\r\n",
+            row.period_id,
+            row.tuple_id,
+            ics_date_time(stamp_minutes),
+            ics_date_time(start),
+            ics_date_time(end),
+            ics_text(&row.label),
+            ics_text(&row.room),
+            ics_text(&row.teacher)
+        )
+        .expect("Writing to a string can't fail");
+    }
+
+    ics.push_str("END:VCALENDAR\r\n");
+    Ok(ics)
+}
+
+/// Write the timetable as an iCalendar file, see [`timetable_ics`]
+pub fn export_ics(
+    config: &AlgorithmConfig,
+    individual: &Individual,
+    tuples: &TupleIndex,
+    first_day: i64,
+    path: impl AsRef<Path>,
+) -> Result<(), ExportError> {
+    let ics = timetable_ics(config, individual, tuples, first_day, SystemTime::now())?;
+    std::fs::write(path, ics)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::algorithm::{
+        datatypes::Timetable,
+        testing::{tuple, TempFile},
+    };
+
+    fn tuples() -> TupleIndex {
+        TupleIndex::from(vec![
+            tuple(1, "Math", "A", "Smith"),
+            tuple(2, "Art", "B", "Jones"),
+            tuple(3, "Physics", "B", "Brown"),
+        ])
+    }
+
+    fn individual() -> Individual {
+        Individual::with_chromosomes(vec![
+            Chromosome {
+                id: 0,
+                genes: vec![3, 1],
+            },
+            Chromosome {
+                id: 1,
+                genes: vec![],
+            },
+            Chromosome {
+                id: 2,
+                genes: vec![2],
+            },
+        ])
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let path = TempFile::new("timetable", "csv");
+        export_timetable_csv(&individual(), &tuples(), &path).unwrap();
+
+        let rows: Vec<TimetableRow> = csv::Reader::from_path(&path)
+            .unwrap()
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(rows, timetable_rows(&individual(), &tuples()).unwrap());
+        assert_eq!(
+            rows[0],
+            TimetableRow {
+                period_id: 0,
+                tuple_id: 3,
+                label: "Physics".to_string(),
+                room: "B".to_string(),
+                teacher: "Brown".to_string(),
+            }
+        );
+
+        // the periods are rebuilt from the rows, empty ones have no rows
+        let periods = rows
+            .iter()
+            .into_group_map_by(|row| row.period_id)
+            .into_iter()
+            .map(|(period_id, rows)| (period_id, rows.iter().map(|row| row.tuple_id).collect()))
+            .sorted()
+            .collect::<Vec<(i32, Vec<i32>)>>();
+        assert_eq!(periods, vec![(0, vec![3, 1]), (2, vec![2])]);
+    }
+
+    #[test]
+    fn test_unknown_gene_is_an_error() {
+        let mut individual = individual();
+        individual.chromosomes[1].genes.push(7);
+
+        assert!(matches!(
+            timetable_rows(&individual, &tuples()),
+            Err(ExportError::UnknownGene(7))
+        ));
+        assert!(matches!(
+            ResolvedTimetable::new(&individual, &tuples()),
+            Err(ExportError::UnknownGene(7))
+        ));
+    }
+
+    #[test]
+    fn test_json_export() {
+        let path = TempFile::new("timetable", "json");
+        export_timetable_json(&individual(), &tuples(), &path).unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+
+        let periods = json["periods"].as_array().unwrap();
+        assert_eq!(periods.len(), 3);
+        assert_eq!(periods[0]["id"], 0);
+        assert_eq!(periods[0]["classes"][0]["label"], "Physics");
+        assert_eq!(periods[0]["classes"][1]["label"], "Math");
+        assert_eq!(periods[1]["classes"].as_array().unwrap().len(), 0);
+        assert_eq!(periods[2]["classes"][0]["teacher"], "Jones");
+
+        let timetable: ResolvedTimetable = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            timetable,
+            ResolvedTimetable::new(&individual(), &tuples()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_load_timetable_reads_both_exports() {
+        for extension in ["csv", "json"] {
+            let path = TempFile::new("loaded-timetable", extension);
+            if extension == "json" {
+                export_timetable_json(&individual(), &tuples(), &path).unwrap();
+            } else {
+                export_timetable_csv(&individual(), &tuples(), &path).unwrap();
+            }
+
+            let loaded = load_timetable(&path, &tuples(), 3).unwrap();
+            assert!(loaded.period_genes().eq(individual().period_genes()));
+
+            assert!(matches!(
+                load_timetable(&path, &tuples(), 2),
+                Err(ExportError::UnknownPeriod(2))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_dates() {
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(parse_date("2024-09-02"), Some(19_968));
+        assert_eq!(parse_date("2024-02-29"), Some(19_782));
+        assert_eq!(parse_date("2023-02-29"), None);
+        assert_eq!(parse_date("2024-13-01"), None);
+        assert_eq!(parse_date("2 September"), None);
+        assert_eq!(civil_from_days(19_968), (2024, 9, 2));
+        assert_eq!(
+            day_of(UNIX_EPOCH + std::time::Duration::from_secs(86_400 + 5)),
+            1
+        );
+    }
+
+    #[test]
+    fn test_ics_export() {
+        let config = AlgorithmConfig {
+            periods_per_day: Some(2),
+            period_minutes: 90,
+            day_start_minutes: 23 * 60,
+            ..AlgorithmConfig::default()
+        };
+        let ics = timetable_ics(
+            &config,
+            &individual(),
+            &tuples(),
+            parse_date("2024-09-02").unwrap(),
+            UNIX_EPOCH,
+        )
+        .unwrap();
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT\r\n").count(), 3);
+        assert_eq!(ics.matches("END:VEVENT\r\n").count(), 3);
+
+        let starts: Vec<&str> = ics
+            .lines()
+            .filter_map(|line| line.strip_prefix("DTSTART:"))
+            .collect();
+        assert!(starts.iter().all(|start| start.len() == 15
+            && start.as_bytes()[8] == b'T'
+            && start.chars().filter(char::is_ascii_digit).count() == 14));
+        // both classes of the first period start at 23:00, the third period is the first one of
+        // the second day
+        assert_eq!(
+            starts,
+            vec!["20240902T230000", "20240902T230000", "20240903T230000"]
+        );
+        // the first period runs past midnight
+        assert!(ics.contains("DTEND:20240903T003000\r\n"));
+        assert!(ics.contains("SUMMARY:Physics\r\nLOCATION:B\r\nDESCRIPTION:Teacher: Brown\r\n"));
+    }
+}
@@ -0,0 +1,240 @@
+//! Advisory checks of the input data, see [`lint_problem`]
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+
+use super::{config::AlgorithmConfig, datatypes::Tuple};
+
+/// Suspicious, but valid, property of the input data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintWarning {
+    /// The teacher has more classes than there are periods, so some of them always clash
+    TeacherOverbooked {
+        teacher: String,
+        classes: usize,
+        periods: usize,
+    },
+    /// The room hosts more classes than there are periods, so some of them always clash
+    RoomOverbooked {
+        room: String,
+        classes: usize,
+        periods: usize,
+    },
+    /// The teacher has a single class, possibly a typo in the name
+    TeacherWithSingleClass { teacher: String },
+    /// The subject has a single class, possibly a typo in the label
+    SubjectWithSingleClass { label: String },
+    /// Only one teacher ever uses the room
+    RoomWithSingleTeacher { room: String, teacher: String },
+    /// No tuple has an id between `after` and `before`, possibly a class left out of the file
+    IdGap { after: i32, before: i32 },
+}
+
+impl Display for LintWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LintWarning::TeacherOverbooked {
+                teacher,
+                classes,
+                periods,
+            } => write!(
+                f,
+                "teacher {} has {} classes in {} periods, some always clash",
+                teacher, classes, periods
+            ),
+            LintWarning::RoomOverbooked {
+                room,
+                classes,
+                periods,
+            } => write!(
+                f,
+                "room {} hosts {} classes in {} periods, some always clash",
+                room, classes, periods
+            ),
+            LintWarning::TeacherWithSingleClass { teacher } => {
+                write!(f, "teacher {} has a single class", teacher)
+            }
+            LintWarning::SubjectWithSingleClass { label } => {
+                write!(f, "subject {} has a single class", label)
+            }
+            LintWarning::RoomWithSingleTeacher { room, teacher } => {
+                write!(f, "room {} is only used by teacher {}", room, teacher)
+            }
+            LintWarning::IdGap { after, before } => {
+                write!(f, "no tuple has an id between {} and {}", after, before)
+            }
+        }
+    }
+}
+
+/// Look for advisory issues in the tuples
+///
+/// Unlike validation, which rejects inputs that can't be loaded, linting only points at data that
+/// is likely a mistake. Warnings are grouped by kind and sorted by name.
+pub fn lint_problem(config: &AlgorithmConfig, tuples: &[Tuple]) -> Vec<LintWarning> {
+    let periods = config.number_of_periods;
+
+    let mut classes_by_teacher: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut classes_by_room: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut classes_by_label: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut teachers_by_room: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+
+    for tuple in tuples {
+        *classes_by_teacher.entry(&tuple.teacher).or_default() += 1;
+        *classes_by_room.entry(&tuple.room).or_default() += 1;
+        *classes_by_label.entry(&tuple.label).or_default() += 1;
+        teachers_by_room
+            .entry(&tuple.room)
+            .or_default()
+            .insert(&tuple.teacher);
+    }
+
+    let mut warnings = Vec::new();
+
+    warnings.extend(
+        classes_by_teacher
+            .iter()
+            .filter(|(_, classes)| **classes > periods)
+            .map(|(teacher, classes)| LintWarning::TeacherOverbooked {
+                teacher: teacher.to_string(),
+                classes: *classes,
+                periods,
+            }),
+    );
+    warnings.extend(
+        classes_by_room
+            .iter()
+            .filter(|(_, classes)| **classes > periods)
+            .map(|(room, classes)| LintWarning::RoomOverbooked {
+                room: room.to_string(),
+                classes: *classes,
+                periods,
+            }),
+    );
+    warnings.extend(
+        classes_by_teacher
+            .iter()
+            .filter(|(_, classes)| **classes == 1)
+            .map(|(teacher, _)| LintWarning::TeacherWithSingleClass {
+                teacher: teacher.to_string(),
+            }),
+    );
+    warnings.extend(
+        classes_by_label
+            .iter()
+            .filter(|(_, classes)| **classes == 1)
+            .map(|(label, _)| LintWarning::SubjectWithSingleClass {
+                label: label.to_string(),
+            }),
+    );
+    warnings.extend(
+        teachers_by_room
+            .iter()
+            .filter(|(_, teachers)| teachers.len() == 1)
+            .map(|(room, teachers)| LintWarning::RoomWithSingleTeacher {
+                room: room.to_string(),
+                teacher: teachers.first().unwrap().to_string(),
+            }),
+    );
+
+    let ids: BTreeSet<i32> = tuples.iter().map(|tuple| tuple.id).collect();
+    warnings.extend(
+        ids.iter()
+            .zip(ids.iter().skip(1))
+            .filter(|(after, before)| **before - **after > 1)
+            .map(|(after, before)| LintWarning::IdGap {
+                after: *after,
+                before: *before,
+            }),
+    );
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::testing::tuple;
+
+    #[test]
+    fn test_overbooked_teacher_and_room_pairing() {
+        let config = AlgorithmConfig {
+            number_of_periods: 2,
+            ..AlgorithmConfig::default()
+        };
+        // the same teacher in the same room three times can't fit into two periods
+        let tuples = vec![
+            tuple(1, "Math", "A", "Smith"),
+            tuple(2, "Math", "A", "Smith"),
+            tuple(3, "Math", "A", "Smith"),
+        ];
+
+        assert_eq!(
+            lint_problem(&config, &tuples),
+            vec![
+                LintWarning::TeacherOverbooked {
+                    teacher: "Smith".to_string(),
+                    classes: 3,
+                    periods: 2
+                },
+                LintWarning::RoomOverbooked {
+                    room: "A".to_string(),
+                    classes: 3,
+                    periods: 2
+                },
+                LintWarning::RoomWithSingleTeacher {
+                    room: "A".to_string(),
+                    teacher: "Smith".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_single_class_warnings() {
+        let tuples = vec![
+            tuple(1, "Math", "A", "Smith"),
+            tuple(2, "Math", "A", "Jones"),
+            tuple(3, "Art", "A", "Smith"),
+        ];
+
+        let warnings = lint_problem(&AlgorithmConfig::default(), &tuples);
+
+        assert_eq!(
+            warnings,
+            vec![
+                LintWarning::TeacherWithSingleClass {
+                    teacher: "Jones".to_string()
+                },
+                LintWarning::SubjectWithSingleClass {
+                    label: "Art".to_string()
+                },
+            ]
+        );
+        assert_eq!(warnings[0].to_string(), "teacher Jones has a single class");
+    }
+
+    #[test]
+    fn test_gaps_between_ids() {
+        let tuples = vec![
+            tuple(1, "Math", "A", "Smith"),
+            tuple(2, "Math", "B", "Smith"),
+            tuple(5, "Math", "A", "Jones"),
+            tuple(6, "Math", "B", "Jones"),
+        ];
+
+        let warnings = lint_problem(&AlgorithmConfig::default(), &tuples);
+
+        assert_eq!(
+            warnings,
+            vec![LintWarning::IdGap {
+                after: 2,
+                before: 5
+            }]
+        );
+        assert_eq!(
+            warnings[0].to_string(),
+            "no tuple has an id between 2 and 5"
+        );
+    }
+}
@@ -3,52 +3,110 @@ use rand::distributions::WeightedIndex;
 use rand::prelude::*;
 use rand::seq::IteratorRandom;
 use rayon::prelude::*;
-use std::cmp::min;
+use std::{
+    cmp::min,
+    collections::{HashMap, HashSet},
+    sync::atomic::{AtomicU64, Ordering},
+};
+use tracing::trace;
 
 use rand::Rng;
 
 use self::{
-    config::AlgorithmConfig,
-    datatypes::{Chromosome, Individual, Population, Tuple},
-    random::get_random_generator,
+    config::{AlgorithmConfig, CoolingSchedule, CrossoverMethod, MutationMethod, SelectionMethod},
+    datatypes::{
+        adaptation_to_f64, compare_adaptation, compare_score, sort_by_adaptation, Adaptation,
+        CachedPeriodFitness, Chromosome, CompletenessError, Conflict, ConflictType,
+        EffectiveParameters, FitnessBreakdown, Gene, Individual, Locks, Population, Score,
+        SoftObjectivePenalty, Timetable, Tuple, TupleIndex, Violation,
+    },
+    random::{get_random_generator, TaskGenerators},
+    report::{SelectionRecord, SelectionTrace},
 };
 
+pub mod checkpoint;
+#[cfg(feature = "compact-genes")]
+pub mod compact;
 pub mod config;
 pub mod datatypes;
-mod random;
+pub mod engine;
+pub mod export;
+pub mod lint;
+pub mod random;
+pub mod report;
+pub mod stopping;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+/// Population size suited to the size of the problem
+///
+/// The search space grows with both the number of tuples and periods, so the suggestion is
+/// `20 * sqrt(tuples * periods)`, which gives 1 000 individuals for 625 tuples in 4 periods. It is
+/// kept between 50, below which the first population has too little diversity, and 10 000, above
+/// which a generation gets slow without improving the results much.
+pub fn suggest_population_size(config: &AlgorithmConfig, tuples: &[Tuple]) -> usize {
+    let search_space = (tuples.len() * config.number_of_periods) as f64;
+
+    ((20.0 * search_space.sqrt()).round() as usize).clamp(50, 10_000)
+}
 
 /// Create a first population
 ///
 /// Create a population of size `population_size` with each individual having `number_of_periods`
 /// periods.
 /// Then assign tuple to a random period of individual
+///
+/// Individuals are created in parallel. With [`AlgorithmConfig::min_initial_distance`] set they
+/// are created one by one instead, and an individual closer than that to any individual created
+/// before is regenerated, at most [`AlgorithmConfig::max_initial_attempts`] times. With
+/// [`AlgorithmConfig::random_seed`] set the population is always the same.
 pub fn create_first_population(config: &AlgorithmConfig, tuples: &[Tuple]) -> Population {
+    create_first_population_with_locks(config, tuples, &Locks::new())
+}
+
+/// [`create_first_population`] placing the genes in `locks` in the period they are pinned to
+pub fn create_first_population_with_locks(
+    config: &AlgorithmConfig,
+    tuples: &[Tuple],
+    locks: &Locks,
+) -> Population {
     let AlgorithmConfig {
         population_size,
         number_of_periods,
+        min_initial_distance,
+        max_initial_attempts,
         ..
     } = config.to_owned();
 
-    let mut population = Population::with_capacity(population_size);
-
-    let mut rng = get_random_generator();
+    let random_individual =
+        |rng: &mut StdRng| random_individual(number_of_periods, tuples, locks, rng);
 
-    for _ in 0..population_size {
-        let mut individual: Individual = Individual::new(number_of_periods);
+    if min_initial_distance == 0 {
+        // individuals are independent, so they are created in parallel, each with a generator
+        // derived from its position
+        return (0..population_size)
+            .into_par_iter()
+            .map_init(TaskGenerators::default, |generators, index| {
+                random_individual(generators.get(config.random_seed, &[index as u64]))
+            })
+            .collect();
+    }
 
-        // create periods
-        for period_id in 0..number_of_periods {
-            let period = Chromosome::new(period_id.try_into().unwrap());
+    // every individual is compared with the ones created before it
+    let mut population = Population::with_capacity(population_size);
+    let mut rng = get_random_generator(config.random_seed, &[]);
 
-            individual.chromosomes.push(period);
-        }
+    for _ in 0..population_size {
+        let mut individual = random_individual(&mut rng);
 
-        // assign tuple to a random period from individual
-        for tuple in tuples {
-            let random_period_index = rng.gen_range(0..number_of_periods);
-            individual.chromosomes[random_period_index]
-                .genes
-                .push(tuple.id);
+        for _ in 0..max_initial_attempts {
+            if population
+                .iter()
+                .all(|other| genome_distance(&individual, other) >= min_initial_distance)
+            {
+                break;
+            }
+            individual = random_individual(&mut rng);
         }
 
         population.push(individual)
@@ -57,6 +115,98 @@ pub fn create_first_population(config: &AlgorithmConfig, tuples: &[Tuple]) -> Po
     population
 }
 
+/// Individual with every tuple assigned to a random one of `number_of_periods` periods, or to the
+/// period it is locked to
+fn random_individual(
+    number_of_periods: usize,
+    tuples: &[Tuple],
+    locks: &Locks,
+    rng: &mut StdRng,
+) -> Individual {
+    let mut individual: Individual = Individual::new(number_of_periods);
+
+    // create periods
+    for period_id in 0..number_of_periods {
+        let period = Chromosome::new(period_id.try_into().unwrap());
+
+        individual.chromosomes.push(period);
+    }
+
+    // assign tuple to a random period from individual
+    for tuple in tuples {
+        let random_period_index = match locks.get(&tuple.id) {
+            Some(period_index) if *period_index < number_of_periods => *period_index,
+            _ => rng.gen_range(0..number_of_periods),
+        };
+        individual.chromosomes[random_period_index]
+            .genes
+            .push(tuple.id);
+    }
+
+    individual
+}
+
+/// Average share of genes two individuals place in different periods, over all pairs of
+/// individuals. `0` when the whole population is the same timetable, close to
+/// `1 - 1 / number_of_periods` for a random one
+///
+/// Same as averaging [`genome_distance`] over all pairs for individuals with the same genes, but
+/// linear in the size of the population: every gene is placed differently by all pairs except the
+/// ones placing it in the same period, which are counted from the individuals in each period.
+pub fn population_diversity(population: &[Individual]) -> f64 {
+    let mut placements: HashMap<(Gene, i32), usize> = HashMap::new();
+    for individual in population {
+        for period in &individual.chromosomes {
+            for gene in &period.genes {
+                *placements.entry((*gene, period.id)).or_default() += 1;
+            }
+        }
+    }
+
+    let genes = placements.keys().map(|(gene, _)| gene).unique().count();
+    let pairs = |count: usize| (count * count.saturating_sub(1) / 2) as f64;
+    let all_pairs = pairs(population.len()) * genes as f64;
+    if all_pairs == 0.0 {
+        return 0.0;
+    }
+
+    let same_placement: f64 = placements.values().map(|count| pairs(*count)).sum();
+    1.0 - same_placement / all_pairs
+}
+
+/// Evaluated random individuals to replace the weakest ones of a population that lost its
+/// diversity, see [`integrate_migrants`]
+///
+/// [`AlgorithmConfig::immigrant_count`] immigrants are created when the [`population_diversity`]
+/// is below [`AlgorithmConfig::diversity_threshold`], none otherwise. Their generators are derived
+/// from the generation and the positions after the end of the population, so they never share
+/// numbers with the children of the generation. Genes in `locks` are placed in their periods.
+pub fn random_immigrants(
+    config: &AlgorithmConfig,
+    population: &[Individual],
+    generation: usize,
+    tuples: &TupleIndex,
+    locks: &Locks,
+) -> Population {
+    if config.immigrant_count == 0 || population_diversity(population) >= config.diversity_threshold
+    {
+        return Population::new();
+    }
+
+    (0..config.immigrant_count.min(population.len()))
+        .into_par_iter()
+        .map_init(TaskGenerators::default, |generators, index| {
+            let rng = generators.get(
+                config.random_seed,
+                &[generation as u64, (population.len() + index) as u64],
+            );
+            let mut immigrant = random_individual(config.number_of_periods, tuples, locks, rng);
+            immigrant.adaptation = calculate_fitness_cached(config, &mut immigrant, tuples);
+            immigrant
+        })
+        .collect()
+}
+
 /// Get parents from the current population
 ///
 /// Can't use roulette wheel selection because the population is big but
@@ -67,35 +217,60 @@ pub fn create_first_population(config: &AlgorithmConfig, tuples: &[Tuple]) -> Po
 /// less adapted individuals are selected with relatively high probability.
 ///
 /// Instead, we sort the population by adaptation descending.
-/// Then we apply exponent function (a * e^x + b) to the index of the individual in the sorted population.
-/// Controlling the a and b parameters we can control the probability of selecting the individual.
-/// Current values are selected by trial and error.
+/// Then we apply exponent function e^(-a * x + b) to the index of the individual in the sorted
+/// population. Controlling the a and b parameters we can control the probability of selecting the
+/// individual. Default values are selected by trial and error, `decay` is the a parameter (see
+/// [`selection_decay`]) and `offset` the b parameter ([`AlgorithmConfig::selection_offset`]).
 /// Then we apply roulette wheel selection to select the parents making sure that the parents are different.
-pub fn rand_parents(parents: &Population) -> (&Individual, &Individual) {
+///
+/// That is [`SelectionMethod::ExponentialRank`], the other `method`s only change how a rank is
+/// drawn (see [`sample_rank`]).
+///
+/// The chosen ranks are recorded into `trace` when given, `generation` is only used for that.
+pub fn rand_parents<'a>(
+    parents: &'a Population,
+    method: SelectionMethod,
+    decay: f64,
+    offset: f64,
+    generation: usize,
+    trace: Option<&SelectionTrace>,
+    rng: &mut impl Rng,
+) -> (&'a Individual, &'a Individual) {
     assert!(parents.len() > 1);
 
-    let mut rng = get_random_generator();
-
     let sorted_parents = parents
         .into_iter()
-        .sorted_by(|a, b| b.adaptation.partial_cmp(&a.adaptation).unwrap())
+        .sorted_by(|a, b| compare_score(&b.score(), &a.score()))
         .collect::<Vec<_>>();
 
-    let weights = (0..sorted_parents.len())
-        .map(|x| f64::exp((-0.3f64 * x as f64) + 2f64))
-        .collect::<Vec<_>>();
+    let weights: Option<Vec<f64>> = match method {
+        SelectionMethod::ExponentialRank => Some(
+            (0..sorted_parents.len())
+                .map(|x| selection_weight(decay, offset, x))
+                .collect(),
+        ),
+        SelectionMethod::RankLinear => Some(
+            (0..sorted_parents.len())
+                .map(|x| (sorted_parents.len() - x) as f64)
+                .collect(),
+        ),
+        SelectionMethod::Tournament { .. } => None,
+    };
 
-    let dist = WeightedIndex::new(weights.clone()).unwrap();
+    let dist = weights.map(|weights| WeightedIndex::new(weights).unwrap());
 
-    let idx1 = dist.sample(&mut rng);
+    let idx1 = sample_rank(method, dist.as_ref(), sorted_parents.len(), None, rng);
 
-    // Sample the second index ensuring its different from the first
-    let idx2 = loop {
-        let idx = dist.sample(&mut rng);
-        if idx != idx1 {
-            break idx;
-        }
-    };
+    // the second parent is drawn from the remaining ranks, so it always differs from the first
+    let idx2 = sample_rank(method, dist.as_ref(), sorted_parents.len(), Some(idx1), rng);
+
+    if let Some(trace) = trace {
+        trace.record(SelectionRecord {
+            generation,
+            first: idx1,
+            second: idx2,
+        });
+    }
 
     // println!(
     //     "Min: {}, Max: {}, Parent 1 weights: {}, Parent 2 weights: {}, Parent 1 weight: {}, Parent 2 weight: {}",
@@ -108,6 +283,46 @@ pub fn rand_parents(parents: &Population) -> (&Individual, &Individual) {
     );
 }
 
+/// Draw a rank of the sorted population other than `excluded`
+///
+/// Weighted methods sample `dist`, a tournament draws `size` ranks uniformly (with replacement)
+/// and keeps the fittest of them, the lowest rank. A tournament larger than the population draws
+/// as many ranks as the population has. The `excluded` rank gets no weight, and when
+/// no other rank has any weight left the rank is drawn uniformly instead.
+fn sample_rank(
+    method: SelectionMethod,
+    dist: Option<&WeightedIndex<f64>>,
+    population_size: usize,
+    excluded: Option<usize>,
+    rng: &mut impl Rng,
+) -> usize {
+    let uniform = |rng: &mut dyn RngCore| {
+        let rank = rng.gen_range(0..population_size - usize::from(excluded.is_some()));
+        match excluded {
+            Some(excluded) if rank >= excluded => rank + 1,
+            _ => rank,
+        }
+    };
+
+    match (method, dist) {
+        (SelectionMethod::Tournament { size }, _) => (0..size.clamp(1, population_size))
+            .map(|_| uniform(rng))
+            .min()
+            .unwrap(),
+        (_, Some(dist)) => match excluded {
+            None => dist.sample(rng),
+            Some(excluded) => {
+                let mut remaining = dist.clone();
+                match remaining.update_weights(&[(excluded, &0.0)]) {
+                    Ok(()) => remaining.sample(rng),
+                    Err(_) => uniform(rng),
+                }
+            }
+        },
+        (_, None) => unreachable!("weighted selection methods have a distribution"),
+    }
+}
+
 /// Crossover two parents to create a child
 ///
 /// We are choosing random parents from the readonly current population. Then for each corresponding
@@ -116,40 +331,141 @@ pub fn rand_parents(parents: &Population) -> (&Individual, &Individual) {
 /// 1. Missing genes. To solve it we are adding missing genes to the random period.
 /// 2. Duplicated genes. To solve it we are removing duplicated genes from the periods.
 ///
-/// There is most likely a bug in Rust or Rayon as when we use par_bridge instead of (collect, par_iter)
-/// the assert fails meaning it selects items from `mother` and `father` in different order.
-pub fn crossover(config: &AlgorithmConfig, population: &Population) -> Individual {
+/// When [`AlgorithmConfig::crossover_fitness_bias`] is positive the mating point is no longer
+/// uniform, it is biased so that the fitter parent contributes a larger part of every period
+/// (see [`fitness_share`]).
+///
+/// Genes in `locks` don't take part in the splicing, they are put straight into the period they
+/// are locked to. The child respects all locks by construction and the repair never has to move
+/// a locked gene.
+///
+/// With [`CrossoverMethod::SinglePoint`] parents with the same genes and period sizes are
+/// recombined by [`swap_crossover`] instead, which needs no repair.
+///
+/// Selection decisions are recorded into `trace` when given, see [`rand_parents`].
+///
+/// Only [`AlgorithmConfig::crossover_probability`] of the children are mated, the rest are copies
+/// of the mother.
+///
+/// Every period is mated by [`mate_period`]. The periods are collected before `par_iter`, as
+/// `par_bridge` wouldn't keep them in order.
+pub fn crossover(
+    config: &AlgorithmConfig,
+    population: &Population,
+    generation: usize,
+    locks: &Locks,
+    trace: Option<&SelectionTrace>,
+    rng: &mut impl Rng,
+) -> Individual {
+    crossover_with_primary_parent(config, population, generation, locks, trace, rng).0
+}
+
+/// [`crossover`] also returning the parent the child takes most genes from, the one on the left
+/// side of the mating point unless the fitness bias favours the other one
+fn crossover_with_primary_parent<'a>(
+    config: &AlgorithmConfig,
+    population: &'a Population,
+    generation: usize,
+    locks: &Locks,
+    trace: Option<&SelectionTrace>,
+    rng: &mut impl Rng,
+) -> (Individual, &'a Individual) {
     let AlgorithmConfig {
-        number_of_periods, ..
+        crossover_fitness_bias,
+        ..
     } = config.to_owned();
 
-    let (mother, father) = rand_parents(population);
+    let (mother, father) = rand_parents(
+        population,
+        config.selection_method,
+        selection_decay(config, generation),
+        config.selection_offset,
+        generation,
+        trace,
+        rng,
+    );
+
+    if !rng.gen_bool(config.crossover_probability.clamp(0.0, 1.0).into()) {
+        return (mother.clone(), mother);
+    }
+
+    let left_share = fitness_share(mother.adaptation, father.adaptation, crossover_fitness_bias);
+    let primary_parent = if left_share >= 0.5 { mother } else { father };
+
+    if config.crossover_method == CrossoverMethod::SinglePoint {
+        if let Some(mut child) = swap_crossover(
+            mother,
+            father,
+            left_share,
+            crossover_fitness_bias,
+            locks,
+            rng,
+        ) {
+            inherit_period_fitness(&mut child, [mother, father]);
+            return (child, primary_parent);
+        }
+    }
+    if config.crossover_method == CrossoverMethod::PartiallyMapped {
+        let mut child = partially_mapped_crossover(
+            mother,
+            father,
+            left_share,
+            crossover_fitness_bias,
+            locks,
+            rng,
+        );
+        inherit_period_fitness(&mut child, [mother, father]);
+        return (child, primary_parent);
+    }
+
+    let all_genes: Vec<Gene> = mother
+        .chromosomes
+        .iter()
+        .flat_map(|g| g.genes.iter().cloned())
+        .collect();
+
+    // periods are mated in parallel, each with its own generator, which keeps a seeded run
+    // reproducible whichever thread mates the period
+    let period_seeds: Vec<u64> = (0..mother.chromosomes.len()).map(|_| rng.gen()).collect();
 
     let mut child: Individual = Individual::with_chromosomes(
         std::iter::zip(mother.chromosomes.iter(), father.chromosomes.iter())
+            .enumerate()
             .collect::<Vec<_>>()
             .par_iter()
             // .par_bridge()
-            .map(|(mother_chromosome, father_chromosome)| {
+            .map(|(index, (mother_chromosome, father_chromosome))| {
                 assert_eq!(mother_chromosome.id, father_chromosome.id);
-                let mut rng = get_random_generator();
+                let mut rng = StdRng::seed_from_u64(period_seeds[*index]);
 
                 let id = mother_chromosome.id;
 
-                let mother_genes = &father_chromosome.genes;
-                let father_genes = &mother_chromosome.genes;
-
-                let mating_point_upper_bound = min(mother_genes.len(), father_genes.len());
-
-                let mating_point = rng.gen_range(0..=mating_point_upper_bound);
-
-                let (mother_left, _) = mother_genes.split_at(mating_point);
-                let (_, father_right) = father_genes.split_at(mating_point);
-                let child_genes = mother_left
+                let unlocked = |genes: &Vec<Gene>| {
+                    genes
+                        .iter()
+                        .filter(|gene| !locks.contains_key(gene))
+                        .cloned()
+                        .collect::<Vec<_>>()
+                };
+                let mother_genes = &unlocked(&mother_chromosome.genes);
+                let father_genes = &unlocked(&father_chromosome.genes);
+
+                let inherited = mate_period(
+                    config.crossover_method,
+                    mother_genes,
+                    father_genes,
+                    left_share,
+                    crossover_fitness_bias,
+                    &mut rng,
+                );
+                let locked_here = locks
                     .iter()
-                    .chain(father_right.iter())
-                    .cloned()
-                    .collect::<Vec<_>>();
+                    .filter(|(gene, period_index)| {
+                        **period_index == *index && all_genes.contains(gene)
+                    })
+                    .map(|(gene, _)| *gene)
+                    .sorted();
+                let child_genes = locked_here.chain(inherited).collect::<Vec<_>>();
 
                 Chromosome {
                     id,
@@ -162,22 +478,14 @@ pub fn crossover(config: &AlgorithmConfig, population: &Population) -> Individua
     // at this point there could be duplicated and missing genes, so we want to fix this
 
     // repair lost
-    let all_genes: Vec<i32> = mother
-        .chromosomes
-        .iter()
-        .flat_map(|g| g.genes.iter().cloned())
-        .collect();
-
     let lost_genes: Vec<i32> = all_genes
         .iter()
         .filter(|g| !child.chromosomes.iter().any(|c| c.genes.contains(g)))
         .cloned()
         .collect();
 
-    let mut rng = get_random_generator();
-
     for gene in lost_genes {
-        let period_id = rng.gen_range(0..number_of_periods);
+        let period_id = repair_period(config, &child.chromosomes, rng);
         child.chromosomes[period_id].genes.push(gene);
     }
 
@@ -188,129 +496,3261 @@ pub fn crossover(config: &AlgorithmConfig, population: &Population) -> Individua
         period.genes.retain(|x| seen.insert(x.clone()));
     }
 
+    inherit_period_fitness(&mut child, [mother, father]);
+
+    (child, primary_parent)
+}
+
+/// Keep the cached fitness of periods inherited unchanged from a parent, the rest are dirty
+fn inherit_period_fitness(child: &mut Individual, parents: [&Individual; 2]) {
+    child.period_fitness = child
+        .chromosomes
+        .iter()
+        .enumerate()
+        .map(|(index, period)| {
+            parents
+                .into_iter()
+                .find(|parent| parent.chromosomes[index].genes == period.genes)
+                .and_then(|parent| parent.period_fitness.get(index).copied())
+                .unwrap_or_default()
+        })
+        .collect();
+}
+
+/// Genes a period of the child inherits from the same period of its parents, which can lose or
+/// duplicate genes the [`crossover`] repairs afterwards
+///
+/// [`CrossoverMethod::SinglePoint`] takes the genes left of the [`mating_point`] from `left` and
+/// the rest from `right`. [`CrossoverMethod::Uniform`] takes every position from `left` with the
+/// chance of `left_share`, which is even without a fitness bias, and from `right` otherwise. A
+/// position only one parent has is dropped when the other parent is chosen.
+fn mate_period(
+    method: CrossoverMethod,
+    left: &[Gene],
+    right: &[Gene],
+    left_share: f64,
+    bias: f32,
+    rng: &mut impl Rng,
+) -> Vec<Gene> {
+    match method {
+        CrossoverMethod::SinglePoint => {
+            let point = mating_point(rng, min(left.len(), right.len()), left_share, bias);
+            left[..point]
+                .iter()
+                .chain(&right[point..])
+                .copied()
+                .collect()
+        }
+        CrossoverMethod::Uniform => (0..left.len().max(right.len()))
+            .filter_map(|index| {
+                if rng.gen_bool(left_share) {
+                    left.get(index)
+                } else {
+                    right.get(index)
+                }
+            })
+            .copied()
+            .collect(),
+        CrossoverMethod::PartiallyMapped => {
+            unreachable!("partially mapped crossover mates whole individuals")
+        }
+    }
+}
+
+/// Number of genes a period of the child takes from the left parent
+///
+/// Uniform in `0..=upper_bound` without a bias, otherwise the left part gets the expected share
+/// of `left_share`.
+fn mating_point(rng: &mut impl Rng, upper_bound: usize, left_share: f64, bias: f32) -> usize {
+    if bias > 0.0 {
+        // U^a has the expected value 1 / (a + 1), so this gives the left part the
+        // expected share of `left_share`
+        let exponent = 1.0 / left_share - 1.0;
+        let fraction = rng.gen::<f64>().powf(exponent);
+        (fraction * upper_bound as f64).round() as usize
+    } else {
+        rng.gen_range(0..=upper_bound)
+    }
+}
+
+/// Crossover treating the individuals as assignments of genes to periods, without any repair
+///
+/// The genes of `left` are ordered by id and a segment of them is mapped to the periods `right`
+/// puts them in, the rest stay in their period of `left`. The segment covers the expected share
+/// of `1 - left_share` of the genes, see [`mating_point`]. Every gene is placed exactly once, so
+/// unlike the splicing of [`crossover`] nothing has to be repaired. Genes missing from `right`
+/// and genes in `locks` stay where `left` or the lock puts them.
+pub fn partially_mapped_crossover(
+    left: &Individual,
+    right: &Individual,
+    left_share: f64,
+    bias: f32,
+    locks: &Locks,
+    rng: &mut impl Rng,
+) -> Individual {
+    let mut child = Individual::with_chromosomes(
+        left.chromosomes
+            .iter()
+            .map(|period| Chromosome::new(period.id))
+            .collect(),
+    );
+    for (gene, target) in partially_mapped_targets(left, right, left_share, bias, locks, rng) {
+        child.chromosomes[target].genes.push(gene);
+    }
+
     child
 }
 
-/// Mutate the individual
+/// Every gene of `left` with the index of the period a [`partially_mapped_crossover`] puts it in,
+/// in the order of the periods and genes of `left`
 ///
-/// Typically, mutation probability determines the probability of individual mutation.
-/// In this case, mutation probability determines the probability of chromosome mutation, so it is
-/// good idea to keep it small.
+/// Reads both parents through [`Timetable`], so the child can be built in either layout.
+fn partially_mapped_targets(
+    left: &impl Timetable,
+    right: &impl Timetable,
+    left_share: f64,
+    bias: f32,
+    locks: &Locks,
+    rng: &mut impl Rng,
+) -> Vec<(Gene, usize)> {
+    let period_in_right: HashMap<Gene, usize> = right
+        .period_genes()
+        .enumerate()
+        .flat_map(|(index, genes)| genes.iter().map(move |gene| (*gene, index)))
+        .collect();
+
+    let genes: Vec<Gene> = left
+        .period_genes()
+        .flatten()
+        .copied()
+        .sorted_unstable()
+        .collect();
+    let mapped_count = genes.len() - mating_point(rng, genes.len(), left_share, bias);
+    let start = rng.gen_range(0..=genes.len() - mapped_count);
+    let mapped: HashSet<Gene> = genes[start..start + mapped_count].iter().copied().collect();
+
+    let number_of_periods = left.period_genes().count();
+    left.period_genes()
+        .enumerate()
+        .flat_map(|(index, genes)| genes.iter().map(move |gene| (*gene, index)))
+        .map(|(gene, index)| {
+            let target = locks
+                .get(&gene)
+                .or_else(|| {
+                    period_in_right
+                        .get(&gene)
+                        .filter(|_| mapped.contains(&gene))
+                })
+                .copied()
+                .filter(|target| *target < number_of_periods)
+                .unwrap_or(index);
+            (gene, target)
+        })
+        .collect()
+}
+
+/// Crossover of parents that differ only in the placement of their genes, without any repair
 ///
-/// For each period, we are checking if the mutation should occur. If it should, we are removing
-/// a random gene from the period and adding it to a random period.
-pub fn mutate(config: &AlgorithmConfig, individual: &mut Individual) {
-    let mutation_probability = config.mutation_probability;
-    let number_of_periods = usize::try_from(config.number_of_periods).unwrap();
+/// Applies when both parents have the same genes, the same number of them in every period and
+/// respect the `locks`, which is common late in the run. The child starts as a copy of `left` and
+/// every gene right of the mating point of a period in `right` is swapped into that period, in
+/// exchange for a gene of the child that isn't locked or placed this way yet. Such a gene always
+/// exists, because all of them belong to the same period of `right`. Swaps keep the genes and the
+/// period sizes, so nothing is lost or duplicated. Returns `None` when the parents don't qualify.
+pub fn swap_crossover(
+    left: &Individual,
+    right: &Individual,
+    left_share: f64,
+    bias: f32,
+    locks: &Locks,
+    rng: &mut impl Rng,
+) -> Option<Individual> {
+    let same_sizes = left.chromosomes.len() == right.chromosomes.len()
+        && std::iter::zip(&left.chromosomes, &right.chromosomes)
+            .all(|(l, r)| l.genes.len() == r.genes.len());
+    if !same_sizes || !left.respects_locks(locks) || !right.respects_locks(locks) {
+        return None;
+    }
+
+    let sorted_genes = |individual: &Individual| {
+        individual
+            .period_genes()
+            .flatten()
+            .copied()
+            .sorted_unstable()
+            .collect::<Vec<_>>()
+    };
+    if sorted_genes(left) != sorted_genes(right) {
+        return None;
+    }
 
-    let mut rng = get_random_generator();
+    let mut child = Individual::with_chromosomes(left.chromosomes.clone());
 
-    for period_id in 0..number_of_periods {
-        if rng.gen_bool(mutation_probability.into()) {
-            let gene_count = individual.chromosomes[period_id].genes.len();
+    let mut period_of: HashMap<Gene, usize> = child
+        .chromosomes
+        .iter()
+        .enumerate()
+        .flat_map(|(index, period)| period.genes.iter().map(move |gene| (*gene, index)))
+        .collect();
+    let mut placed: std::collections::HashSet<Gene> = locks.keys().copied().collect();
 
-            if gene_count == 0 {
-                continue;
-            }
+    for (index, right_period) in right.chromosomes.iter().enumerate() {
+        let point = mating_point(rng, right_period.genes.len(), left_share, bias);
 
-            let gene_index = rng.gen_range(0..gene_count);
+        for gene in &right_period.genes[point..] {
+            let current = period_of[gene];
 
-            let gene = individual.chromosomes[period_id].genes.remove(gene_index);
+            if current != index {
+                let exchanged_index = child.chromosomes[index]
+                    .genes
+                    .iter()
+                    .positions(|g| !placed.contains(g))
+                    .choose(rng)
+                    .expect("a period always has a gene that wasn't placed");
+                let exchanged = child.chromosomes[index].genes[exchanged_index];
 
-            // remove gene from current period
-            individual.chromosomes[period_id]
-                .genes
-                .retain(|g| g != &gene);
+                let gene_index = child.chromosomes[current]
+                    .genes
+                    .iter()
+                    .position(|g| g == gene)
+                    .unwrap();
 
-            // add gene to random period
-            individual
-                .chromosomes
-                .iter_mut()
-                .filter(|target| target.id != i32::try_from(period_id).unwrap())
-                .choose(&mut rng)
-                .unwrap()
-                .genes
-                .push(gene);
+                child.chromosomes[index].genes[exchanged_index] = *gene;
+                child.chromosomes[current].genes[gene_index] = exchanged;
+                period_of.insert(*gene, index);
+                period_of.insert(exchanged, current);
+            }
+
+            placed.insert(*gene);
         }
     }
+
+    Some(child)
 }
 
-/// Calculate fitness of the individual
+/// Number of children created every generation according to [`AlgorithmConfig::generation_gap`]
 ///
-/// For every period in individual we are checking 2 rules:
-/// 1) If the same teacher is teaching more than one class at the same time decrease fitness by 10
-/// 2) If different teachers occupy the same room at the same time decrease fitness by 20
-pub fn calculate_fitness(individual: &Individual, tuples: &Vec<Tuple>, debug: bool) -> i32 {
-    let mut individual_fitness = 0;
+/// At least one child is always created.
+pub fn offspring_count(config: &AlgorithmConfig) -> usize {
+    let gap = config.generation_gap.clamp(0.0, 1.0) as f64;
 
-    for period in &individual.chromosomes {
-        // if teacher is teaching more than one class at the same time decrease fitness by 10
+    ((gap * config.population_size as f64).round() as usize).clamp(1, config.population_size)
+}
 
-        let genes = &period.genes;
+/// Replace the weakest individuals of a sorted island with migrants from another island
+///
+/// At most the whole island is replaced. The island is sorted again afterwards.
+pub fn integrate_migrants(island: &mut Population, migrants: Population) {
+    let replaced = migrants.len().min(island.len());
+    island.truncate(island.len() - replaced);
+    island.extend(migrants.into_iter().take(replaced));
+    sort_by_adaptation(island);
+}
 
-        for gene_id in genes {
-            // if the same teacher is teaching more than one class at the same time decrease fitness by 10,
-            // if different teachers occupy the same room at the same time decrease fitness by 20
+/// Replace every individual without `number_of_periods` periods by a copy of the fittest one
+/// that has them
+///
+/// Such an individual serializes to a different size than the rest, so it can't be sent to other
+/// nodes. The population keeps its length, so every node still receives the same number of
+/// individuals. Returns the indices of the replaced individuals, none are replaced when no
+/// individual has the right number of periods.
+pub fn repair_period_counts(population: &mut [Individual], number_of_periods: usize) -> Vec<usize> {
+    let Some(replacement) = population
+        .iter()
+        .filter(|individual| individual.chromosomes.len() == number_of_periods)
+        .max_by(|a, b| compare_score(&a.score(), &b.score()))
+        .cloned()
+    else {
+        return Vec::new();
+    };
 
-            // additional rules may be added, for example,
-            // the division of lectures by type of classes, if the types of classes differ for the
-            // same lecture, reduce the suitability by a smaller value
+    let malformed: Vec<usize> = population
+        .iter()
+        .positions(|individual| individual.chromosomes.len() != number_of_periods)
+        .collect();
+    for index in &malformed {
+        population[*index] = replacement.clone();
+    }
 
-            let tuple = tuples
-                .iter()
-                .find(|t| t.id == *gene_id)
-                .expect(format!("Tuple with id {} not found", *gene_id).as_str());
+    malformed
+}
 
-            let other_classes = tuples
-                .iter()
-                .filter(|t| genes.contains(&t.id))
-                .filter(|t| t.id != tuple.id);
+/// Create the next generation of a shard of the population
+///
+/// `population` is the whole current population sorted by adaptation and `shard` is the part of it
+/// processed by this node, `positions` holds the index in `population` of every shard element.
+/// Individuals ranked within the survivors (see [`offspring_count`]) are carried over, the rest are
+/// replaced by new evaluated children. Children whose hard adaptation is below
+/// [`AlgorithmConfig::dead_threshold`] are
+/// created again, at most [`AlgorithmConfig::max_dead_retries`] times, then the individual they
+/// would replace is kept, so no new individual below the threshold enters the population. With
+/// [`AlgorithmConfig::min_child_improvement`] set, a child not improving enough on its primary
+/// parent is replaced by a copy of that parent. Every child evaluated, the ones created again
+/// included, is counted in `evaluations`.
+#[allow(clippy::too_many_arguments)]
+pub fn evolve_shard(
+    config: &AlgorithmConfig,
+    population: &Population,
+    shard: &[Individual],
+    positions: &[usize],
+    generation: usize,
+    tuples: &TupleIndex,
+    locks: &Locks,
+    trace: Option<&SelectionTrace>,
+    evaluations: Option<&AtomicU64>,
+) -> Population {
+    let survivors = config.population_size - offspring_count(config);
+
+    shard
+        .par_iter()
+        .enumerate()
+        .map_init(
+            TaskGenerators::default,
+            |generators, (index, individual)| {
+                if positions[index] < survivors {
+                    return individual.clone();
+                }
 
-            // get count of tuples with the same teacher
-            let same_teacher_different_classes_count = other_classes
-                .clone()
-                .filter(|t| t.room == tuple.room)
-                .filter(|t| t.teacher == tuple.teacher)
-                .count();
+                let rng = generators.get(
+                    config.random_seed,
+                    &[generation as u64, positions[index] as u64],
+                );
+                let mut retries = 0;
+                let (child, primary_parent) = loop {
+                    let (mut child, primary_parent) = crossover_with_primary_parent(
+                        config, population, generation, locks, trace, rng,
+                    );
+                    mutate(config, &mut child, generation, tuples, locks, rng);
+                    child.adaptation = calculate_fitness_cached(config, &mut child, tuples);
+                    if let Some(evaluations) = evaluations {
+                        evaluations.fetch_add(1, Ordering::Relaxed);
+                    }
+
+                    let dead = config.dead_threshold.is_some_and(|threshold| {
+                        compare_adaptation(&child.score().hard, &threshold).is_lt()
+                    });
+                    if !dead {
+                        break (child, primary_parent);
+                    }
+                    if retries == config.max_dead_retries {
+                        return individual.clone();
+                    }
+                    retries += 1;
+                };
+
+                if let Some(threshold) = config.min_child_improvement {
+                    let improvement = child.score().improvement_over(&primary_parent.score());
+                    if compare_adaptation(&improvement, &threshold).is_lt() {
+                        return primary_parent.clone();
+                    }
+                }
 
-            individual_fitness -= (same_teacher_different_classes_count as i32) * 10;
+                child
+            },
+        )
+        .collect()
+}
 
-            let same_room_different_teacher_count = other_classes
-                .clone()
-                .filter(|t| t.room == tuple.room)
-                .filter(|t| t.teacher != tuple.teacher)
-                .count();
+/// Create the next generation of a whole sorted population in this process
+///
+/// [`evolve_shard`] with the whole population as the only shard, used by a single node and by
+/// every island. The result is sorted again. A population of fewer than two individuals has no
+/// parents to mate and is returned unchanged.
+pub fn evolve_one_generation(
+    config: &AlgorithmConfig,
+    population: &Population,
+    generation: usize,
+    tuples: &TupleIndex,
+    locks: &Locks,
+    trace: Option<&SelectionTrace>,
+    evaluations: Option<&AtomicU64>,
+) -> Population {
+    if population.len() < 2 {
+        return population.clone();
+    }
 
-            individual_fitness -= (same_room_different_teacher_count as i32) * 20;
+    // the population is sorted, so its positions are the ranks within it
+    let positions: Vec<usize> = (0..population.len()).collect();
+
+    let mut next = evolve_shard(
+        config,
+        population,
+        population,
+        &positions,
+        generation,
+        tuples,
+        locks,
+        trace,
+        evaluations,
+    );
+    sort_by_adaptation(&mut next);
 
-            let same_teacher_same_subject_count = other_classes
-                .clone()
-                .filter(|t| t.teacher == tuple.teacher)
-                .filter(|t| t.label == tuple.label)
-                .count();
+    next
+}
 
-            individual_fitness -= (same_teacher_same_subject_count as i32) * 10;
+/// Choose the period a gene lost in crossover is put back into
+///
+/// Without [`AlgorithmConfig::period_capacity`] any random period will do. With it, random
+/// periods that still have room are tried at most [`AlgorithmConfig::max_repair_attempts`] times.
+/// When all of them are full (or just unlucky), the least occupied period is used even though it
+/// goes over the capacity, so the repair always terminates.
+pub fn repair_period(
+    config: &AlgorithmConfig,
+    periods: &[Chromosome],
+    rng: &mut impl Rng,
+) -> usize {
+    let Some(capacity) = config.period_capacity else {
+        return rng.gen_range(0..config.number_of_periods);
+    };
 
-            let same_teacher_different_subject_count = other_classes
-                .clone()
-                .filter(|t| t.teacher == tuple.teacher)
-                .filter(|t| t.label != tuple.label)
-                .count();
+    for _ in 0..config.max_repair_attempts {
+        let period_id = rng.gen_range(0..config.number_of_periods);
+        if periods[period_id].genes.len() < capacity {
+            return period_id;
+        }
+    }
 
-            individual_fitness -= (same_teacher_different_subject_count as i32) * 20;
+    periods
+        .iter()
+        .position_min_by_key(|period| period.genes.len())
+        .unwrap()
+}
 
-            if debug {
-                println!(
-                    "same_teacher_different_classes_count: {}, same_room_different_teacher_count: {}",
-                    same_teacher_different_classes_count, same_room_different_teacher_count
-                );
+/// Expected share of genes the first parent should pass on to the child
+///
+/// Adaptation is a (non-positive) penalty, so it is mapped to `1 / (1 + penalty)` first and the
+/// share is the first parent's part of the sum of both values raised to `bias`. A bias of `0`
+/// gives both parents an equal share.
+pub fn fitness_share(
+    first_adaptation: Adaptation,
+    second_adaptation: Adaptation,
+    bias: f32,
+) -> f64 {
+    let goodness =
+        |adaptation: Adaptation| 1.0 / (1.0 + adaptation_to_f64(adaptation).min(0.0).abs());
+
+    let first = goodness(first_adaptation).powf(bias.into());
+    let second = goodness(second_adaptation).powf(bias.into());
+
+    (first / (first + second)).clamp(f64::EPSILON, 1.0)
+}
+
+/// Mutate the individual
+///
+/// Typically, mutation probability determines the probability of individual mutation.
+/// In this case, mutation probability determines the probability of chromosome mutation, so it is
+/// good idea to keep it small.
+///
+/// For each period, we are checking if the mutation should occur. If it should, we are removing
+/// a random gene from the period and adding it to a random period. How many genes are moved at
+/// once depends on the generation, see [`mutation_step_size`].
+///
+/// With [`MutationMethod::ConflictDirected`] the gene is chosen among the genes in conflict with
+/// another gene of the period (see [`period_conflicts`]), so the move is more likely to help.
+/// [`MutationMethod::Swap`] exchanges the gene for a gene of the other period instead of moving
+/// it, so moves don't drift genes towards emptier periods.
+///
+/// Genes in `locks` are never moved or swapped out of their period.
+pub fn mutate(
+    config: &AlgorithmConfig,
+    individual: &mut Individual,
+    generation: usize,
+    tuples: &TupleIndex,
+    locks: &Locks,
+    rng: &mut impl Rng,
+) {
+    let mutation_probability = config.mutation_probability;
+    let number_of_periods = usize::try_from(config.number_of_periods).unwrap();
+    let step_size = mutation_step_size(config, generation);
+
+    for period_id in 0..number_of_periods {
+        if rng.gen_bool(mutation_probability.into()) {
+            for _ in 0..step_size {
+                let unlocked: Vec<usize> = individual.chromosomes[period_id]
+                    .genes
+                    .iter()
+                    .positions(|gene| !locks.contains_key(gene))
+                    .collect();
+
+                if unlocked.is_empty() {
+                    break;
+                }
+
+                // pick the target first, so a gene is never removed without a place to go (there
+                // is no other period when `number_of_periods` is 1)
+                let Some(target_index) = individual
+                    .chromosomes
+                    .iter()
+                    .positions(|target| target.id != i32::try_from(period_id).unwrap())
+                    .choose(rng)
+                else {
+                    break;
+                };
+
+                let period = &individual.chromosomes[period_id];
+                let gene_index = match config.mutation_method {
+                    MutationMethod::Move | MutationMethod::Swap => None,
+                    MutationMethod::ConflictDirected => {
+                        let conflicting: HashSet<Gene> = period_conflicts(period, tuples)
+                            .iter()
+                            .flat_map(|conflict| [conflict.tuples.0, conflict.tuples.1])
+                            .collect();
+                        unlocked
+                            .iter()
+                            .copied()
+                            .filter(|index| conflicting.contains(&period.genes[*index]))
+                            .choose(rng)
+                    }
+                }
+                .unwrap_or_else(|| unlocked[rng.gen_range(0..unlocked.len())]);
+
+                if config.mutation_method == MutationMethod::Swap {
+                    let target_unlocked: Vec<usize> = individual.chromosomes[target_index]
+                        .genes
+                        .iter()
+                        .positions(|gene| !locks.contains_key(gene))
+                        .collect();
+                    if !target_unlocked.is_empty() {
+                        let target_gene_index =
+                            target_unlocked[rng.gen_range(0..target_unlocked.len())];
+                        individual
+                            .swap_genes((period_id, gene_index), (target_index, target_gene_index));
+                    }
+                } else {
+                    individual.move_gene(period_id, gene_index, target_index);
+                }
             }
         }
     }
+}
+
+/// Default of [`AlgorithmConfig::selection_decay`]
+pub const DEFAULT_SELECTION_DECAY: f64 = 0.3;
+
+/// Default of [`AlgorithmConfig::selection_offset`]
+pub const DEFAULT_SELECTION_OFFSET: f64 = 2.0;
+
+/// Weight of `rank` in [`SelectionMethod::ExponentialRank`]
+pub(crate) fn selection_weight(decay: f64, offset: f64, rank: usize) -> f64 {
+    f64::exp((-decay * rank as f64) + offset)
+}
+
+/// First rank of a population of `population_size` whose exponential selection weight underflows
+/// to zero, so it can never be selected. `None` when all ranks can be selected
+///
+/// Checked with the steepest decay of the run, the first or the last generation with annealing.
+pub fn first_unselectable_rank(config: &AlgorithmConfig) -> Option<usize> {
+    let last_generation = config.max_generations.saturating_sub(1);
+    let decay = selection_decay(config, 0).max(selection_decay(config, last_generation));
+
+    (0..config.population_size)
+        .find(|rank| selection_weight(decay, config.selection_offset, *rank) == 0.0)
+}
 
-    if debug {
-        println!("Individual fitness: {}", individual_fitness);
+/// Progress of the run in the given generation, from 0 in the first to 1 in the last generation
+fn run_progress(config: &AlgorithmConfig, generation: usize) -> f64 {
+    if config.max_generations > 1 {
+        (generation as f64 / (config.max_generations - 1) as f64).min(1.0)
+    } else {
+        1.0
     }
+}
 
-    individual_fitness
+/// Decay of the selection weights in [`rand_parents`] for the given generation
+///
+/// Constant [`AlgorithmConfig::selection_decay`] unless [`AlgorithmConfig::selection_annealing`]
+/// is set, then it is interpolated linearly between its start and end values.
+pub fn selection_decay(config: &AlgorithmConfig, generation: usize) -> f64 {
+    match config.selection_annealing {
+        Some(annealing) => {
+            let progress = run_progress(config, generation);
+            annealing.start_decay + (annealing.end_decay - annealing.start_decay) * progress
+        }
+        None => config.selection_decay,
+    }
+}
+
+/// Mutation probability for the next generation of `population`
+///
+/// [`AlgorithmConfig::mutation_probability`] unless [`AlgorithmConfig::adaptive_mutation`] is set.
+/// Then the probability rises linearly towards its `max_probability` as the difference between
+/// the best and the mean adaptation drops below the `diversity_threshold`, and falls back as the
+/// spread recovers. The distributed run computes it on the root, so every node mutates with the
+/// same probability.
+pub fn adaptive_mutation_probability(config: &AlgorithmConfig, population: &[Individual]) -> f32 {
+    let Some(adaptive) = config
+        .adaptive_mutation
+        .filter(|adaptive| adaptive.diversity_threshold > 0.0 && !population.is_empty())
+    else {
+        return config.mutation_probability;
+    };
+
+    let adaptations = population
+        .iter()
+        .map(|individual| adaptation_to_f64(individual.adaptation));
+    let best = adaptations.clone().fold(f64::NEG_INFINITY, f64::max);
+    let mean = adaptations.sum::<f64>() / population.len() as f64;
+
+    let lack_of_diversity = (1.0 - (best - mean) / adaptive.diversity_threshold).clamp(0.0, 1.0);
+    let base = config.mutation_probability;
+    base + (adaptive.max_probability - base).max(0.0) * lack_of_diversity as f32
+}
+
+/// Number of genes moved out of a mutating period in the given generation
+///
+/// Starts at [`AlgorithmConfig::max_mutation_step`] and cools down to a single gene by the last
+/// generation following [`AlgorithmConfig::mutation_cooling`]:
+/// * `Constant` - always `max_mutation_step` genes
+/// * `Linear` - decreases linearly with the progress of the run
+/// * `Exponential` - `max_mutation_step ^ (1 - progress)`, drops quickly at the start
+pub fn mutation_step_size(config: &AlgorithmConfig, generation: usize) -> usize {
+    let max_step = config.max_mutation_step.max(1) as f64;
+    let progress = run_progress(config, generation);
+
+    let step = match config.mutation_cooling {
+        CoolingSchedule::Constant => max_step,
+        CoolingSchedule::Linear => max_step - (max_step - 1.0) * progress,
+        CoolingSchedule::Exponential => max_step.powf(1.0 - progress),
+    };
+
+    (step.round() as usize).max(1)
+}
+
+/// Parameters in effect in the given generation, according to their schedules
+pub fn effective_parameters(config: &AlgorithmConfig, generation: usize) -> EffectiveParameters {
+    EffectiveParameters {
+        mutation_probability: config.mutation_probability,
+        mutation_step: mutation_step_size(config, generation),
+        selection_decay: selection_decay(config, generation),
+        generation_gap: offspring_count(config) as f32 / config.population_size as f32,
+    }
+}
+
+/// Calculate fitness of the individual
+///
+/// Every class in a conflict with another class of its period, see [`ConflictType`], decreases
+/// the fitness by the weight of that conflict in [`AlgorithmConfig::fitness_weights`]. The
+/// default weights are [`ConflictType::penalty`]. Soft objectives enabled in the config, like
+/// [`subject_spacing_violations`], are subtracted on top of that.
+pub fn calculate_fitness(
+    config: &AlgorithmConfig,
+    individual: &impl Timetable,
+    tuples: &TupleIndex,
+) -> Adaptation {
+    let individual_fitness = calculate_fitness_detailed(config, individual, tuples).total();
+    trace!(%individual_fitness, "individual evaluated");
+
+    individual_fitness
+}
+
+/// [`calculate_fitness`] split into hard and soft penalties, see
+/// [`config::HardConstraints`]. Timetables compare by their scores, see [`compare_score`]
+pub fn calculate_score(
+    config: &AlgorithmConfig,
+    individual: &impl Timetable,
+    tuples: &TupleIndex,
+) -> Score {
+    calculate_fitness_detailed(config, individual, tuples).score(&config.hard_constraints)
+}
+
+/// [`calculate_fitness`] split by the kind of penalty
+pub fn calculate_fitness_detailed(
+    config: &AlgorithmConfig,
+    individual: &impl Timetable,
+    tuples: &TupleIndex,
+) -> FitnessBreakdown {
+    let mut breakdown = FitnessBreakdown {
+        soft_objectives: soft_objectives_fitness(config, individual, tuples),
+        ..FitnessBreakdown::default()
+    };
+
+    for genes in individual.period_genes() {
+        breakdown += period_fitness_breakdown(config, genes, tuples);
+    }
+
+    breakdown
+}
+
+/// Fitness of the individual using the per period cache
+///
+/// Conflicts only happen between tuples in the same period, so the fitness is a sum of
+/// independent per period contributions. They are cached in [`Individual::period_fitness`], keyed
+/// by the hash of the period's genes, and only periods that are dirty or whose genes changed are
+/// recomputed. Soft objectives span many periods and are always computed. The result is the same
+/// as [`calculate_fitness`], the hard part of it is stored in [`Individual::hard_adaptation`] as
+/// [`calculate_score`] splits it.
+pub fn calculate_fitness_cached(
+    config: &AlgorithmConfig,
+    individual: &mut Individual,
+    tuples: &TupleIndex,
+) -> Adaptation {
+    let number_of_periods = individual.chromosomes.len();
+    individual
+        .period_fitness
+        .resize(number_of_periods, CachedPeriodFitness::default());
+
+    let mut individual_fitness = soft_objectives_fitness(config, individual, tuples);
+    let mut hard_fitness = Adaptation::default();
+
+    for (period, cached) in individual
+        .chromosomes
+        .iter()
+        .zip(individual.period_fitness.iter_mut())
+    {
+        let genes_hash = period.genes_hash();
+
+        if cached.dirty || cached.genes_hash != genes_hash {
+            let breakdown = period_fitness_breakdown(config, &period.genes, tuples);
+            *cached = CachedPeriodFitness {
+                genes_hash,
+                fitness: breakdown.total(),
+                hard: breakdown.score(&config.hard_constraints).hard,
+                dirty: false,
+            };
+        }
+
+        individual_fitness += cached.fitness;
+        hard_fitness += cached.hard;
+    }
+
+    individual.hard_adaptation = hard_fitness;
+    individual_fitness
+}
+
+/// Fitness of the objectives enabled in the config that aren't conflicts within a period
+fn soft_objectives_fitness(
+    config: &AlgorithmConfig,
+    individual: &impl Timetable,
+    tuples: &TupleIndex,
+) -> Adaptation {
+    soft_objective_penalties(config, individual, tuples)
+        .into_iter()
+        .map(|penalty| -(penalty.occurrences as Adaptation) * penalty.weight)
+        .sum()
+}
+
+/// Occurrences of every soft objective enabled in the config, in the order they are listed in
+/// [`AlgorithmConfig`]
+pub fn soft_objective_penalties(
+    config: &AlgorithmConfig,
+    individual: &impl Timetable,
+    tuples: &TupleIndex,
+) -> Vec<SoftObjectivePenalty> {
+    let mut penalties = Vec::new();
+
+    if config.subject_spacing_weight != Adaptation::default() {
+        penalties.push(SoftObjectivePenalty {
+            name: "subject spacing",
+            occurrences: subject_spacing_violations(individual, tuples),
+            weight: config.subject_spacing_weight,
+        });
+    }
+
+    // rewarding empty periods is done by penalising used ones, so the adaptation stays <= 0
+    if config.compaction_weight != Adaptation::default() {
+        penalties.push(SoftObjectivePenalty {
+            name: "compaction",
+            occurrences: individual
+                .period_genes()
+                .filter(|genes| !genes.is_empty())
+                .count(),
+            weight: config.compaction_weight,
+        });
+    }
+
+    if config.room_change_weight != Adaptation::default() {
+        penalties.push(SoftObjectivePenalty {
+            name: "room changes",
+            occurrences: teacher_room_changes(individual, tuples, config.periods_per_day),
+            weight: config.room_change_weight,
+        });
+    }
+
+    if config.room_capacity_weight != Adaptation::default() {
+        penalties.push(SoftObjectivePenalty {
+            name: "room capacity",
+            occurrences: overfull_rooms(individual, tuples),
+            weight: config.room_capacity_weight,
+        });
+    }
+
+    penalties
+}
+
+/// Fitness contribution of conflicts between tuples placed in one period, split by the kind of
+/// conflict
+///
+/// Every gene is compared with every other tuple placed in the period, so a conflicting pair is
+/// counted from both sides. Instead of comparing all pairs, the tuples of the period are counted
+/// per teacher, room, teacher and room, teacher and subject, and student group, and the conflicts
+/// of a gene are read from the counts of its groups, linear in the number of genes.
+fn period_fitness_breakdown(
+    config: &AlgorithmConfig,
+    genes: &[Gene],
+    tuples: &TupleIndex,
+) -> FitnessBreakdown {
+    let mut fitness = FitnessBreakdown::default();
+
+    let tuple_of = |gene_id: &Gene| -> &Tuple {
+        tuples
+            .get(*gene_id)
+            .unwrap_or_else(|| panic!("Tuple with id {} not found", gene_id))
+    };
+
+    // a gene placed twice is still a single other class for the rest of the period
+    let mut by_teacher: HashMap<&str, usize> = HashMap::new();
+    let mut by_room: HashMap<&str, usize> = HashMap::new();
+    let mut by_teacher_room: HashMap<(&str, &str), usize> = HashMap::new();
+    let mut by_teacher_label: HashMap<(&str, &str), usize> = HashMap::new();
+    let mut by_group: HashMap<&str, usize> = HashMap::new();
+
+    for tuple in genes.iter().unique().map(tuple_of) {
+        *by_teacher.entry(&tuple.teacher).or_default() += 1;
+        *by_room.entry(&tuple.room).or_default() += 1;
+        *by_teacher_room
+            .entry((&tuple.teacher, &tuple.room))
+            .or_default() += 1;
+        *by_teacher_label
+            .entry((&tuple.teacher, &tuple.label))
+            .or_default() += 1;
+        *by_group.entry(&tuple.group).or_default() += 1;
+    }
+
+    for gene_id in genes {
+        // if the same teacher is teaching more than one class at the same time decrease fitness by 10,
+        // if different teachers occupy the same room at the same time decrease fitness by 20
+
+        // additional rules may be added, for example,
+        // the division of lectures by type of classes, if the types of classes differ for the
+        // same lecture, reduce the suitability by a smaller value
+
+        let tuple = tuple_of(gene_id);
+
+        // the tuple itself is in every one of its groups
+        let same_teacher_same_room =
+            by_teacher_room[&(tuple.teacher.as_str(), tuple.room.as_str())];
+        let same_teacher_same_label =
+            by_teacher_label[&(tuple.teacher.as_str(), tuple.label.as_str())];
+
+        let same_teacher_different_classes_count = same_teacher_same_room - 1;
+        let same_room_different_teacher_count =
+            by_room[tuple.room.as_str()] - same_teacher_same_room;
+        let same_teacher_same_subject_count = same_teacher_same_label - 1;
+        let same_teacher_different_subject_count =
+            by_teacher[tuple.teacher.as_str()] - same_teacher_same_label;
+        // classes without a group never clash
+        let same_group_count = match tuple.group.as_str() {
+            "" => 0,
+            group => by_group[group] - 1,
+        };
+
+        for (conflict_type, count) in [
+            (
+                ConflictType::TeacherMultiClass,
+                same_teacher_different_classes_count,
+            ),
+            (ConflictType::RoomClash, same_room_different_teacher_count),
+            (
+                ConflictType::SameTeacherSameSubject,
+                same_teacher_same_subject_count,
+            ),
+            (
+                ConflictType::SameTeacherDifferentSubject,
+                same_teacher_different_subject_count,
+            ),
+            (ConflictType::GroupClash, same_group_count),
+        ] {
+            fitness.add_conflicts(
+                conflict_type,
+                count,
+                config.fitness_weights.penalty(conflict_type),
+            );
+        }
+
+        trace!(
+            gene = gene_id,
+            same_teacher_different_classes_count,
+            same_room_different_teacher_count,
+            "gene conflicts counted"
+        );
+    }
+
+    fitness
+}
+
+/// List every conflict in the individual
+///
+/// Uses the same rules as [`calculate_fitness`], but every conflicting pair of tuples is reported
+/// once, whereas the fitness counts it from both sides.
+pub fn list_conflicts(individual: &Individual, tuples: &TupleIndex) -> Vec<Conflict> {
+    individual
+        .chromosomes
+        .iter()
+        .flat_map(|period| period_conflicts(period, tuples))
+        .collect()
+}
+
+/// List every conflict in the individual with the teacher, room, subject or group it is about
+///
+/// Same conflicts as [`list_conflicts`], so with the default [`config::FitnessWeights`] and no
+/// soft objectives the penalties add up to the negated [`calculate_fitness`].
+pub fn violations(individual: &Individual, tuples: &TupleIndex) -> Vec<Violation> {
+    list_conflicts(individual, tuples)
+        .iter()
+        .map(|conflict| Violation::new(conflict, &tuples[&conflict.tuples.0]))
+        .collect()
+}
+
+/// Every conflict between the tuples of one period, see [`list_conflicts`]
+pub fn period_conflicts(period: &Chromosome, tuples: &TupleIndex) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+
+    for (first, second) in period.genes.iter().sorted().tuple_combinations() {
+        let a = &tuples[first];
+        let b = &tuples[second];
+
+        let mut conflict_types = Vec::new();
+        if a.teacher == b.teacher && a.room == b.room {
+            conflict_types.push(ConflictType::TeacherMultiClass);
+        }
+        if a.teacher != b.teacher && a.room == b.room {
+            conflict_types.push(ConflictType::RoomClash);
+        }
+        if a.teacher == b.teacher && a.label == b.label {
+            conflict_types.push(ConflictType::SameTeacherSameSubject);
+        }
+        if a.teacher == b.teacher && a.label != b.label {
+            conflict_types.push(ConflictType::SameTeacherDifferentSubject);
+        }
+        if !a.group.is_empty() && a.group == b.group {
+            conflict_types.push(ConflictType::GroupClash);
+        }
+
+        conflicts.extend(conflict_types.into_iter().map(|conflict_type| Conflict {
+            conflict_type,
+            period: period.id,
+            tuples: (a.id, b.id),
+        }));
+    }
+
+    conflicts
+}
+
+/// Whether the individual has no hard conflicts, see [`config::HardConstraints`]
+pub fn is_hard_feasible(
+    hard_constraints: &config::HardConstraints,
+    individual: &Individual,
+    tuples: &TupleIndex,
+) -> bool {
+    list_conflicts(individual, tuples)
+        .iter()
+        .all(|conflict| !hard_constraints.is_hard(conflict.conflict_type))
+}
+
+/// Check that every tuple is scheduled exactly once
+///
+/// Crossover and mutation must neither drop nor copy genes, this catches it when they do. Lists
+/// the missing, duplicated and unknown ids in the order of the ids.
+pub fn validate_completeness(
+    individual: &Individual,
+    tuples: &[Tuple],
+) -> Result<(), Vec<CompletenessError>> {
+    let mut counts = individual.period_genes().flatten().copied().counts();
+
+    let mut errors: Vec<(i32, CompletenessError)> = tuples
+        .iter()
+        .filter_map(|tuple| match counts.remove(&tuple.id).unwrap_or_default() {
+            0 => Some((tuple.id, CompletenessError::Missing(tuple.id))),
+            1 => None,
+            count => Some((
+                tuple.id,
+                CompletenessError::Duplicated {
+                    id: tuple.id,
+                    count,
+                },
+            )),
+        })
+        .collect();
+    errors.extend(
+        counts
+            .into_keys()
+            .map(|gene| (gene, CompletenessError::Unknown(gene))),
+    );
+
+    if errors.is_empty() {
+        return Ok(());
+    }
+
+    errors.sort_by_key(|(id, _)| *id);
+    Err(errors.into_iter().map(|(_, error)| error).collect())
+}
+
+/// Copy of the individual with `gene` moved from its period to the period at `target_index`
+pub fn try_move(individual: &Individual, gene: Gene, target_index: usize) -> Individual {
+    let mut moved = individual.clone();
+
+    for period in &mut moved.chromosomes {
+        period.genes.retain(|g| *g != gene);
+    }
+    moved.chromosomes[target_index].genes.push(gene);
+
+    moved
+}
+
+/// Number of genes placed in different periods in the two individuals
+///
+/// Genes present in only one of the individuals count as differently placed.
+pub fn genome_distance(first: &Individual, second: &Individual) -> usize {
+    let placement = |individual: &Individual| -> HashMap<Gene, i32> {
+        individual
+            .chromosomes
+            .iter()
+            .flat_map(|period| period.genes.iter().map(move |gene| (*gene, period.id)))
+            .collect()
+    };
+
+    let first_placement = placement(first);
+    let second_placement = placement(second);
+
+    let moved = first_placement
+        .iter()
+        .filter(|(gene, period)| second_placement.get(gene) != Some(period))
+        .count();
+    let only_in_second = second_placement
+        .keys()
+        .filter(|gene| !first_placement.contains_key(gene))
+        .count();
+
+    moved + only_in_second
+}
+
+/// Count pairs of classes of the same subject that are bunched together
+///
+/// Sessions of one subject ([`Tuple::label`]) should be spread across the week, so every pair of
+/// them placed in the same or in adjacent periods counts as one violation.
+pub fn subject_spacing_violations(individual: &impl Timetable, tuples: &[Tuple]) -> usize {
+    let labels: HashMap<i32, &str> = tuples.iter().map(|t| (t.id, t.label.as_str())).collect();
+
+    let mut periods_by_label: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (period_index, genes) in individual.period_genes().enumerate() {
+        for gene in genes {
+            periods_by_label
+                .entry(labels[gene])
+                .or_default()
+                .push(period_index);
+        }
+    }
+
+    periods_by_label
+        .values()
+        .map(|periods| {
+            periods
+                .iter()
+                .tuple_combinations()
+                .filter(|(a, b)| a.abs_diff(**b) <= 1)
+                .count()
+        })
+        .sum()
+}
+
+/// Count the rooms teachers teach in beyond one room a day
+///
+/// A day is `periods_per_day` consecutive periods, the whole timetable when unset. A teacher using
+/// three distinct rooms ([`Tuple::room`]) in a day counts as two changes.
+pub fn teacher_room_changes(
+    individual: &impl Timetable,
+    tuples: &TupleIndex,
+    periods_per_day: Option<usize>,
+) -> usize {
+    let mut rooms_by_teacher_day: HashMap<(&str, usize), HashSet<&str>> = HashMap::new();
+    for (period_index, genes) in individual.period_genes().enumerate() {
+        let day = periods_per_day.map_or(0, |periods| period_index / periods.max(1));
+        for gene in genes {
+            let tuple = &tuples[gene];
+            rooms_by_teacher_day
+                .entry((tuple.teacher.as_str(), day))
+                .or_default()
+                .insert(tuple.room.as_str());
+        }
+    }
+
+    rooms_by_teacher_day
+        .values()
+        .map(|rooms| rooms.len() - 1)
+        .sum()
+}
+
+/// Count the periods in which a room is attended by more students than it has seats
+///
+/// A room with a [`Tuple::capacity`] of `0` has an unknown capacity and is never overfull.
+pub fn overfull_rooms(individual: &impl Timetable, tuples: &TupleIndex) -> usize {
+    individual
+        .period_genes()
+        .map(|genes| {
+            // (capacity, attending students) of every room in the period
+            let mut rooms: HashMap<&str, (u32, u32)> = HashMap::new();
+            for tuple in genes.iter().unique().map(|gene| &tuples[gene]) {
+                let (capacity, students) = rooms.entry(tuple.room.as_str()).or_default();
+                *capacity = (*capacity).max(tuple.capacity);
+                *students += tuple.size;
+            }
+
+            rooms
+                .values()
+                .filter(|(capacity, students)| *capacity > 0 && students > capacity)
+                .count()
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{
+        config::{AdaptiveMutation, FitnessWeights, HardConstraints, SelectionAnnealing},
+        testing::tuple,
+    };
+
+    /// Two parents with the same genes placed in opposite periods, `fitter` has no conflicts
+    fn opposite_parents() -> (Individual, Individual) {
+        let first_half: Vec<i32> = (1..=10).collect();
+        let second_half: Vec<i32> = (11..=20).collect();
+
+        let mut fitter = Individual::with_chromosomes(vec![
+            Chromosome {
+                id: 0,
+                genes: first_half.clone(),
+            },
+            Chromosome {
+                id: 1,
+                genes: second_half.clone(),
+            },
+        ]);
+        fitter.adaptation = 0 as Adaptation;
+
+        let mut weaker = Individual::with_chromosomes(vec![
+            Chromosome {
+                id: 0,
+                genes: second_half,
+            },
+            Chromosome {
+                id: 1,
+                genes: first_half,
+            },
+        ]);
+        weaker.adaptation = -200 as Adaptation;
+
+        (fitter, weaker)
+    }
+
+    /// Fraction of the child's genes placed in the same period as in `parent`
+    fn inherited_fraction(child: &Individual, parent: &Individual) -> f64 {
+        let total: usize = child.chromosomes.iter().map(|c| c.genes.len()).sum();
+        let inherited: usize = child
+            .chromosomes
+            .iter()
+            .zip(parent.chromosomes.iter())
+            .map(|(c, p)| c.genes.iter().filter(|g| p.genes.contains(g)).count())
+            .sum();
+
+        inherited as f64 / total as f64
+    }
+
+    /// Fitness of an individual with both tuples placed in a single period
+    fn fitness_of_pair(first: Tuple, second: Tuple) -> Adaptation {
+        let individual = Individual::with_chromosomes(vec![Chromosome {
+            id: 0,
+            genes: vec![first.id, second.id],
+        }]);
+
+        calculate_fitness(
+            &AlgorithmConfig::default(),
+            &individual,
+            &TupleIndex::from(vec![first, second]),
+        )
+    }
+
+    #[test]
+    fn test_conflict_type_penalties_match_fitness() {
+        // every conflicting pair is counted once from each side
+        assert_eq!(
+            fitness_of_pair(
+                tuple(1, "Math", "A", "Smith"),
+                tuple(2, "Art", "A", "Jones")
+            ),
+            -2 as Adaptation * ConflictType::RoomClash.penalty()
+        );
+        assert_eq!(
+            fitness_of_pair(
+                tuple(1, "Math", "A", "Smith"),
+                tuple(2, "Math", "B", "Smith")
+            ),
+            -2 as Adaptation * ConflictType::SameTeacherSameSubject.penalty()
+        );
+        assert_eq!(
+            fitness_of_pair(
+                tuple(1, "Math", "A", "Smith"),
+                tuple(2, "Art", "B", "Smith")
+            ),
+            -2 as Adaptation * ConflictType::SameTeacherDifferentSubject.penalty()
+        );
+        assert_eq!(
+            fitness_of_pair(
+                tuple(1, "Math", "A", "Smith"),
+                tuple(2, "Art", "A", "Smith")
+            ),
+            -2 as Adaptation
+                * (ConflictType::TeacherMultiClass.penalty()
+                    + ConflictType::SameTeacherDifferentSubject.penalty())
+        );
+    }
+
+    /// Reference implementation of [`period_fitness_breakdown`], scanning all tuples for every gene
+    fn slow_period_fitness_breakdown(genes: &[Gene], tuples: &[Tuple]) -> FitnessBreakdown {
+        let mut fitness = FitnessBreakdown::default();
+
+        for gene_id in genes {
+            let tuple = tuples.iter().find(|t| t.id == *gene_id).unwrap();
+
+            let other_classes = tuples
+                .iter()
+                .filter(|t| genes.contains(&t.id))
+                .filter(|t| t.id != tuple.id);
+
+            // get count of tuples with the same teacher
+            let same_teacher_different_classes_count = other_classes
+                .clone()
+                .filter(|t| t.room == tuple.room)
+                .filter(|t| t.teacher == tuple.teacher)
+                .count();
+
+            fitness.add_conflicts(
+                ConflictType::TeacherMultiClass,
+                same_teacher_different_classes_count,
+                ConflictType::TeacherMultiClass.penalty(),
+            );
+
+            let same_room_different_teacher_count = other_classes
+                .clone()
+                .filter(|t| t.room == tuple.room)
+                .filter(|t| t.teacher != tuple.teacher)
+                .count();
+
+            fitness.add_conflicts(
+                ConflictType::RoomClash,
+                same_room_different_teacher_count,
+                ConflictType::RoomClash.penalty(),
+            );
+
+            let same_teacher_same_subject_count = other_classes
+                .clone()
+                .filter(|t| t.teacher == tuple.teacher)
+                .filter(|t| t.label == tuple.label)
+                .count();
+
+            fitness.add_conflicts(
+                ConflictType::SameTeacherSameSubject,
+                same_teacher_same_subject_count,
+                ConflictType::SameTeacherSameSubject.penalty(),
+            );
+
+            let same_teacher_different_subject_count = other_classes
+                .clone()
+                .filter(|t| t.teacher == tuple.teacher)
+                .filter(|t| t.label != tuple.label)
+                .count();
+
+            fitness.add_conflicts(
+                ConflictType::SameTeacherDifferentSubject,
+                same_teacher_different_subject_count,
+                ConflictType::SameTeacherDifferentSubject.penalty(),
+            );
+        }
+
+        fitness
+    }
+
+    #[test]
+    fn test_indexed_fitness_matches_scan() {
+        let config = AlgorithmConfig {
+            number_of_periods: 6,
+            population_size: 30,
+            ..AlgorithmConfig::default()
+        };
+
+        for seed in 0..5 {
+            let tuples = TupleIndex::from(testing::generate_synthetic_problem(80, 6, 5, 10, seed));
+            let mut rng = StdRng::seed_from_u64(seed);
+
+            for mut individual in create_first_population(&config, &tuples) {
+                // duplicated genes are counted once as the other class of a gene
+                let duplicate = individual.chromosomes[0].genes.first().copied();
+                if let Some(gene) = duplicate.filter(|_| rng.gen_bool(0.3)) {
+                    individual.chromosomes[0].genes.push(gene);
+                }
+
+                let mut expected = FitnessBreakdown::default();
+                for genes in individual.period_genes() {
+                    expected += slow_period_fitness_breakdown(genes, &tuples);
+                }
+
+                assert_eq!(
+                    calculate_fitness_detailed(&config, &individual, &tuples),
+                    expected
+                );
+                assert_eq!(
+                    calculate_fitness_cached(&config, &mut individual, &tuples),
+                    expected.total()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_fitness_breakdown() {
+        let config = AlgorithmConfig {
+            number_of_periods: 2,
+            compaction_weight: 1 as Adaptation,
+            ..AlgorithmConfig::default()
+        };
+        let tuples = TupleIndex::from(vec![
+            tuple(1, "Math", "A", "Smith"),
+            tuple(2, "Math", "A", "Smith"),
+            tuple(3, "Art", "B", "Jones"),
+            tuple(4, "Physics", "B", "Brown"),
+        ]);
+        let individual = Individual::with_chromosomes(vec![
+            Chromosome {
+                id: 0,
+                genes: vec![1, 2],
+            },
+            Chromosome {
+                id: 1,
+                genes: vec![3, 4],
+            },
+        ]);
+
+        let breakdown = calculate_fitness_detailed(&config, &individual, &tuples);
+
+        // every conflict is counted from both of its tuples
+        assert_eq!(
+            breakdown,
+            FitnessBreakdown {
+                teacher_multi_class: -20 as Adaptation,
+                room_clash: -40 as Adaptation,
+                same_teacher_same_subject: -20 as Adaptation,
+                same_teacher_different_subject: Adaptation::default(),
+                group_clash: Adaptation::default(),
+                soft_objectives: -2 as Adaptation,
+            }
+        );
+        assert_eq!(
+            breakdown.total(),
+            calculate_fitness(&config, &individual, &tuples)
+        );
+    }
+
+    #[test]
+    fn test_hard_violation_ranks_below_soft_violations() {
+        // teachers in two rooms at once only cost soft penalties, but a lot of them
+        let config = AlgorithmConfig {
+            number_of_periods: 1,
+            fitness_weights: FitnessWeights {
+                teacher_multi_class: 100 as Adaptation,
+                same_teacher_same_subject: 100 as Adaptation,
+                ..FitnessWeights::default()
+            },
+            hard_constraints: HardConstraints {
+                teacher_multi_class: false,
+                same_teacher_same_subject: false,
+                ..HardConstraints::default()
+            },
+            ..AlgorithmConfig::default()
+        };
+        let tuples = TupleIndex::from(vec![
+            tuple(1, "Art", "A", "Jones"),
+            tuple(2, "Math", "A", "Brown"),
+            tuple(3, "Math", "B", "Smith"),
+            tuple(4, "Math", "C", "Smith"),
+            tuple(5, "Math", "D", "Smith"),
+            tuple(6, "Math", "E", "Smith"),
+        ]);
+        let one_period = |genes| Individual::with_chromosomes(vec![Chromosome { id: 0, genes }]);
+
+        let mut room_clash = one_period(vec![1, 2]);
+        let mut soft_only = one_period(vec![3, 4, 5, 6]);
+        let mut population = [&mut room_clash, &mut soft_only]
+            .map(|individual| {
+                individual.adaptation = calculate_fitness_cached(&config, individual, &tuples);
+                individual.clone()
+            })
+            .to_vec();
+
+        let hard = calculate_score(&config, &room_clash, &tuples);
+        let soft = calculate_score(&config, &soft_only, &tuples);
+        assert_eq!(hard, room_clash.score());
+        assert_eq!(soft, soft_only.score());
+        assert!(hard.hard < Adaptation::default());
+        assert_eq!(hard.soft, Adaptation::default());
+        assert_eq!(soft.hard, Adaptation::default());
+        assert_eq!(
+            soft.total(),
+            calculate_fitness(&config, &soft_only, &tuples)
+        );
+        // worse in total, but without hard violations
+        assert!(compare_adaptation(&soft.total(), &hard.total()).is_lt());
+        assert!(compare_score(&soft, &hard).is_gt());
+
+        sort_by_adaptation(&mut population);
+        assert_eq!(population[0].chromosomes[0].genes, vec![3, 4, 5, 6]);
+        assert!(is_hard_feasible(
+            &config.hard_constraints,
+            &population[0],
+            &tuples
+        ));
+        assert!(!is_hard_feasible(
+            &config.hard_constraints,
+            &population[1],
+            &tuples
+        ));
+    }
+
+    #[test]
+    fn test_subject_spacing_penalty() {
+        let config = AlgorithmConfig {
+            number_of_periods: 4,
+            subject_spacing_weight: 5 as Adaptation,
+            ..AlgorithmConfig::default()
+        };
+        let tuples = TupleIndex::from(vec![
+            tuple(1, "Math", "A", "Smith"),
+            tuple(2, "Math", "B", "Jones"),
+        ]);
+        let place = |first: usize, second: usize| {
+            let mut chromosomes: Vec<Chromosome> = (0..4).map(Chromosome::new).collect();
+            chromosomes[first].genes.push(1);
+            chromosomes[second].genes.push(2);
+            Individual::with_chromosomes(chromosomes)
+        };
+
+        assert_eq!(
+            calculate_fitness(&config, &place(0, 1), &tuples),
+            -5 as Adaptation
+        );
+        assert_eq!(
+            calculate_fitness(&config, &place(0, 3), &tuples),
+            0 as Adaptation
+        );
+    }
+
+    #[test]
+    fn test_room_change_penalty() {
+        let config = AlgorithmConfig {
+            number_of_periods: 6,
+            room_change_weight: 1 as Adaptation,
+            periods_per_day: Some(3),
+            ..AlgorithmConfig::default()
+        };
+        let tuples = TupleIndex::from(vec![
+            tuple(1, "Math", "A", "Smith"),
+            tuple(2, "Math", "B", "Smith"),
+            tuple(3, "Math", "C", "Smith"),
+            tuple(4, "Physics", "A", "Jones"),
+            tuple(5, "Physics", "B", "Jones"),
+            tuple(6, "Physics", "B", "Jones"),
+        ]);
+        let place = |genes: &[i32], first_period: usize| {
+            let mut chromosomes: Vec<Chromosome> = (0..6).map(Chromosome::new).collect();
+            for (offset, gene) in genes.iter().enumerate() {
+                chromosomes[first_period + offset].genes.push(*gene);
+            }
+            Individual::with_chromosomes(chromosomes)
+        };
+
+        let three_rooms = calculate_fitness(&config, &place(&[1, 2, 3], 0), &tuples);
+        let two_rooms = calculate_fitness(&config, &place(&[4, 5, 6], 0), &tuples);
+        assert_eq!(two_rooms, -1 as Adaptation);
+        assert_eq!(three_rooms, 2 as Adaptation * two_rooms);
+
+        // the same classes split across two days
+        assert_eq!(
+            calculate_fitness(&config, &place(&[1, 2, 3], 2), &tuples),
+            -1 as Adaptation
+        );
+    }
+
+    #[test]
+    fn test_fitness_weights_scale_penalties() {
+        let tuples = TupleIndex::from(vec![
+            tuple(1, "Math", "A", "Smith"),
+            tuple(2, "Art", "A", "Jones"),
+            tuple(3, "Math", "B", "Smith"),
+        ]);
+        let individual = Individual::with_chromosomes(vec![Chromosome {
+            id: 0,
+            genes: vec![1, 2, 3],
+        }]);
+        let breakdown = |fitness_weights| {
+            let config = AlgorithmConfig {
+                number_of_periods: 1,
+                fitness_weights,
+                ..AlgorithmConfig::default()
+            };
+            calculate_fitness_detailed(&config, &individual, &tuples)
+        };
+
+        let default = breakdown(FitnessWeights::default());
+        let doubled = breakdown(FitnessWeights {
+            room_clash: 2 as Adaptation * FitnessWeights::default().room_clash,
+            ..FitnessWeights::default()
+        });
+
+        assert_ne!(default.room_clash, Adaptation::default());
+        assert_eq!(doubled.room_clash, 2 as Adaptation * default.room_clash);
+        assert_eq!(
+            FitnessBreakdown {
+                room_clash: default.room_clash,
+                ..doubled
+            },
+            default
+        );
+
+        let negative = AlgorithmConfig {
+            fitness_weights: FitnessWeights {
+                same_teacher_same_subject: -1 as Adaptation,
+                ..FitnessWeights::default()
+            },
+            ..AlgorithmConfig::default()
+        };
+        assert!(matches!(
+            negative.validate(),
+            Err(config::ConfigLoadError::NegativeFitnessWeight {
+                name: "same_teacher_same_subject",
+                ..
+            })
+        ));
+        assert!(AlgorithmConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_evolve_one_generation_keeps_population_size() {
+        let config = AlgorithmConfig {
+            population_size: 12,
+            number_of_periods: 5,
+            generation_gap: 0.5,
+            random_seed: Some(4),
+            ..AlgorithmConfig::default()
+        };
+        let tuples = TupleIndex::from(testing::generate_synthetic_problem(30, 4, 4, 5, 2));
+        let mut population = create_first_population(&config, &tuples);
+        for individual in population.iter_mut() {
+            individual.adaptation = calculate_fitness_cached(&config, individual, &tuples);
+        }
+        sort_by_adaptation(&mut population);
+
+        for generation in 0..3 {
+            let best = population[0].adaptation;
+            population = evolve_one_generation(
+                &config,
+                &population,
+                generation,
+                &tuples,
+                &Locks::new(),
+                None,
+                None,
+            );
+
+            assert_eq!(population.len(), config.population_size);
+            assert!(population.windows(2).all(|pair| compare_adaptation(
+                &pair[0].adaptation,
+                &pair[1].adaptation
+            )
+            .is_ge()));
+            // survivors are carried over, so the best never gets worse
+            assert!(compare_adaptation(&population[0].adaptation, &best).is_ge());
+        }
+    }
+
+    #[test]
+    fn test_group_clash_penalty() {
+        let config = AlgorithmConfig {
+            number_of_periods: 1,
+            fitness_weights: FitnessWeights {
+                group_clash: 15 as Adaptation,
+                ..FitnessWeights::default()
+            },
+            ..AlgorithmConfig::default()
+        };
+        let grouped = |id, teacher, room, group: &str| Tuple {
+            group: group.to_string(),
+            ..tuple(id, "Lecture", room, teacher)
+        };
+        let tuples = TupleIndex::from(vec![
+            grouped(1, "Smith", "A", "1a"),
+            grouped(2, "Jones", "B", "1a"),
+            grouped(3, "Brown", "C", ""),
+            grouped(4, "Green", "D", ""),
+        ]);
+        let individual = Individual::with_chromosomes(vec![Chromosome {
+            id: 0,
+            genes: vec![1, 2, 3, 4],
+        }]);
+
+        // ungrouped classes 3 and 4 don't clash with each other
+        let breakdown = calculate_fitness_detailed(&config, &individual, &tuples);
+        assert_eq!(breakdown.group_clash, -30 as Adaptation);
+        assert_eq!(breakdown.total(), breakdown.group_clash);
+
+        let conflicts = list_conflicts(&individual, &tuples);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].conflict_type, ConflictType::GroupClash);
+        assert!(!is_hard_feasible(
+            &HardConstraints::default(),
+            &individual,
+            &tuples
+        ));
+    }
+
+    #[test]
+    fn test_room_capacity_penalty() {
+        let config = AlgorithmConfig {
+            number_of_periods: 2,
+            room_capacity_weight: 5 as Adaptation,
+            ..AlgorithmConfig::default()
+        };
+        let sized = |id, room, teacher, capacity, size| Tuple {
+            capacity,
+            size,
+            ..tuple(id, "Lecture", room, teacher)
+        };
+        // Smith and Jones share the 30 seats of room A, room B's capacity is unknown
+        let tuples = TupleIndex::from(vec![
+            sized(1, "A", "Smith", 30, 20),
+            sized(2, "A", "Jones", 30, 15),
+            sized(3, "B", "Brown", 0, 100),
+            sized(4, "A", "Smith", 30, 30),
+        ]);
+        let individual = |first: Vec<i32>, second: Vec<i32>| {
+            Individual::with_chromosomes(vec![
+                Chromosome {
+                    id: 0,
+                    genes: first,
+                },
+                Chromosome {
+                    id: 1,
+                    genes: second,
+                },
+            ])
+        };
+
+        let overfull = individual(vec![1, 2, 3], vec![4]);
+        assert_eq!(overfull_rooms(&overfull, &tuples), 1);
+        assert_eq!(
+            calculate_fitness_detailed(&config, &overfull, &tuples).soft_objectives,
+            -5 as Adaptation
+        );
+
+        // moving Jones to the other period overfills it instead
+        assert_eq!(
+            overfull_rooms(&individual(vec![1, 3], vec![2, 4]), &tuples),
+            1
+        );
+        assert_eq!(
+            overfull_rooms(&individual(vec![1, 3, 4], vec![2]), &tuples),
+            1
+        );
+        assert_eq!(overfull_rooms(&individual(vec![1, 3], vec![2]), &tuples), 0);
+    }
+
+    #[test]
+    fn test_suggest_population_size() {
+        let config = AlgorithmConfig::default();
+        let problem = |num_tuples: i32| -> Vec<Tuple> {
+            (0..num_tuples)
+                .map(|id| tuple(id, "Math", "A", "Smith"))
+                .collect()
+        };
+
+        let suggestions: Vec<usize> = [0, 1, 10, 100, 1_000, 10_000, 100_000]
+            .into_iter()
+            .map(|num_tuples| suggest_population_size(&config, &problem(num_tuples)))
+            .collect();
+
+        assert!(suggestions.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(suggestions.iter().all(|size| (50..=10_000).contains(size)));
+        assert_eq!(suggestions[0], 50);
+        assert_eq!(suggestions[3], 566);
+        assert_eq!(*suggestions.last().unwrap(), 10_000);
+    }
+
+    #[test]
+    fn test_adaptive_mutation_rises_as_diversity_drops() {
+        let config = AlgorithmConfig {
+            mutation_probability: 0.05,
+            adaptive_mutation: Some(AdaptiveMutation {
+                max_probability: 0.45,
+                diversity_threshold: 100.0,
+            }),
+            ..AlgorithmConfig::default()
+        };
+        let population = |adaptations: &[i32]| -> Population {
+            adaptations
+                .iter()
+                .map(|adaptation| Individual {
+                    adaptation: *adaptation as Adaptation,
+                    ..Individual::default()
+                })
+                .collect()
+        };
+
+        let diverse = population(&[0, -200, -400]);
+        let half = population(&[0, -50, -100]);
+        let converged = population(&[-120, -120, -120]);
+
+        assert_eq!(adaptive_mutation_probability(&config, &diverse), 0.05);
+        assert!((adaptive_mutation_probability(&config, &half) - 0.25).abs() < 1e-6);
+        assert!((adaptive_mutation_probability(&config, &converged) - 0.45).abs() < 1e-6);
+
+        let fixed = AlgorithmConfig {
+            adaptive_mutation: None,
+            ..config
+        };
+        assert_eq!(adaptive_mutation_probability(&fixed, &converged), 0.05);
+    }
+
+    #[test]
+    fn test_mutation_step_size_cools_down() {
+        let config = AlgorithmConfig {
+            max_generations: 10,
+            max_mutation_step: 4,
+            mutation_cooling: CoolingSchedule::Linear,
+            ..AlgorithmConfig::default()
+        };
+
+        assert_eq!(mutation_step_size(&config, 0), 4);
+        assert_eq!(mutation_step_size(&config, 9), 1);
+
+        let exponential = AlgorithmConfig {
+            mutation_cooling: CoolingSchedule::Exponential,
+            ..config
+        };
+        assert_eq!(mutation_step_size(&exponential, 0), 4);
+        assert_eq!(mutation_step_size(&exponential, 9), 1);
+    }
+
+    #[test]
+    fn test_constant_mutation_step_keeps_the_maximum() {
+        let config = AlgorithmConfig {
+            max_generations: 10,
+            max_mutation_step: 5,
+            mutation_cooling: CoolingSchedule::Constant,
+            ..AlgorithmConfig::default()
+        };
+
+        for generation in 0..config.max_generations {
+            assert_eq!(mutation_step_size(&config, generation), 5);
+        }
+    }
+
+    #[test]
+    fn test_early_mutation_moves_more_genes() {
+        let config = AlgorithmConfig {
+            number_of_periods: 2,
+            max_generations: 10,
+            mutation_probability: 1.0,
+            max_mutation_step: 4,
+            mutation_cooling: CoolingSchedule::Linear,
+            ..AlgorithmConfig::default()
+        };
+        let (parent, _) = opposite_parents();
+
+        let average_moved = |generation| {
+            (0..200)
+                .map(|_| {
+                    let mut individual = parent.clone();
+                    mutate(
+                        &config,
+                        &mut individual,
+                        generation,
+                        &TupleIndex::default(),
+                        &Locks::new(),
+                        &mut rand::thread_rng(),
+                    );
+                    let kept = inherited_fraction(&individual, &parent);
+                    (1.0 - kept) * 20.0
+                })
+                .sum::<f64>()
+                / 200.0
+        };
+
+        assert!(average_moved(0) > average_moved(9));
+    }
+
+    #[test]
+    fn test_swap_mutation_keeps_genes_and_period_sizes() {
+        let config = AlgorithmConfig {
+            number_of_periods: 3,
+            mutation_probability: 1.0,
+            mutation_method: MutationMethod::Swap,
+            ..AlgorithmConfig::default()
+        };
+        let mut individual = Individual::with_chromosomes(vec![
+            Chromosome {
+                id: 0,
+                genes: vec![1, 2, 3, 4],
+            },
+            Chromosome {
+                id: 1,
+                genes: vec![5, 6],
+            },
+            Chromosome {
+                id: 2,
+                genes: vec![],
+            },
+        ]);
+
+        let mut rng = StdRng::seed_from_u64(21);
+        for _ in 0..50 {
+            mutate(
+                &config,
+                &mut individual,
+                0,
+                &TupleIndex::default(),
+                &Locks::new(),
+                &mut rng,
+            );
+
+            let sizes: Vec<usize> = individual
+                .chromosomes
+                .iter()
+                .map(|period| period.genes.len())
+                .collect();
+            let genes: Vec<Gene> = individual
+                .period_genes()
+                .flatten()
+                .copied()
+                .sorted()
+                .collect();
+            assert_eq!(sizes, vec![4, 2, 0]);
+            assert_eq!(genes, (1..=6).collect::<Vec<_>>());
+        }
+        // genes still move between the non-empty periods
+        assert!(individual.chromosomes[1].genes != vec![5, 6]);
+    }
+
+    #[test]
+    fn test_mutate_single_period_is_noop() {
+        let config = AlgorithmConfig {
+            number_of_periods: 1,
+            mutation_probability: 1.0,
+            ..AlgorithmConfig::default()
+        };
+        let mut individual = Individual::with_chromosomes(vec![Chromosome {
+            id: 0,
+            genes: vec![1, 2, 3],
+        }]);
+
+        mutate(
+            &config,
+            &mut individual,
+            0,
+            &TupleIndex::default(),
+            &Locks::new(),
+            &mut rand::thread_rng(),
+        );
+
+        assert_eq!(individual.chromosomes[0].genes, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_mutate_keeps_duplicate_genes() {
+        let config = AlgorithmConfig {
+            number_of_periods: 3,
+            mutation_probability: 1.0,
+            max_mutation_step: 2,
+            mutation_cooling: CoolingSchedule::Linear,
+            ..AlgorithmConfig::default()
+        };
+        let mut individual = Individual::with_chromosomes(vec![
+            Chromosome {
+                id: 0,
+                genes: vec![1, 1, 1, 2],
+            },
+            Chromosome {
+                id: 1,
+                genes: vec![3, 3],
+            },
+            Chromosome::new(2),
+        ]);
+        let gene_counts = |individual: &Individual| {
+            individual
+                .period_genes()
+                .flatten()
+                .copied()
+                .counts()
+                .into_iter()
+                .sorted()
+                .collect::<Vec<_>>()
+        };
+        let before = gene_counts(&individual);
+
+        for generation in 0..10 {
+            mutate(
+                &config,
+                &mut individual,
+                generation,
+                &TupleIndex::default(),
+                &Locks::new(),
+                &mut rand::thread_rng(),
+            );
+            assert_eq!(gene_counts(&individual), before);
+        }
+    }
+
+    #[test]
+    fn test_generation_gap_replaces_part_of_population() {
+        let config = AlgorithmConfig {
+            population_size: 10,
+            number_of_periods: 2,
+            generation_gap: 0.5,
+            ..AlgorithmConfig::default()
+        };
+        // no two tuples share anything, so every child has adaptation 0
+        let tuples: TupleIndex = (1..=20)
+            .map(|id| {
+                let name = id.to_string();
+                tuple(id, &name, &name, &name)
+            })
+            .collect();
+        let (parent, _) = opposite_parents();
+        let population: Population = (1..=10)
+            .map(|i| Individual {
+                adaptation: -i as Adaptation,
+                ..parent.clone()
+            })
+            .collect();
+
+        let positions: Vec<usize> = (0..10).collect();
+        let next = evolve_shard(
+            &config,
+            &population,
+            &population,
+            &positions,
+            0,
+            &tuples,
+            &Locks::new(),
+            None,
+            None,
+        );
+
+        assert_eq!(next.len(), 10);
+        assert_eq!(
+            next.iter()
+                .filter(|i| i.adaptation == 0 as Adaptation)
+                .count(),
+            5
+        );
+        assert_eq!(
+            next[..5].iter().map(|i| i.adaptation).collect::<Vec<_>>(),
+            [-1, -2, -3, -4, -5].map(|a| a as Adaptation)
+        );
+    }
+
+    #[test]
+    fn test_selection_decay_annealing() {
+        let config = AlgorithmConfig {
+            max_generations: 50,
+            selection_annealing: Some(SelectionAnnealing {
+                start_decay: 0.1,
+                end_decay: 0.9,
+            }),
+            ..AlgorithmConfig::default()
+        };
+
+        assert_eq!(selection_decay(&config, 0), 0.1);
+        assert_eq!(selection_decay(&config, 49), 0.9);
+        assert!(selection_decay(&config, 25) > 0.1 && selection_decay(&config, 25) < 0.9);
+
+        let constant = AlgorithmConfig::default();
+        assert_eq!(selection_decay(&constant, 0), DEFAULT_SELECTION_DECAY);
+        assert_eq!(selection_decay(&constant, 99), DEFAULT_SELECTION_DECAY);
+    }
+
+    #[test]
+    fn test_compaction_prefers_empty_periods() {
+        let config = AlgorithmConfig {
+            number_of_periods: 4,
+            compaction_weight: 3 as Adaptation,
+            ..AlgorithmConfig::default()
+        };
+        let tuples: TupleIndex = (1..=4)
+            .map(|id| {
+                let name = id.to_string();
+                tuple(id, &name, &name, &name)
+            })
+            .collect();
+        let place = |periods: [usize; 4]| {
+            let mut chromosomes: Vec<Chromosome> = (0..4).map(Chromosome::new).collect();
+            for (gene, period) in periods.into_iter().enumerate() {
+                chromosomes[period].genes.push(gene as i32 + 1);
+            }
+            Individual::with_chromosomes(chromosomes)
+        };
+
+        let compact = calculate_fitness(&config, &place([0, 0, 1, 1]), &tuples);
+        let spread = calculate_fitness(&config, &place([0, 1, 2, 3]), &tuples);
+
+        assert_eq!(compact, -6 as Adaptation);
+        assert_eq!(spread, -12 as Adaptation);
+        assert!(compact > spread);
+    }
+
+    #[test]
+    fn test_genome_distance() {
+        let (fitter, weaker) = opposite_parents();
+
+        assert_eq!(genome_distance(&fitter, &fitter.clone()), 0);
+        assert_eq!(genome_distance(&fitter, &weaker), 20);
+
+        let mut moved = fitter.clone();
+        let gene = moved.chromosomes[0].genes.pop().unwrap();
+        moved.chromosomes[1].genes.push(gene);
+        assert_eq!(genome_distance(&fitter, &moved), 1);
+
+        let mut missing = fitter.clone();
+        missing.chromosomes[0].genes.pop();
+        assert_eq!(genome_distance(&fitter, &missing), 1);
+        assert_eq!(genome_distance(&missing, &fitter), 1);
+    }
+
+    #[test]
+    fn test_population_diversity_averages_genome_distance() {
+        let config = AlgorithmConfig {
+            population_size: 12,
+            number_of_periods: 4,
+            random_seed: Some(9),
+            ..AlgorithmConfig::default()
+        };
+        let tuples = testing::generate_synthetic_problem(30, 4, 4, 4, 2);
+        let population = create_first_population(&config, &tuples);
+
+        let distances: Vec<usize> = population
+            .iter()
+            .tuple_combinations()
+            .map(|(first, second)| genome_distance(first, second))
+            .collect();
+        let average = distances.iter().sum::<usize>() as f64 / distances.len() as f64;
+        assert!((population_diversity(&population) - average / 30.0).abs() < 1e-9);
+
+        let converged = vec![population[0].clone(); 12];
+        assert_eq!(population_diversity(&converged), 0.0);
+        assert_eq!(population_diversity(&population[..1]), 0.0);
+    }
+
+    #[test]
+    fn test_low_diversity_brings_in_immigrants() {
+        let config = AlgorithmConfig {
+            population_size: 10,
+            number_of_periods: 4,
+            random_seed: Some(2),
+            diversity_threshold: 0.1,
+            immigrant_count: 3,
+            ..AlgorithmConfig::default()
+        };
+        let tuples = TupleIndex::from(testing::generate_synthetic_problem(30, 4, 4, 4, 2));
+        let mut converged = create_first_population(&config, &tuples)[0].clone();
+        converged.adaptation = calculate_fitness_cached(&config, &mut converged, &tuples);
+        let mut population = vec![converged.clone(); 10];
+
+        // a diverse population takes no immigrants
+        let diverse = create_first_population(&config, &tuples);
+        assert!(random_immigrants(&config, &diverse, 0, &tuples, &Locks::new()).is_empty());
+
+        let immigrants = random_immigrants(&config, &population, 0, &tuples, &Locks::new());
+        assert_eq!(immigrants.len(), 3);
+        for immigrant in &immigrants {
+            assert_eq!(validate_completeness(immigrant, &tuples), Ok(()));
+            assert_eq!(
+                immigrant.adaptation,
+                calculate_fitness(&config, immigrant, &tuples)
+            );
+        }
+        // immigrants of the same generation are different individuals
+        assert!(genome_distance(&immigrants[0], &immigrants[1]) > 0);
+
+        integrate_migrants(&mut population, immigrants);
+        assert_eq!(population.len(), 10);
+        assert_eq!(
+            population
+                .iter()
+                .filter(|individual| genome_distance(individual, &converged) > 0)
+                .count(),
+            3
+        );
+        assert!(population_diversity(&population) > 0.0);
+    }
+
+    #[test]
+    fn test_repair_period_counts_copies_the_fittest() {
+        let individual = |adaptation: i32, number_of_periods: i32| Individual {
+            adaptation: adaptation as Adaptation,
+            hard_adaptation: adaptation as Adaptation,
+            ..Individual::with_chromosomes((0..number_of_periods).map(Chromosome::new).collect())
+        };
+        let mut population = vec![
+            individual(-5, 4),
+            individual(0, 3),
+            individual(-1, 4),
+            individual(-2, 5),
+        ];
+
+        assert_eq!(repair_period_counts(&mut population, 4), vec![1, 3]);
+        assert!(population
+            .iter()
+            .all(|individual| individual.chromosomes.len() == 4));
+        assert_eq!(population[1].adaptation, -1 as Adaptation);
+        assert_eq!(population[3].adaptation, -1 as Adaptation);
+
+        // without any well formed individual there is nothing to copy
+        assert!(repair_period_counts(&mut population, 6).is_empty());
+    }
+
+    #[test]
+    fn test_repair_terminates_when_capacity_is_saturated() {
+        let config = AlgorithmConfig {
+            number_of_periods: 3,
+            period_capacity: Some(1),
+            max_repair_attempts: 5,
+            ..AlgorithmConfig::default()
+        };
+        let periods = vec![
+            Chromosome {
+                id: 0,
+                genes: vec![1, 2, 3],
+            },
+            Chromosome {
+                id: 1,
+                genes: vec![4],
+            },
+            Chromosome {
+                id: 2,
+                genes: vec![5, 6],
+            },
+        ];
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..100 {
+            assert_eq!(repair_period(&config, &periods, &mut rng), 1);
+        }
+
+        let with_room = AlgorithmConfig {
+            period_capacity: Some(2),
+            max_repair_attempts: 1000,
+            ..config
+        };
+        assert_eq!(repair_period(&with_room, &periods, &mut rng), 1);
+    }
+
+    #[test]
+    fn test_cached_fitness_recomputes_only_changed_periods() {
+        let config = AlgorithmConfig {
+            number_of_periods: 4,
+            ..AlgorithmConfig::default()
+        };
+        let tuples = TupleIndex::from(vec![
+            tuple(1, "Math", "A", "Smith"),
+            tuple(2, "Art", "A", "Jones"),
+            tuple(3, "Physics", "B", "Smith"),
+            tuple(4, "Biology", "B", "Brown"),
+            tuple(5, "Music", "C", "Brown"),
+        ]);
+        let mut individual = Individual::with_chromosomes(vec![
+            Chromosome {
+                id: 0,
+                genes: vec![1, 2],
+            },
+            Chromosome {
+                id: 1,
+                genes: vec![3],
+            },
+            Chromosome {
+                id: 2,
+                genes: vec![4, 5],
+            },
+            Chromosome::new(3),
+        ]);
+
+        let fitness = calculate_fitness_cached(&config, &mut individual, &tuples);
+        assert_eq!(fitness, calculate_fitness(&config, &individual, &tuples));
+        let cache_before = individual.period_fitness.clone();
+
+        // move Physics next to Math, both are taught by Smith
+        individual.move_gene(1, 0, 0);
+        let dirty: Vec<usize> = individual
+            .period_fitness
+            .iter()
+            .positions(|cached| cached.dirty)
+            .collect();
+        assert_eq!(dirty, vec![0, 1]);
+
+        let fitness = calculate_fitness_cached(&config, &mut individual, &tuples);
+        assert_eq!(fitness, calculate_fitness(&config, &individual, &tuples));
+        assert!(individual.period_fitness.iter().all(|cached| !cached.dirty));
+        assert_eq!(individual.period_fitness[2..], cache_before[2..]);
+        assert_ne!(individual.period_fitness[0], cache_before[0]);
+
+        // clean periods aren't recomputed
+        individual.period_fitness[3].fitness = -1 as Adaptation;
+        assert_eq!(
+            calculate_fitness_cached(&config, &mut individual, &tuples),
+            fitness - 1 as Adaptation
+        );
+    }
+
+    #[test]
+    fn test_list_conflicts_matches_fitness() {
+        let tuples = TupleIndex::from(vec![
+            tuple(1, "Math", "A", "Smith"),
+            tuple(2, "Art", "A", "Smith"),
+            tuple(3, "Physics", "A", "Jones"),
+            tuple(4, "Math", "C", "Brown"),
+        ]);
+        let individual = Individual::with_chromosomes(vec![
+            Chromosome {
+                id: 0,
+                genes: vec![1, 2, 3],
+            },
+            Chromosome {
+                id: 1,
+                genes: vec![4],
+            },
+        ]);
+
+        let conflicts = list_conflicts(&individual, &tuples);
+        let penalty: Adaptation = conflicts.iter().map(|c| c.conflict_type.penalty()).sum();
+
+        assert_eq!(conflicts.len(), 4);
+        assert!(conflicts.iter().all(|c| c.period == 0));
+        assert_eq!(
+            calculate_fitness(&AlgorithmConfig::default(), &individual, &tuples),
+            -2 as Adaptation * penalty
+        );
+    }
+
+    #[test]
+    fn test_violations() {
+        let tuples = TupleIndex::from(vec![
+            Tuple {
+                group: "1a".to_string(),
+                ..tuple(1, "Math", "A", "Smith")
+            },
+            tuple(2, "Math", "A", "Smith"),
+            tuple(3, "Art", "B", "Smith"),
+            Tuple {
+                group: "1a".to_string(),
+                ..tuple(4, "Physics", "B", "Jones")
+            },
+            tuple(5, "Biology", "C", "Brown"),
+        ]);
+        let individual = Individual::with_chromosomes(vec![
+            Chromosome {
+                id: 0,
+                genes: vec![5],
+            },
+            Chromosome {
+                id: 1,
+                genes: vec![4, 3, 2, 1],
+            },
+        ]);
+
+        let violations = violations(&individual, &tuples);
+        let smith = || "Smith".to_string();
+        assert_eq!(
+            violations,
+            vec![
+                Violation::TeacherRepeatedInRoom {
+                    period: 1,
+                    teacher: smith(),
+                    room: "A".to_string(),
+                    tuples: (1, 2),
+                },
+                Violation::SubjectRepeated {
+                    period: 1,
+                    teacher: smith(),
+                    label: "Math".to_string(),
+                    tuples: (1, 2),
+                },
+                Violation::TeacherDoubleBooked {
+                    period: 1,
+                    teacher: smith(),
+                    tuples: (1, 3),
+                },
+                Violation::GroupDoubleBooked {
+                    period: 1,
+                    group: "1a".to_string(),
+                    tuples: (1, 4),
+                },
+                Violation::TeacherDoubleBooked {
+                    period: 1,
+                    teacher: smith(),
+                    tuples: (2, 3),
+                },
+                Violation::RoomDoubleBooked {
+                    period: 1,
+                    room: "B".to_string(),
+                    tuples: (3, 4),
+                },
+            ]
+        );
+
+        let config = AlgorithmConfig::default();
+        let penalty: Adaptation = violations
+            .iter()
+            .map(|violation| violation.penalty(&config.fitness_weights))
+            .sum();
+        assert_eq!(calculate_fitness(&config, &individual, &tuples), -penalty);
+    }
+
+    #[test]
+    fn test_violations_penalty_matches_fitness() {
+        let config = AlgorithmConfig {
+            number_of_periods: 5,
+            population_size: 20,
+            ..AlgorithmConfig::default()
+        };
+        let tuples = TupleIndex::from(testing::generate_synthetic_problem(60, 6, 5, 6, 3));
+
+        for individual in create_first_population(&config, &tuples) {
+            let penalty: Adaptation = violations(&individual, &tuples)
+                .iter()
+                .map(|violation| violation.penalty(&config.fitness_weights))
+                .sum();
+            assert_eq!(calculate_fitness(&config, &individual, &tuples), -penalty);
+        }
+    }
+
+    #[test]
+    fn test_validate_completeness() {
+        let tuples = vec![
+            tuple(1, "Math", "A", "Smith"),
+            tuple(2, "Art", "B", "Jones"),
+            tuple(3, "Physics", "C", "Brown"),
+        ];
+        let individual = |first: Vec<Gene>, second: Vec<Gene>| {
+            Individual::with_chromosomes(vec![
+                Chromosome {
+                    id: 0,
+                    genes: first,
+                },
+                Chromosome {
+                    id: 1,
+                    genes: second,
+                },
+            ])
+        };
+
+        assert_eq!(
+            validate_completeness(&individual(vec![3, 1], vec![2]), &tuples),
+            Ok(())
+        );
+        assert_eq!(
+            validate_completeness(&individual(vec![3], vec![2]), &tuples),
+            Err(vec![CompletenessError::Missing(1)])
+        );
+        assert_eq!(
+            validate_completeness(&individual(vec![1, 2], vec![2, 3, 2]), &tuples),
+            Err(vec![CompletenessError::Duplicated { id: 2, count: 3 }])
+        );
+        assert_eq!(
+            validate_completeness(&individual(vec![7, 1, 1], vec![2]), &tuples),
+            Err(vec![
+                CompletenessError::Duplicated { id: 1, count: 2 },
+                CompletenessError::Missing(3),
+                CompletenessError::Unknown(7),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_fitness_share_without_bias_is_even() {
+        let share = |first: i32, second: i32, bias| {
+            fitness_share(first as Adaptation, second as Adaptation, bias)
+        };
+
+        assert_eq!(share(0, -500, 0.0), 0.5);
+        assert!(share(0, -500, 1.0) > 0.5);
+        assert!(share(-500, 0, 1.0) < 0.5);
+    }
+
+    #[test]
+    fn test_biased_crossover_favours_fitter_parent() {
+        let config = AlgorithmConfig {
+            number_of_periods: 2,
+            crossover_fitness_bias: 1.0,
+            ..AlgorithmConfig::default()
+        };
+        let (fitter, weaker) = opposite_parents();
+        let population = vec![fitter.clone(), weaker];
+
+        let trials = 500;
+        let average = (0..trials)
+            .map(|_| {
+                inherited_fraction(
+                    &crossover(
+                        &config,
+                        &population,
+                        0,
+                        &Locks::new(),
+                        None,
+                        &mut rand::thread_rng(),
+                    ),
+                    &fitter,
+                )
+            })
+            .sum::<f64>()
+            / trials as f64;
+
+        assert!(average > 0.8, "average inherited fraction {}", average);
+    }
+
+    #[test]
+    fn test_uniform_crossover_keeps_every_gene_once() {
+        let config = AlgorithmConfig {
+            number_of_periods: 2,
+            crossover_method: CrossoverMethod::Uniform,
+            crossover_probability: 1.0,
+            ..AlgorithmConfig::default()
+        };
+        let (fitter, mut weaker) = opposite_parents();
+        // uneven periods, so positions only one parent has are mated too
+        let moved = weaker.chromosomes[0].genes.drain(..3).collect::<Vec<_>>();
+        weaker.chromosomes[1].genes.extend(moved);
+        let population = vec![fitter, weaker];
+
+        let mut rng = StdRng::seed_from_u64(8);
+        for _ in 0..200 {
+            let child = crossover(&config, &population, 0, &Locks::new(), None, &mut rng);
+            let genes: Vec<Gene> = child
+                .chromosomes
+                .iter()
+                .flat_map(|c| c.genes.iter().cloned())
+                .sorted()
+                .collect();
+
+            assert_eq!(child.chromosomes.len(), 2);
+            assert_eq!(genes, (1..=20).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_partially_mapped_crossover_needs_no_repair() {
+        let (fitter, weaker) = opposite_parents();
+        let period_of = |individual: &Individual, gene: Gene| {
+            individual
+                .chromosomes
+                .iter()
+                .position(|period| period.genes.contains(&gene))
+                .unwrap()
+        };
+
+        let mut rng = StdRng::seed_from_u64(13);
+        for _ in 0..200 {
+            let child =
+                partially_mapped_crossover(&fitter, &weaker, 0.5, 0.0, &Locks::new(), &mut rng);
+            let genes: Vec<Gene> = child
+                .chromosomes
+                .iter()
+                .flat_map(|c| c.genes.iter().cloned())
+                .sorted()
+                .collect();
+
+            assert_eq!(genes, (1..=20).collect::<Vec<_>>());
+            // every gene is where one of the parents put it
+            for gene in genes {
+                let period = period_of(&child, gene);
+                assert!(period == period_of(&fitter, gene) || period == period_of(&weaker, gene));
+            }
+        }
+
+        let locks = Locks::from([(1, 0)]);
+        let child = partially_mapped_crossover(&fitter, &weaker, 0.0, 0.0, &locks, &mut rng);
+        assert_eq!(period_of(&child, 1), 0);
+    }
+
+    #[test]
+    fn test_crossover_keeps_locked_genes_in_place() {
+        let config = AlgorithmConfig {
+            number_of_periods: 2,
+            ..AlgorithmConfig::default()
+        };
+        let (mut first, mut second) = opposite_parents();
+
+        // lock gene 1 to the first and gene 11 to the second period in both parents
+        first.chromosomes[0].genes.retain(|g| *g != 1);
+        first.chromosomes[1].genes.retain(|g| *g != 11);
+        second.chromosomes[0].genes.retain(|g| *g != 1);
+        second.chromosomes[1].genes.retain(|g| *g != 11);
+        for parent in [&mut first, &mut second] {
+            parent.chromosomes[0].genes.push(1);
+            parent.chromosomes[1].genes.push(11);
+        }
+        let locks = Locks::from([(1, 0), (11, 1)]);
+        assert!(first.respects_locks(&locks) && second.respects_locks(&locks));
+
+        let population = vec![first, second];
+
+        for _ in 0..200 {
+            let child = crossover(
+                &config,
+                &population,
+                0,
+                &locks,
+                None,
+                &mut rand::thread_rng(),
+            );
+            let genes: Vec<Gene> = child
+                .chromosomes
+                .iter()
+                .flat_map(|c| c.genes.iter().cloned())
+                .sorted()
+                .collect();
+
+            assert!(child.respects_locks(&locks));
+            assert_eq!(genes, (1..=20).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_pinned_genes_stay_in_their_period() {
+        let problem = testing::generate_synthetic_problem(30, 4, 4, 4, 2);
+        let locks = Locks::from([(problem[0].id, 0), (problem[7].id, 2), (problem[12].id, 2)]);
+        let tuples = TupleIndex::from(problem);
+        let mut rng = StdRng::seed_from_u64(4);
+
+        for (mutation_method, crossover_method) in [
+            (MutationMethod::Move, CrossoverMethod::SinglePoint),
+            (MutationMethod::Swap, CrossoverMethod::Uniform),
+            (
+                MutationMethod::ConflictDirected,
+                CrossoverMethod::PartiallyMapped,
+            ),
+        ] {
+            let config = AlgorithmConfig {
+                population_size: 10,
+                number_of_periods: 4,
+                mutation_probability: 1.0,
+                max_mutation_step: 3,
+                mutation_cooling: CoolingSchedule::Linear,
+                max_generations: 50,
+                mutation_method,
+                crossover_method,
+                random_seed: Some(8),
+                ..AlgorithmConfig::default()
+            };
+            let mut population = create_first_population_with_locks(&config, &tuples, &locks);
+            assert!(population
+                .iter()
+                .all(|individual| individual.respects_locks(&locks)));
+
+            for generation in 0..50 {
+                let child = crossover(&config, &population, generation, &locks, None, &mut rng);
+                assert!(child.respects_locks(&locks));
+
+                for individual in &mut population {
+                    mutate(&config, individual, generation, &tuples, &locks, &mut rng);
+                    assert!(individual.respects_locks(&locks));
+                    assert_eq!(validate_completeness(individual, &tuples), Ok(()));
+                }
+            }
+
+            let converged = vec![population[0].clone(); 10];
+            let immigrants = random_immigrants(
+                &AlgorithmConfig {
+                    diversity_threshold: 0.1,
+                    immigrant_count: 3,
+                    ..config
+                },
+                &converged,
+                0,
+                &tuples,
+                &locks,
+            );
+            assert_eq!(immigrants.len(), 3);
+            assert!(immigrants
+                .iter()
+                .all(|immigrant| immigrant.respects_locks(&locks)));
+        }
+    }
+
+    #[test]
+    fn test_effective_parameters_follow_schedules() {
+        let config = AlgorithmConfig {
+            max_generations: 11,
+            population_size: 100,
+            max_mutation_step: 11,
+            mutation_cooling: CoolingSchedule::Linear,
+            generation_gap: 0.5,
+            selection_annealing: Some(SelectionAnnealing {
+                start_decay: 0.1,
+                end_decay: 1.1,
+            }),
+            ..AlgorithmConfig::default()
+        };
+
+        for (generation, mutation_step, decay) in [(0, 11, 0.1), (5, 6, 0.6), (10, 1, 1.1)] {
+            let parameters = effective_parameters(&config, generation);
+
+            assert_eq!(parameters.mutation_probability, config.mutation_probability);
+            assert_eq!(parameters.mutation_step, mutation_step);
+            assert!((parameters.selection_decay - decay).abs() < 1e-9);
+            assert_eq!(parameters.generation_gap, 0.5);
+        }
+    }
+
+    #[cfg(feature = "float-fitness")]
+    #[test]
+    fn test_fractional_weights_are_distinguishable() {
+        let config = AlgorithmConfig {
+            number_of_periods: 4,
+            subject_spacing_weight: 0.4,
+            ..AlgorithmConfig::default()
+        };
+        let tuples: TupleIndex = (1..=3)
+            .map(|id| tuple(id, "Math", &id.to_string(), &id.to_string()))
+            .collect();
+        let place = |periods: [usize; 3]| {
+            let mut chromosomes: Vec<Chromosome> = (0..4).map(Chromosome::new).collect();
+            for (gene, period) in periods.into_iter().enumerate() {
+                chromosomes[period].genes.push(gene as i32 + 1);
+            }
+            Individual::with_chromosomes(chromosomes)
+        };
+
+        // a weight rounded to an integer would be 0 and both would have the same fitness
+        let bunched = calculate_fitness(&config, &place([0, 0, 0]), &tuples);
+        let spread = calculate_fitness(&config, &place([0, 1, 3]), &tuples);
+
+        assert!((bunched - -1.2).abs() < 1e-9);
+        assert!((spread - -0.4).abs() < 1e-9);
+        assert!(spread > bunched);
+    }
+
+    #[test]
+    fn test_first_population_assigns_every_tuple_once() {
+        let config = AlgorithmConfig {
+            population_size: 64,
+            number_of_periods: 6,
+            random_seed: Some(4),
+            ..AlgorithmConfig::default()
+        };
+        let tuples = testing::generate_synthetic_problem(50, 6, 5, 6, 3);
+
+        let population = create_first_population(&config, &tuples);
+
+        assert_eq!(population.len(), 64);
+        for individual in &population {
+            assert_eq!(individual.chromosomes.len(), 6);
+            assert_eq!(validate_completeness(individual, &tuples), Ok(()));
+        }
+        // a seeded population doesn't depend on the order the threads create it in
+        let again = create_first_population(&config, &tuples);
+        assert_eq!(
+            serde_json::to_string(&population).unwrap(),
+            serde_json::to_string(&again).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_first_population_is_diverse() {
+        // 2 periods and 3 tuples can be arranged in only 8 ways
+        let config = AlgorithmConfig {
+            population_size: 8,
+            number_of_periods: 2,
+            min_initial_distance: 1,
+            max_initial_attempts: 1_000,
+            ..AlgorithmConfig::default()
+        };
+        let tuples: Vec<Tuple> = (1..=3).map(|id| tuple(id, "Math", "A", "Smith")).collect();
+
+        let population = create_first_population(&config, &tuples);
+
+        assert_eq!(population.len(), 8);
+        for (first, second) in population.iter().tuple_combinations() {
+            assert!(genome_distance(first, second) >= 1);
+        }
+    }
+
+    #[test]
+    fn test_swap_crossover_keeps_genes_without_repair() {
+        let config = AlgorithmConfig {
+            number_of_periods: 2,
+            ..AlgorithmConfig::default()
+        };
+        let (fitter, weaker) = opposite_parents();
+        let sorted_genes = |individual: &Individual| -> Vec<Gene> {
+            individual
+                .period_genes()
+                .flatten()
+                .copied()
+                .sorted()
+                .collect()
+        };
+
+        for _ in 0..100 {
+            let child = swap_crossover(
+                &fitter,
+                &weaker,
+                0.5,
+                0.0,
+                &Locks::new(),
+                &mut rand::thread_rng(),
+            )
+            .unwrap();
+
+            // swaps keep the period sizes, the repair would put lost genes anywhere
+            for (period, parent_period) in child.chromosomes.iter().zip(&fitter.chromosomes) {
+                assert_eq!(period.genes.len(), parent_period.genes.len());
+            }
+            assert_eq!(sorted_genes(&child), (1..=20).collect::<Vec<_>>());
+        }
+
+        let population = vec![fitter.clone(), weaker.clone()];
+        let repaired = crossover(
+            &config,
+            &population,
+            0,
+            &Locks::new(),
+            None,
+            &mut rand::thread_rng(),
+        );
+        assert_eq!(sorted_genes(&repaired), sorted_genes(&fitter));
+
+        let mut uneven = weaker;
+        uneven.move_gene(0, 0, 1);
+        assert!(swap_crossover(
+            &fitter,
+            &uneven,
+            0.5,
+            0.0,
+            &Locks::new(),
+            &mut rand::thread_rng()
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_conflict_directed_mutation_moves_conflicting_genes() {
+        let config = AlgorithmConfig {
+            number_of_periods: 2,
+            mutation_probability: 1.0,
+            mutation_method: MutationMethod::ConflictDirected,
+            ..AlgorithmConfig::default()
+        };
+        // only tuples 1 and 2 share a room, the other 8 don't conflict
+        let tuples: TupleIndex = (1..=10)
+            .map(|id| {
+                let room = if id <= 2 {
+                    "A".to_string()
+                } else {
+                    id.to_string()
+                };
+                tuple(id, &id.to_string(), &room, &id.to_string())
+            })
+            .collect();
+        // the empty period mutates first and has nothing to give away
+        let parent = Individual::with_chromosomes(vec![
+            Chromosome::new(0),
+            Chromosome {
+                id: 1,
+                genes: (1..=10).collect(),
+            },
+        ]);
+
+        let trials = 200;
+        let conflicting_moved = (0..trials)
+            .filter(|_| {
+                let mut individual = parent.clone();
+                mutate(
+                    &config,
+                    &mut individual,
+                    0,
+                    &tuples,
+                    &Locks::new(),
+                    &mut rand::thread_rng(),
+                );
+                let moved = &individual.chromosomes[0].genes;
+                moved.iter().any(|gene| *gene <= 2)
+            })
+            .count();
+
+        // uniform selection would move one of them in 20% of the trials
+        assert_eq!(conflicting_moved, trials);
+    }
+
+    #[test]
+    fn test_min_child_improvement_keeps_parents() {
+        let config = AlgorithmConfig {
+            population_size: 10,
+            number_of_periods: 2,
+            min_child_improvement: Some(1_000 as Adaptation),
+            ..AlgorithmConfig::default()
+        };
+        // every child has adaptation 0, which is far from improving on its parent by 1000
+        let tuples: TupleIndex = (1..=20)
+            .map(|id| tuple(id, &id.to_string(), &id.to_string(), &id.to_string()))
+            .collect();
+        let (parent, _) = opposite_parents();
+        let population: Population = (1..=10)
+            .map(|i| Individual {
+                adaptation: -i as Adaptation,
+                ..parent.clone()
+            })
+            .collect();
+        let positions: Vec<usize> = (0..10).collect();
+
+        let evolve = |config: &AlgorithmConfig| {
+            evolve_shard(
+                config,
+                &population,
+                &population,
+                &positions,
+                0,
+                &tuples,
+                &Locks::new(),
+                None,
+                None,
+            )
+        };
+
+        assert!(evolve(&config)
+            .iter()
+            .all(|individual| individual.adaptation < Adaptation::default()));
+
+        let accepting = AlgorithmConfig {
+            min_child_improvement: None,
+            ..config
+        };
+        assert!(evolve(&accepting)
+            .iter()
+            .all(|individual| individual.adaptation == Adaptation::default()));
+    }
+
+    #[test]
+    fn test_crossover_takes_left_from_mother_and_right_from_father() {
+        let config = AlgorithmConfig {
+            number_of_periods: 2,
+            ..AlgorithmConfig::default()
+        };
+        // different genes, so no gene of one parent can be mistaken for the other's
+        let parent = |adaptation: i32, first_gene: i32| Individual {
+            adaptation: adaptation as Adaptation,
+            ..Individual::with_chromosomes(vec![
+                Chromosome {
+                    id: 0,
+                    genes: (first_gene..first_gene + 4).collect(),
+                },
+                Chromosome {
+                    id: 1,
+                    genes: (first_gene + 4..first_gene + 8).collect(),
+                },
+            ])
+        };
+        let population = vec![parent(0, 1), parent(-1, 11)];
+
+        for _ in 0..20 {
+            let trace = SelectionTrace::default();
+            let child = crossover(
+                &config,
+                &population,
+                0,
+                &Locks::new(),
+                Some(&trace),
+                &mut rand::thread_rng(),
+            );
+            let record = trace.records()[0];
+            let (mother, father) = (&population[record.first], &population[record.second]);
+
+            for (index, period) in child.chromosomes.iter().enumerate() {
+                let mother_genes = &mother.chromosomes[index].genes;
+                let father_genes = &father.chromosomes[index].genes;
+
+                // genes lost by the splice are appended by the repair, after the first four
+                assert!(
+                    (0..=4).any(|point| period.genes[..4]
+                        .iter()
+                        .eq(mother_genes[..point].iter().chain(&father_genes[point..]))),
+                    "{:?} isn't spliced from {:?} and {:?}",
+                    period.genes,
+                    mother_genes,
+                    father_genes
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_children_copy_parents_without_crossover() {
+        let config = AlgorithmConfig {
+            population_size: 10,
+            number_of_periods: 4,
+            mutation_probability: 0.0,
+            crossover_probability: 0.0,
+            ..AlgorithmConfig::default()
+        };
+        let tuples = TupleIndex::from(testing::generate_synthetic_problem(40, 5, 4, 4, 3));
+        let mut population = create_first_population(&config, &tuples);
+        for individual in &mut population {
+            individual.adaptation = calculate_fitness_cached(&config, individual, &tuples);
+        }
+        datatypes::sort_by_adaptation(&mut population);
+
+        let genes = |individual: &Individual| {
+            individual
+                .period_genes()
+                .map(<[Gene]>::to_vec)
+                .collect::<Vec<_>>()
+        };
+        let positions: Vec<usize> = (0..10).collect();
+        let next = evolve_shard(
+            &config,
+            &population,
+            &population,
+            &positions,
+            0,
+            &tuples,
+            &Locks::new(),
+            None,
+            None,
+        );
+
+        for child in &next {
+            assert!(population.iter().any(
+                |parent| genes(parent) == genes(child) && parent.adaptation == child.adaptation
+            ));
+        }
+    }
+
+    #[test]
+    fn test_dead_children_are_replaced() {
+        let config = AlgorithmConfig {
+            population_size: 10,
+            number_of_periods: 4,
+            max_dead_retries: 2,
+            ..AlgorithmConfig::default()
+        };
+        let tuples = TupleIndex::from(testing::generate_synthetic_problem(60, 4, 4, 4, 5));
+        let mut population = create_first_population(&config, &tuples);
+        for individual in &mut population {
+            individual.adaptation = calculate_fitness_cached(&config, individual, &tuples);
+        }
+        datatypes::sort_by_adaptation(&mut population);
+
+        // only as good as the best random individual lives
+        let threshold = population[0].adaptation;
+        let config = AlgorithmConfig {
+            dead_threshold: Some(threshold),
+            ..config
+        };
+        let positions: Vec<usize> = (0..10).collect();
+
+        for generation in 0..5 {
+            let next = evolve_shard(
+                &config,
+                &population,
+                &population,
+                &positions,
+                generation,
+                &tuples,
+                &Locks::new(),
+                None,
+                None,
+            );
+
+            for (old, new) in population.iter().zip(&next) {
+                assert!(new.adaptation >= threshold || new.period_genes().eq(old.period_genes()));
+            }
+
+            population = next;
+            datatypes::sort_by_adaptation(&mut population);
+        }
+    }
+
+    #[test]
+    fn test_children_created_again_are_counted() {
+        let config = AlgorithmConfig {
+            population_size: 10,
+            number_of_periods: 4,
+            max_dead_retries: 2,
+            ..AlgorithmConfig::default()
+        };
+        let tuples = TupleIndex::from(testing::generate_synthetic_problem(60, 4, 4, 4, 5));
+        let mut population = create_first_population(&config, &tuples);
+        for individual in &mut population {
+            individual.adaptation = calculate_fitness_cached(&config, individual, &tuples);
+        }
+        datatypes::sort_by_adaptation(&mut population);
+        let positions: Vec<usize> = (0..10).collect();
+
+        let evaluations = |config: &AlgorithmConfig| {
+            let evaluations = AtomicU64::new(0);
+            evolve_shard(
+                config,
+                &population,
+                &population,
+                &positions,
+                0,
+                &tuples,
+                &Locks::new(),
+                None,
+                Some(&evaluations),
+            );
+            evaluations.into_inner()
+        };
+
+        assert_eq!(evaluations(&config), offspring_count(&config) as u64);
+        // no child is ever good enough, every one is created 3 times
+        let config = AlgorithmConfig {
+            dead_threshold: Some(1 as Adaptation),
+            ..config
+        };
+        assert_eq!(evaluations(&config), 3 * offspring_count(&config) as u64);
+    }
+
+    #[test]
+    fn test_tournament_selection_favours_fit_individuals() {
+        let population: Population = (0..10)
+            .map(|i| Individual {
+                adaptation: -i as Adaptation,
+                ..Individual::default()
+            })
+            .collect();
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let mean_rank = |method: SelectionMethod, rng: &mut StdRng| {
+            let trace = SelectionTrace::default();
+            for _ in 0..2_000 {
+                rand_parents(
+                    &population,
+                    method,
+                    DEFAULT_SELECTION_DECAY,
+                    DEFAULT_SELECTION_OFFSET,
+                    0,
+                    Some(&trace),
+                    rng,
+                );
+            }
+            let records = trace.records();
+            records.iter().map(|r| r.first as f64).sum::<f64>() / records.len() as f64
+        };
+
+        // a uniform draw has the mean rank 4.5, the fittest of three draws about 2.0
+        let pairs = mean_rank(SelectionMethod::Tournament { size: 2 }, &mut rng);
+        let triples = mean_rank(SelectionMethod::Tournament { size: 3 }, &mut rng);
+        assert!(triples < 2.5, "{}", triples);
+        assert!(triples < pairs && pairs < 4.0, "{} {}", triples, pairs);
+
+        let linear = mean_rank(SelectionMethod::RankLinear, &mut rng);
+        assert!(linear < 4.0, "{}", linear);
+
+        // a tournament of the whole population still finds a different second parent
+        let trace = SelectionTrace::default();
+        for _ in 0..100 {
+            rand_parents(
+                &population,
+                SelectionMethod::Tournament { size: 1_000_000 },
+                DEFAULT_SELECTION_DECAY,
+                DEFAULT_SELECTION_OFFSET,
+                0,
+                Some(&trace),
+                &mut rng,
+            );
+        }
+        assert!(trace
+            .records()
+            .iter()
+            .all(|record| record.first != record.second));
+    }
+
+    #[test]
+    fn test_selection_decay_changes_distribution() {
+        let population: Population = (0..20)
+            .map(|i| Individual {
+                adaptation: -i as Adaptation,
+                ..Individual::default()
+            })
+            .collect();
+        let mut rng = StdRng::seed_from_u64(2);
+
+        let mean_rank = |decay: f64, rng: &mut StdRng| {
+            let trace = SelectionTrace::default();
+            for _ in 0..2_000 {
+                rand_parents(
+                    &population,
+                    SelectionMethod::ExponentialRank,
+                    decay,
+                    DEFAULT_SELECTION_OFFSET,
+                    0,
+                    Some(&trace),
+                    rng,
+                );
+            }
+            let records = trace.records();
+            records.iter().map(|r| r.first as f64).sum::<f64>() / records.len() as f64
+        };
+
+        // about 1 / (e^a - 1), capped by the population size: 6.5 for a = 0.1, 0.6 for a = 1
+        let gentle = mean_rank(0.1, &mut rng);
+        let steep = mean_rank(1.0, &mut rng);
+        assert!(gentle > 5.0 && steep < 1.0, "{} {}", gentle, steep);
+    }
+
+    #[test]
+    fn test_parents_differ_when_only_the_fittest_has_weight() {
+        let population: Population = (0..5)
+            .map(|i| Individual {
+                adaptation: -i as Adaptation,
+                ..Individual::default()
+            })
+            .collect();
+        let mut rng = StdRng::seed_from_u64(3);
+
+        // every rank but the first underflows, the second parent is drawn from the rest
+        for _ in 0..100 {
+            let (first, second) = rand_parents(
+                &population,
+                SelectionMethod::ExponentialRank,
+                800.0,
+                DEFAULT_SELECTION_OFFSET,
+                0,
+                None,
+                &mut rng,
+            );
+            assert_eq!(first.adaptation, 0 as Adaptation);
+            assert_ne!(second.adaptation, first.adaptation);
+        }
+    }
+
+    #[test]
+    fn test_first_unselectable_rank() {
+        let config = AlgorithmConfig {
+            population_size: 10_000,
+            ..AlgorithmConfig::default()
+        };
+        // e^(-0.3 * x + 2) underflows, subnormals included, once -0.3 * x + 2 < -745.1
+        assert_eq!(first_unselectable_rank(&config), Some(2_491));
+
+        let small = AlgorithmConfig {
+            population_size: 100,
+            ..config
+        };
+        assert_eq!(first_unselectable_rank(&small), None);
+
+        // annealing towards a steep decay makes the last generations pickier
+        let annealed = AlgorithmConfig {
+            selection_annealing: Some(SelectionAnnealing {
+                start_decay: 0.1,
+                end_decay: 30.0,
+            }),
+            ..small
+        };
+        assert_eq!(first_unselectable_rank(&annealed), Some(25));
+    }
+
+    #[test]
+    fn test_seeded_runs_are_reproducible() {
+        let config = AlgorithmConfig {
+            population_size: 20,
+            number_of_periods: 6,
+            mutation_probability: 0.3,
+            random_seed: Some(7),
+            ..AlgorithmConfig::default()
+        };
+        let tuples = TupleIndex::from(testing::generate_synthetic_problem(60, 5, 4, 6, 1));
+
+        let run = || {
+            let mut population = create_first_population(&config, &tuples);
+            for individual in &mut population {
+                individual.adaptation = calculate_fitness_cached(&config, individual, &tuples);
+            }
+            datatypes::sort_by_adaptation(&mut population);
+
+            let positions: Vec<usize> = (0..population.len()).collect();
+            for generation in 0..5 {
+                population = evolve_shard(
+                    &config,
+                    &population,
+                    &population,
+                    &positions,
+                    generation,
+                    &tuples,
+                    &Locks::new(),
+                    None,
+                    None,
+                );
+                datatypes::sort_by_adaptation(&mut population);
+            }
+
+            population
+        };
+
+        let genes = |population: &Population| {
+            population
+                .iter()
+                .map(|individual| individual.period_genes().map(<[Gene]>::to_vec).collect())
+                .collect::<Vec<Vec<Vec<Gene>>>>()
+        };
+
+        // a single thread splits the work into other tasks
+        let single_thread = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+        let (first, second) = (run(), single_thread.install(run));
+        assert_eq!(first[0].adaptation, second[0].adaptation);
+        assert_eq!(genes(&first), genes(&second));
+    }
+
+    #[test]
+    fn test_selection_trace_records_every_crossover() {
+        let config = AlgorithmConfig {
+            population_size: 10,
+            number_of_periods: 2,
+            generation_gap: 0.5,
+            ..AlgorithmConfig::default()
+        };
+        let (parent, _) = opposite_parents();
+        let tuples: TupleIndex = (1..=20)
+            .map(|id| tuple(id, &id.to_string(), &id.to_string(), &id.to_string()))
+            .collect();
+        let population: Population = (1..=10)
+            .map(|i| Individual {
+                adaptation: -i as Adaptation,
+                ..parent.clone()
+            })
+            .collect();
+        let positions: Vec<usize> = (0..10).collect();
+        let trace = SelectionTrace::default();
+
+        for generation in 0..3 {
+            evolve_shard(
+                &config,
+                &population,
+                &population,
+                &positions,
+                generation,
+                &tuples,
+                &Locks::new(),
+                Some(&trace),
+                None,
+            );
+        }
+
+        let records = trace.records();
+        assert_eq!(records.len(), 3 * offspring_count(&config));
+        for record in &records {
+            assert!(record.first < 10 && record.second < 10);
+            assert_ne!(record.first, record.second);
+        }
+        assert_eq!(records.last().unwrap().generation, 2);
+    }
 }
@@ -1,11 +1,97 @@
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
 /// Returns a random number generator.
 ///
-/// Uses [`rand::rngs::ThreadRng`] to get a random number generator.
-/// It refreshes entropy every 64 KiB of random data and on fork.
-pub fn get_random_generator() -> impl Rng {
-    rand::thread_rng()
-    // let seed: [u8; 32] = [42; 32];
-    // StdRng::from_seed(seed)
+/// Without a `seed` the generator is seeded from [`rand::rngs::ThreadRng`], which refreshes
+/// entropy every 64 KiB of random data and on fork.
+///
+/// With a `seed` (see [`crate::algorithm::config::AlgorithmConfig::random_seed`]) the generator is
+/// derived from the seed and `stream`, the values identifying the work it is used for (e.g. the
+/// generation and the position of the child being created). Work is spread over threads in a
+/// different order every run, so deriving the generator from the work instead of the thread keeps
+/// the run reproducible while different children still get different numbers.
+pub fn get_random_generator(seed: Option<u64>, stream: &[u64]) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(mix_seed(seed, stream)),
+        None => StdRng::seed_from_u64(rand::thread_rng().gen()),
+    }
+}
+
+/// Generators of one rayon task, created with `map_init` and used for all the work of the task
+///
+/// An unseeded run reuses a single generator for the whole task instead of seeding a new one for
+/// every piece of work. A seeded run still derives a generator from every `stream`, see
+/// [`get_random_generator`], because rayon splits the work into tasks differently every run.
+#[derive(Debug, Default)]
+pub struct TaskGenerators {
+    seeded: Option<StdRng>,
+    unseeded: Option<StdRng>,
+}
+
+impl TaskGenerators {
+    /// Generator for the work identified by `stream`
+    pub fn get(&mut self, seed: Option<u64>, stream: &[u64]) -> &mut StdRng {
+        match seed {
+            Some(_) => self.seeded.insert(get_random_generator(seed, stream)),
+            None => self
+                .unseeded
+                .get_or_insert_with(|| get_random_generator(None, &[])),
+        }
+    }
+}
+
+/// Combine a seed with the values of a stream into a new seed, using the SplitMix64 finalizer so
+/// close inputs give unrelated seeds
+pub fn mix_seed(seed: u64, stream: &[u64]) -> u64 {
+    let split_mix = |value: u64| {
+        let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    };
+
+    stream
+        .iter()
+        .fold(split_mix(seed ^ stream.len() as u64), |mixed, value| {
+            split_mix(mixed ^ value)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded_generators_are_reproducible() {
+        let numbers = |seed, stream: &[u64]| {
+            let mut rng = get_random_generator(seed, stream);
+            (0..4).map(|_| rng.gen::<u64>()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(numbers(Some(1), &[2, 3]), numbers(Some(1), &[2, 3]));
+        assert_ne!(numbers(Some(1), &[2, 3]), numbers(Some(1), &[3, 2]));
+        assert_ne!(numbers(Some(1), &[2, 3]), numbers(Some(2), &[2, 3]));
+        assert_ne!(numbers(Some(1), &[]), numbers(Some(1), &[0]));
+        assert_ne!(numbers(None, &[2, 3]), numbers(None, &[2, 3]));
+    }
+
+    #[test]
+    fn test_task_generators_keep_the_distribution() {
+        let mut generators = TaskGenerators::default();
+
+        // seeded work gets the same numbers as its own generator
+        let seeded = generators.get(Some(4), &[1, 2]).gen::<u64>();
+        assert_eq!(seeded, get_random_generator(Some(4), &[1, 2]).gen::<u64>());
+
+        // the reused generator keeps drawing uniformly
+        let mut counts = [0usize; 10];
+        for _ in 0..10_000 {
+            for _ in 0..5 {
+                counts[generators.get(None, &[]).gen_range(0..10)] += 1;
+            }
+        }
+        for count in counts {
+            assert!((4_500..5_500).contains(&count), "{:?}", counts);
+        }
+    }
 }
@@ -0,0 +1,893 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs::File,
+    path::Path,
+    sync::Mutex,
+};
+
+use itertools::Itertools;
+use serde::Serialize;
+
+use super::{
+    calculate_fitness,
+    config::AlgorithmConfig,
+    datatypes::{
+        adaptation_to_f64, compare_adaptation, Adaptation, ConflictType, Gene, Individual,
+        RunResult, Timetable, Tuple, TupleIndex,
+    },
+    list_conflicts, soft_objective_penalties, try_move,
+};
+
+/// Single gene move to a different period and the change of fitness it causes
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct NeighborMove {
+    pub gene: Gene,
+    /// [`crate::algorithm::datatypes::Chromosome::id`] of the period the gene is moved from
+    pub from_period: i32,
+    /// [`crate::algorithm::datatypes::Chromosome::id`] of the period the gene is moved to
+    pub to_period: i32,
+    /// Fitness after the move minus the fitness before it, positive values are improvements
+    pub delta: Adaptation,
+}
+
+/// Evaluate every single gene move of the individual
+///
+/// Describes the fitness landscape around a solution: the number of moves with a positive
+/// `delta` tells how many improving moves are left. Produces `genes * (periods - 1)` moves.
+pub fn analyze_neighborhood(
+    config: &AlgorithmConfig,
+    individual: &Individual,
+    tuples: &TupleIndex,
+) -> Vec<NeighborMove> {
+    let fitness = calculate_fitness(config, individual, tuples);
+
+    let mut moves = Vec::new();
+
+    for source in &individual.chromosomes {
+        for gene in &source.genes {
+            for (target_index, target) in individual.chromosomes.iter().enumerate() {
+                if target.id == source.id {
+                    continue;
+                }
+
+                let neighbor = try_move(individual, *gene, target_index);
+                moves.push(NeighborMove {
+                    gene: *gene,
+                    from_period: source.id,
+                    to_period: target.id,
+                    delta: calculate_fitness(config, &neighbor, tuples) - fitness,
+                });
+            }
+        }
+    }
+
+    moves
+}
+
+/// Write the moves found by [`analyze_neighborhood`] into a CSV file
+pub fn write_neighborhood_csv(
+    path: impl AsRef<Path>,
+    moves: &[NeighborMove],
+) -> Result<(), csv::Error> {
+    let mut writer = csv::Writer::from_path(path)?;
+
+    for neighbor_move in moves {
+        writer.serialize(neighbor_move)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Parents chosen by one [`crate::algorithm::rand_parents`] call
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub struct SelectionRecord {
+    pub generation: usize,
+    /// Rank of the first parent in the population sorted from the fittest individual
+    pub first: usize,
+    /// Rank of the second parent
+    pub second: usize,
+}
+
+/// Sink collecting every selection decision, shared by the threads creating children
+///
+/// Together with the sorted population of every generation, the ranks are enough to rebuild
+/// which individuals were mated, even when the rest of the randomness can't be reproduced.
+#[derive(Debug, Default)]
+pub struct SelectionTrace {
+    records: Mutex<Vec<SelectionRecord>>,
+}
+
+impl SelectionTrace {
+    pub fn record(&self, record: SelectionRecord) {
+        self.records.lock().unwrap().push(record);
+    }
+
+    /// Recorded decisions ordered by generation, within a generation in the order of recording
+    pub fn records(&self) -> Vec<SelectionRecord> {
+        let mut records = self.records.lock().unwrap().clone();
+        records.sort_by_key(|record| record.generation);
+        records
+    }
+
+    /// Write the recorded decisions into a CSV file
+    pub fn write_csv(&self, path: impl AsRef<Path>) -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_path(path)?;
+
+        for record in self.records() {
+            writer.serialize(record)?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Resource whose use is summarized by a [`UtilizationRecord`]
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UtilizationScope {
+    Period,
+    Room,
+}
+
+/// How much a period or a room is used in a timetable
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct UtilizationRecord {
+    pub scope: UtilizationScope,
+    /// Period number counted from 1, or the room name
+    pub name: String,
+    /// Number of classes held in the period or the room
+    pub classes: usize,
+    /// Number of periods the room is used in, empty for periods
+    pub occupied_periods: Option<usize>,
+}
+
+/// Count the classes of every period and the classes and occupied periods of every room
+///
+/// Periods come first in their order, followed by the rooms sorted by name.
+pub fn utilization(individual: &impl Timetable, tuples: &[Tuple]) -> Vec<UtilizationRecord> {
+    let rooms: HashMap<i32, &str> = tuples.iter().map(|t| (t.id, t.room.as_str())).collect();
+
+    let mut records = Vec::new();
+    let mut room_periods: BTreeMap<&str, (usize, BTreeSet<usize>)> = BTreeMap::new();
+
+    for (period_index, genes) in individual.period_genes().enumerate() {
+        records.push(UtilizationRecord {
+            scope: UtilizationScope::Period,
+            name: (period_index + 1).to_string(),
+            classes: genes.len(),
+            occupied_periods: None,
+        });
+
+        for gene in genes {
+            let (classes, periods) = room_periods.entry(rooms[gene]).or_default();
+            *classes += 1;
+            periods.insert(period_index);
+        }
+    }
+
+    records.extend(
+        room_periods
+            .into_iter()
+            .map(|(room, (classes, periods))| UtilizationRecord {
+                scope: UtilizationScope::Room,
+                name: room.to_string(),
+                classes,
+                occupied_periods: Some(periods.len()),
+            }),
+    );
+
+    records
+}
+
+/// Write the utilization of the periods and rooms into a CSV file
+pub fn write_utilization_csv(
+    path: impl AsRef<Path>,
+    records: &[UtilizationRecord],
+) -> Result<(), csv::Error> {
+    let mut writer = csv::Writer::from_path(path)?;
+
+    for record in records {
+        writer.serialize(record)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Describe the hard conflicts left in the individual in a human readable form
+///
+/// Conflicts are grouped by the period and the double-booked resource (teacher, room or student
+/// group), so the
+/// output reads like "Period 3: teacher Smith is double-booked". Every group lists the tuples
+/// involved. Returns an empty string when there are no hard conflicts.
+pub fn explain_conflicts(individual: &Individual, tuples: &TupleIndex) -> String {
+    let mut groups: BTreeMap<(i32, String), (BTreeSet<ConflictType>, BTreeSet<i32>)> =
+        BTreeMap::new();
+
+    for conflict in list_conflicts(individual, tuples) {
+        if !conflict.conflict_type.is_hard() {
+            continue;
+        }
+
+        let (first, second) = conflict.tuples;
+        let tuple = &tuples[&first];
+        let resource = match conflict.conflict_type {
+            ConflictType::RoomClash => format!("room {}", tuple.room),
+            ConflictType::TeacherMultiClass
+            | ConflictType::SameTeacherSameSubject
+            | ConflictType::SameTeacherDifferentSubject => format!("teacher {}", tuple.teacher),
+            ConflictType::GroupClash => format!("group {}", tuple.group),
+        };
+
+        let (conflict_types, involved) = groups.entry((conflict.period, resource)).or_default();
+        conflict_types.insert(conflict.conflict_type);
+        involved.insert(first);
+        involved.insert(second);
+    }
+
+    groups
+        .into_iter()
+        .map(|((period, resource), (conflict_types, involved))| {
+            format!(
+                "Period {}: {} is double-booked ({})\n - {}",
+                period + 1,
+                resource,
+                conflict_types.iter().join(", "),
+                involved.iter().map(|id| &tuples[id]).join("\n - ")
+            )
+        })
+        .join("\n")
+}
+
+/// Summary of the adaptations of one generation, for following how the run converges
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub struct GenerationStats {
+    /// Generation number counted from 1
+    pub generation: usize,
+    pub min: Adaptation,
+    pub max: Adaptation,
+    pub mean: f64,
+    pub median: f64,
+    pub stddev: f64,
+    /// Variance of the number of genes in the same period across the individuals, averaged over
+    /// the periods. A crude measure of diversity, `0` when all individuals spread their genes the
+    /// same way
+    pub gene_count_variance: f64,
+}
+
+impl GenerationStats {
+    /// Compute the statistics of a population in any order
+    ///
+    /// A population ranked by score need not have its adaptations sorted, the fittest individual
+    /// may have fewer hard penalties but a lower adaptation. Returns `None` for an empty population.
+    pub fn new(generation: usize, population: &[Individual]) -> Option<GenerationStats> {
+        let mut adaptations: Vec<Adaptation> = population
+            .iter()
+            .map(|individual| individual.adaptation)
+            .collect();
+        adaptations.sort_by(compare_adaptation);
+        let (min, max) = (*adaptations.first()?, *adaptations.last()?);
+
+        let mut sum = 0.0;
+        let mut sum_of_squares = 0.0;
+        let mut gene_counts: Vec<(f64, f64)> = Vec::new();
+
+        for individual in population {
+            let adaptation = adaptation_to_f64(individual.adaptation);
+            sum += adaptation;
+            sum_of_squares += adaptation * adaptation;
+
+            if gene_counts.len() < individual.chromosomes.len() {
+                gene_counts.resize(individual.chromosomes.len(), (0.0, 0.0));
+            }
+            for (period, (count_sum, count_squares)) in
+                individual.chromosomes.iter().zip(&mut gene_counts)
+            {
+                let count = period.genes.len() as f64;
+                *count_sum += count;
+                *count_squares += count * count;
+            }
+        }
+
+        let len = population.len() as f64;
+        let variance = |sum: f64, sum_of_squares: f64| {
+            let mean = sum / len;
+            (sum_of_squares / len - mean * mean).max(0.0)
+        };
+        let middle = adaptations.len() / 2;
+        let median = if adaptations.len().is_multiple_of(2) {
+            (adaptation_to_f64(adaptations[middle - 1]) + adaptation_to_f64(adaptations[middle]))
+                / 2.0
+        } else {
+            adaptation_to_f64(adaptations[middle])
+        };
+
+        Some(GenerationStats {
+            generation,
+            min,
+            max,
+            mean: sum / len,
+            median,
+            stddev: variance(sum, sum_of_squares).sqrt(),
+            gene_count_variance: if gene_counts.is_empty() {
+                0.0
+            } else {
+                gene_counts
+                    .iter()
+                    .map(|(count_sum, count_squares)| variance(*count_sum, *count_squares))
+                    .sum::<f64>()
+                    / gene_counts.len() as f64
+            },
+        })
+    }
+}
+
+impl std::fmt::Display for GenerationStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "min {}, max {}, mean {:.2}, median {:.1}, stddev {:.2}, gene count variance {:.3}",
+            self.min, self.max, self.mean, self.median, self.stddev, self.gene_count_variance
+        )
+    }
+}
+
+/// Row of the fitness history, see [`HistoryWriter`]
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+struct HistoryRow {
+    generation: usize,
+    best: Adaptation,
+    mean: f64,
+    worst: Adaptation,
+}
+
+/// CSV log of the `generation,best,mean,worst` adaptations of every generation, for plotting
+/// how the run converges
+///
+/// Every row is flushed as soon as it is written, so a run that crashes still leaves the
+/// generations before the crash. Runs whose nodes only gather their best individuals write the
+/// mean and worst of all individuals with [`HistoryWriter::write_with_spread`], so the columns
+/// mean the same as in a run gathering everything.
+#[derive(Debug)]
+pub struct HistoryWriter {
+    writer: csv::Writer<File>,
+}
+
+impl HistoryWriter {
+    /// Create the file and write the header
+    pub fn create(path: impl AsRef<Path>) -> Result<HistoryWriter, csv::Error> {
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_path(path)?;
+        writer.write_record(["generation", "best", "mean", "worst"])?;
+        writer.flush()?;
+
+        Ok(HistoryWriter { writer })
+    }
+
+    pub fn write(&mut self, stats: &GenerationStats) -> Result<(), csv::Error> {
+        self.write_with_spread(stats, stats.mean, stats.min)
+    }
+
+    /// [`HistoryWriter::write`] with the mean and worst adaptation of more individuals than
+    /// `stats` was computed from, e.g. of the islands of all nodes when only their best
+    /// individuals are gathered
+    pub fn write_with_spread(
+        &mut self,
+        stats: &GenerationStats,
+        mean: f64,
+        worst: Adaptation,
+    ) -> Result<(), csv::Error> {
+        self.writer.serialize(HistoryRow {
+            generation: stats.generation,
+            best: stats.max,
+            mean,
+            worst,
+        })?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Marker between the classes sharing a room in a period, see [`render_grid`]
+pub const GRID_COLLISION_MARKER: &str = " !! ";
+
+/// Draw the timetable as a text grid with a row for every period and a column for every room
+///
+/// Cells read `teacher/label`. Classes held in the same room in the same period share the cell,
+/// separated by [`GRID_COLLISION_MARKER`], so room clashes stand out. Columns are as wide as
+/// their longest cell and the rooms are sorted by name.
+pub fn render_grid(individual: &Individual, tuples: &TupleIndex) -> String {
+    let rooms: Vec<&str> = tuples
+        .iter()
+        .map(|tuple| tuple.room.as_str())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    let header = std::iter::once("Period".to_string())
+        .chain(rooms.iter().map(|room| room.to_string()))
+        .collect::<Vec<_>>();
+    let rows: Vec<Vec<String>> = individual
+        .period_genes()
+        .enumerate()
+        .map(|(period_index, genes)| {
+            let classes = genes
+                .iter()
+                .map(|gene| &tuples[gene])
+                .into_group_map_by(|tuple| tuple.room.as_str());
+
+            std::iter::once((period_index + 1).to_string())
+                .chain(rooms.iter().map(|room| {
+                    classes
+                        .get(room)
+                        .into_iter()
+                        .flatten()
+                        .map(|tuple| format!("{}/{}", tuple.teacher, tuple.label))
+                        .join(GRID_COLLISION_MARKER)
+                }))
+                .collect()
+        })
+        .collect();
+
+    let widths: Vec<usize> = (0..header.len())
+        .map(|column| {
+            std::iter::once(&header)
+                .chain(&rows)
+                .map(|row| row[column].chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    let line = |row: &[String]| {
+        row.iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .join(" | ")
+    };
+
+    std::iter::once(line(&header))
+        .chain(std::iter::once(
+            widths.iter().map(|width| "-".repeat(*width)).join("-+-"),
+        ))
+        .chain(rows.iter().map(|row| line(row)))
+        .join("\n")
+}
+
+/// Part of the score coming from one kind of conflict or soft objective
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ScoreCategory {
+    pub name: String,
+    pub occurrences: usize,
+    /// Contribution to the total score, `0` or negative
+    pub score: Adaptation,
+}
+
+/// Conflict left in the final timetable
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ResidualConflict {
+    /// Period number counted from 1
+    pub period: i32,
+    pub conflict_type: ConflictType,
+    pub description: String,
+}
+
+/// Explanation of the final timetable for people who don't read the code
+///
+/// Written by `--report` as JSON. The categories add up to `total_score`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SolutionReport {
+    pub total_score: Adaptation,
+    pub baseline_score: Adaptation,
+    /// See [`RunResult::improvement_over_baseline`]
+    pub improvement_over_baseline: f64,
+    pub generations: usize,
+    pub categories: Vec<ScoreCategory>,
+    pub conflicts: Vec<ResidualConflict>,
+    pub utilization: Vec<UtilizationRecord>,
+}
+
+impl SolutionReport {
+    pub fn new(
+        config: &AlgorithmConfig,
+        result: &RunResult,
+        tuples: &TupleIndex,
+    ) -> SolutionReport {
+        let conflicts = list_conflicts(&result.best, tuples);
+
+        // the fitness counts a conflict once from each of its tuples
+        let conflict_categories = conflicts
+            .iter()
+            .counts_by(|conflict| conflict.conflict_type)
+            .into_iter()
+            .sorted()
+            .map(|(conflict_type, occurrences)| ScoreCategory {
+                name: conflict_type.to_string(),
+                occurrences,
+                score: -((2 * occurrences) as Adaptation)
+                    * config.fitness_weights.penalty(conflict_type),
+            });
+        let soft_categories = soft_objective_penalties(config, &result.best, tuples)
+            .into_iter()
+            .map(|penalty| ScoreCategory {
+                name: penalty.name.to_string(),
+                occurrences: penalty.occurrences,
+                score: -(penalty.occurrences as Adaptation) * penalty.weight,
+            });
+
+        SolutionReport {
+            total_score: result.best.adaptation,
+            baseline_score: result.baseline_adaptation,
+            improvement_over_baseline: result.improvement_over_baseline(),
+            generations: result.generations,
+            categories: conflict_categories.chain(soft_categories).collect(),
+            conflicts: conflicts
+                .iter()
+                .map(|conflict| {
+                    let (first, second) = conflict.tuples;
+                    ResidualConflict {
+                        period: conflict.period + 1,
+                        conflict_type: conflict.conflict_type,
+                        description: format!(
+                            "{}: {} and {}",
+                            conflict.conflict_type, &tuples[&first], &tuples[&second]
+                        ),
+                    }
+                })
+                .collect(),
+            utilization: utilization(&result.best, tuples),
+        }
+    }
+
+    /// Write the report as pretty printed JSON
+    pub fn write_json(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{
+        datatypes::Chromosome,
+        testing::{tuple, TempFile},
+    };
+
+    #[test]
+    fn test_solution_report() {
+        let config = AlgorithmConfig {
+            compaction_weight: 1 as Adaptation,
+            ..AlgorithmConfig::default()
+        };
+        let tuples = TupleIndex::from(vec![
+            tuple(1, "Math", "A", "Smith"),
+            tuple(2, "Art", "B", "Smith"),
+            tuple(3, "Physics", "B", "Jones"),
+            tuple(4, "Biology", "C", "Brown"),
+        ]);
+        let mut best = Individual::with_chromosomes(vec![
+            Chromosome {
+                id: 0,
+                genes: vec![4],
+            },
+            Chromosome {
+                id: 1,
+                genes: vec![1, 2, 3],
+            },
+        ]);
+        best.adaptation = calculate_fitness(&config, &best, &tuples);
+        let result = RunResult {
+            best,
+            generations: 3,
+            baseline_adaptation: -200 as Adaptation,
+            evaluations: 0,
+            elapsed: std::time::Duration::from_secs(1),
+        };
+
+        let report = SolutionReport::new(&config, &result, &tuples);
+
+        assert_eq!(report.total_score, result.best.adaptation);
+        assert_eq!(
+            report
+                .categories
+                .iter()
+                .map(|category| category.score)
+                .sum::<Adaptation>(),
+            report.total_score
+        );
+        assert_eq!(
+            report
+                .categories
+                .iter()
+                .map(|category| (category.name.as_str(), category.occurrences))
+                .collect::<Vec<_>>(),
+            vec![
+                ("room is occupied by different teachers", 1),
+                ("teacher teaches different subjects", 1),
+                ("compaction", 2),
+            ]
+        );
+        assert_eq!(
+            report
+                .conflicts
+                .iter()
+                .map(|conflict| (conflict.period, conflict.conflict_type))
+                .collect::<Vec<_>>(),
+            vec![
+                (2, ConflictType::SameTeacherDifferentSubject),
+                (2, ConflictType::RoomClash),
+            ]
+        );
+        assert_eq!(
+            report.conflicts[1].description,
+            "room is occupied by different teachers: Art, B, Smith and Physics, B, Jones"
+        );
+    }
+
+    #[test]
+    fn test_utilization() {
+        let tuples = vec![
+            tuple(1, "Math", "A", "Smith"),
+            tuple(2, "Art", "B", "Smith"),
+            tuple(3, "Physics", "B", "Jones"),
+            tuple(4, "Biology", "A", "Brown"),
+        ];
+        let individual = Individual::with_chromosomes(vec![
+            Chromosome {
+                id: 0,
+                genes: vec![1, 3],
+            },
+            Chromosome::new(1),
+            Chromosome {
+                id: 2,
+                genes: vec![2, 4],
+            },
+        ]);
+
+        let record = |scope, name: &str, classes, occupied_periods| UtilizationRecord {
+            scope,
+            name: name.to_string(),
+            classes,
+            occupied_periods,
+        };
+        assert_eq!(
+            utilization(&individual, &tuples),
+            vec![
+                record(UtilizationScope::Period, "1", 2, None),
+                record(UtilizationScope::Period, "2", 0, None),
+                record(UtilizationScope::Period, "3", 2, None),
+                record(UtilizationScope::Room, "A", 2, Some(2)),
+                record(UtilizationScope::Room, "B", 2, Some(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_explain_conflicts() {
+        let tuples = TupleIndex::from(vec![
+            tuple(1, "Math", "A", "Smith"),
+            tuple(2, "Art", "B", "Smith"),
+            tuple(3, "Physics", "B", "Jones"),
+            tuple(4, "Biology", "C", "Brown"),
+        ]);
+        let individual = Individual::with_chromosomes(vec![
+            Chromosome {
+                id: 0,
+                genes: vec![4],
+            },
+            Chromosome {
+                id: 1,
+                genes: vec![1, 2, 3],
+            },
+        ]);
+
+        assert_eq!(
+            explain_conflicts(&individual, &tuples),
+            "Period 2: room B is double-booked (room is occupied by different teachers)\n \
+             - Art, B, Smith\n \
+             - Physics, B, Jones\n\
+             Period 2: teacher Smith is double-booked (teacher teaches different subjects)\n \
+             - Math, A, Smith\n \
+             - Art, B, Smith"
+        );
+    }
+
+    #[test]
+    fn test_analyze_neighborhood() {
+        let config = AlgorithmConfig {
+            number_of_periods: 3,
+            ..AlgorithmConfig::default()
+        };
+        let tuples = TupleIndex::from(vec![
+            tuple(1, "Math", "A", "Smith"),
+            tuple(2, "Art", "A", "Jones"),
+            tuple(3, "Physics", "B", "Brown"),
+        ]);
+        let individual = Individual::with_chromosomes(vec![
+            Chromosome {
+                id: 0,
+                genes: vec![1, 2],
+            },
+            Chromosome {
+                id: 1,
+                genes: vec![3],
+            },
+            Chromosome::new(2),
+        ]);
+        let fitness = calculate_fitness(&config, &individual, &tuples);
+
+        let moves = analyze_neighborhood(&config, &individual, &tuples);
+
+        assert_eq!(moves.len(), 3 * (3 - 1));
+        for neighbor_move in &moves {
+            let mut neighbor = individual.clone();
+            neighbor.chromosomes[neighbor_move.from_period as usize]
+                .genes
+                .retain(|g| *g != neighbor_move.gene);
+            neighbor.chromosomes[neighbor_move.to_period as usize]
+                .genes
+                .push(neighbor_move.gene);
+
+            assert_eq!(
+                neighbor_move.delta,
+                calculate_fitness(&config, &neighbor, &tuples) - fitness
+            );
+        }
+        // moving either of the clashing classes away removes the room clash
+        assert_eq!(
+            moves
+                .iter()
+                .filter(|m| m.delta > Adaptation::default())
+                .count(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_explain_without_conflicts_is_empty() {
+        let tuples = TupleIndex::from(vec![tuple(1, "Math", "A", "Smith")]);
+        let individual = Individual::with_chromosomes(vec![Chromosome {
+            id: 0,
+            genes: vec![1],
+        }]);
+
+        assert_eq!(explain_conflicts(&individual, &tuples), "");
+    }
+
+    #[test]
+    fn test_render_grid() {
+        let tuples = TupleIndex::from(vec![
+            tuple(1, "Math", "A", "Smith"),
+            tuple(2, "Art", "B", "Smith"),
+            tuple(3, "Physics", "B", "Jones"),
+            tuple(4, "Biology", "C", "Brown"),
+        ]);
+        let individual = Individual::with_chromosomes(vec![
+            Chromosome {
+                id: 0,
+                genes: vec![4, 1],
+            },
+            Chromosome {
+                id: 1,
+                genes: vec![2, 3],
+            },
+            Chromosome::new(2),
+        ]);
+
+        assert_eq!(
+            render_grid(&individual, &tuples),
+            [
+                "Period | A          | B                          | C            ",
+                "-------+------------+----------------------------+--------------",
+                "1      | Smith/Math |                            | Brown/Biology",
+                "2      |            | Smith/Art !! Jones/Physics |              ",
+                "3      |            |                            |              ",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_generation_stats() {
+        let individual = |adaptation: i32, genes: [Vec<Gene>; 2]| Individual {
+            adaptation: adaptation as Adaptation,
+            ..Individual::with_chromosomes(
+                genes
+                    .into_iter()
+                    .enumerate()
+                    .map(|(id, genes)| Chromosome {
+                        id: id as i32,
+                        genes,
+                    })
+                    .collect(),
+            )
+        };
+        let population = vec![
+            individual(0, [vec![1, 2], vec![3]]),
+            individual(-20, [vec![1], vec![2, 3]]),
+            individual(-40, [vec![1, 2], vec![3]]),
+            individual(-60, [vec![1], vec![2, 3]]),
+        ];
+
+        let stats = GenerationStats::new(3, &population).unwrap();
+        assert_eq!(stats.generation, 3);
+        assert_eq!(stats.max, 0 as Adaptation);
+        assert_eq!(stats.min, -60 as Adaptation);
+        assert_eq!(stats.mean, -30.0);
+        assert_eq!(stats.median, -30.0);
+        assert!((stats.stddev - 500f64.sqrt()).abs() < 1e-9);
+        // every period holds 1 or 2 genes, each half of the time
+        assert_eq!(stats.gene_count_variance, 0.25);
+
+        assert_eq!(
+            GenerationStats::new(1, &population[..3]).unwrap().median,
+            -20.0
+        );
+        assert_eq!(GenerationStats::new(1, &[]), None);
+
+        // ranked by score, the fittest individual does not have the highest adaptation
+        let ranked = [
+            &population[1],
+            &population[0],
+            &population[3],
+            &population[2],
+        ]
+        .map(Clone::clone);
+        let stats = GenerationStats::new(3, &ranked).unwrap();
+        assert_eq!(stats.max, 0 as Adaptation);
+        assert_eq!(stats.min, -60 as Adaptation);
+        assert_eq!(stats.median, -30.0);
+    }
+
+    #[test]
+    fn test_history_has_a_row_per_generation() {
+        let config = AlgorithmConfig {
+            population_size: 20,
+            number_of_periods: 4,
+            max_generations: 5,
+            ..AlgorithmConfig::default()
+        };
+        let mut algorithm = crate::algorithm::engine::GeneticAlgorithm::new(
+            config,
+            crate::algorithm::testing::generate_synthetic_problem(30, 5, 4, 5, 2),
+        );
+        algorithm.run();
+
+        let path = TempFile::new("history", "csv");
+        let mut history = HistoryWriter::create(&path).unwrap();
+        for stats in algorithm.history() {
+            history.write(stats).unwrap();
+        }
+
+        let mut reader = csv::Reader::from_path(&path).unwrap();
+        assert_eq!(
+            reader.headers().unwrap(),
+            vec!["generation", "best", "mean", "worst"]
+        );
+        let rows = reader.records().collect::<Result<Vec<_>, _>>().unwrap();
+
+        assert_eq!(rows.len(), algorithm.generation());
+        assert_eq!(&rows[0][0], "1");
+
+        let path = TempFile::new("spread", "csv");
+        let stats = &algorithm.history()[0];
+        let mut history = HistoryWriter::create(&path).unwrap();
+        history
+            .write_with_spread(stats, -40.5, -90 as Adaptation)
+            .unwrap();
+        let rows = csv::Reader::from_path(&path)
+            .unwrap()
+            .records()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let row: Vec<f64> = rows[0].iter().map(|field| field.parse().unwrap()).collect();
+        assert_eq!(row[1], adaptation_to_f64(stats.max));
+        assert_eq!((row[2], row[3]), (-40.5, -90.0));
+
+        assert!(HistoryWriter::create(std::env::temp_dir().join("missing/history.csv")).is_err());
+    }
+}
@@ -0,0 +1,295 @@
+//! Deciding when the run ends before `max_generations`
+
+use std::time::{Duration, Instant};
+
+use super::{
+    config::{AlgorithmConfig, HardConstraints, RequiredSolutions},
+    datatypes::{compare_score, Adaptation, Individual, Score, TupleIndex},
+    genome_distance, is_hard_feasible,
+};
+
+/// State of the early stop conditions, checked with the population of every generation
+#[derive(Debug, Clone)]
+pub struct StopCondition {
+    hard_constraints: HardConstraints,
+    polish_generations: Option<usize>,
+    /// Generation in which the best individual first had no hard conflicts
+    first_feasible_generation: Option<usize>,
+    required_solutions: Option<RequiredSolutions>,
+    /// Distinct hard feasible individuals found so far
+    archive: Vec<Individual>,
+    stagnation_limit: usize,
+    /// Best score seen so far
+    best: Option<Score>,
+    /// Generations in a row without a strictly better best score
+    stagnant_generations: usize,
+    /// End of [`AlgorithmConfig::time_budget_secs`], counted from the creation of the condition
+    deadline: Option<Instant>,
+}
+
+impl StopCondition {
+    pub fn new(config: &AlgorithmConfig) -> Self {
+        StopCondition {
+            hard_constraints: config.hard_constraints,
+            polish_generations: config.polish_generations,
+            first_feasible_generation: None,
+            required_solutions: config.required_solutions,
+            archive: Vec::new(),
+            stagnation_limit: config.stagnation_limit,
+            best: None,
+            stagnant_generations: 0,
+            deadline: config
+                .time_budget_secs
+                .map(|secs| Instant::now() + Duration::from_secs(secs)),
+        }
+    }
+
+    /// Whether the time budget of the run is used up
+    pub fn out_of_time(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Whether [`StopCondition::should_stop`] looks at more than the best adaptation, so the
+    /// best individuals have to be gathered every generation
+    pub fn needs_population(&self) -> bool {
+        self.polish_generations.is_some() || self.required_solutions.is_some()
+    }
+
+    /// Distinct hard feasible individuals found while looking for
+    /// [`AlgorithmConfig::required_solutions`]
+    pub fn archive(&self) -> &[Individual] {
+        &self.archive
+    }
+
+    /// Record the best score of a generation, returns whether the run has stagnated for
+    /// [`AlgorithmConfig::stagnation_limit`] generations
+    ///
+    /// Only strictly better scores reset the count, see [`compare_score`], so lowering the hard
+    /// penalties is progress even when the total drops. Needs nothing but the best score, so
+    /// nodes that only know it can decide the same way as the root.
+    pub fn record_best(&mut self, score: Score) -> bool {
+        match self.best {
+            Some(best) if compare_score(&score, &best).is_le() => {
+                self.stagnant_generations += 1;
+            }
+            _ => {
+                self.best = Some(score);
+                self.stagnant_generations = 0;
+            }
+        }
+
+        self.stagnation_limit != 0 && self.stagnant_generations >= self.stagnation_limit
+    }
+
+    /// Whether the run should stop after the given generation, `population` is sorted from the
+    /// fittest individual
+    ///
+    /// The run stops once it stagnates, see [`StopCondition::record_best`], or is
+    /// [`StopCondition::out_of_time`]. A perfect timetable always stops the run. With
+    /// [`AlgorithmConfig::polish_generations`] the run also stops that many generations after the
+    /// first hard feasible best individual.
+    ///
+    /// [`AlgorithmConfig::required_solutions`] replaces both: the run goes on until enough
+    /// distinct hard feasible individuals are archived, even past a perfect one. Every individual
+    /// of the population is checked until then, so it is slower.
+    pub fn should_stop(
+        &mut self,
+        generation: usize,
+        population: &[Individual],
+        tuples: &TupleIndex,
+    ) -> bool {
+        if self.record_best(population[0].score()) || self.out_of_time() {
+            return true;
+        }
+
+        if let Some(required) = self.required_solutions {
+            self.archive_feasible(population, tuples, required.min_distance);
+            return self.archive.len() >= required.count;
+        }
+
+        let best = &population[0];
+
+        if best.adaptation == Adaptation::default() {
+            return true;
+        }
+
+        let Some(polish_generations) = self.polish_generations else {
+            return false;
+        };
+
+        if self.first_feasible_generation.is_none()
+            && is_hard_feasible(&self.hard_constraints, best, tuples)
+        {
+            self.first_feasible_generation = Some(generation);
+        }
+
+        self.first_feasible_generation
+            .is_some_and(|feasible| generation >= feasible + polish_generations)
+    }
+
+    /// Archive hard feasible individuals at least `min_distance` away from every archived one
+    fn archive_feasible(
+        &mut self,
+        population: &[Individual],
+        tuples: &TupleIndex,
+        min_distance: usize,
+    ) {
+        for individual in population {
+            let distinct = self
+                .archive
+                .iter()
+                .all(|archived| genome_distance(archived, individual) >= min_distance.max(1));
+
+            if distinct && is_hard_feasible(&self.hard_constraints, individual, tuples) {
+                self.archive.push(individual.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{datatypes::Chromosome, testing::tuple};
+
+    fn individual(genes: Vec<i32>, adaptation: i32) -> Individual {
+        Individual {
+            adaptation: adaptation as Adaptation,
+            ..Individual::with_chromosomes(vec![Chromosome { id: 0, genes }])
+        }
+    }
+
+    #[test]
+    fn test_stops_polish_generations_after_first_feasible() {
+        let config = AlgorithmConfig {
+            polish_generations: Some(3),
+            ..AlgorithmConfig::default()
+        };
+        let tuples = TupleIndex::from(vec![
+            tuple(1, "1", "A", "Smith"),
+            tuple(2, "2", "A", "Jones"),
+        ]);
+        // both tuples in the same room, only soft penalties left in the feasible one
+        let infeasible = individual(vec![1, 2], -40);
+        let feasible = individual(vec![1], -5);
+
+        let mut stop = StopCondition::new(&config);
+        let stopped_at = (0..100)
+            .find(|generation| {
+                let best = if *generation < 4 {
+                    &infeasible
+                } else {
+                    &feasible
+                };
+                stop.should_stop(*generation, std::slice::from_ref(best), &tuples)
+            })
+            .unwrap();
+
+        assert_eq!(stopped_at, 4 + 3);
+    }
+
+    #[test]
+    fn test_without_polish_only_perfect_stops() {
+        let tuples = TupleIndex::from(vec![tuple(1, "1", "A", "Smith")]);
+        let mut stop = StopCondition::new(&AlgorithmConfig::default());
+
+        assert!(!stop.should_stop(0, &[individual(vec![1], -5)], &tuples));
+        assert!(stop.should_stop(1, &[individual(vec![1], 0)], &tuples));
+    }
+
+    #[test]
+    fn test_runs_until_enough_distinct_feasible_solutions() {
+        let config = AlgorithmConfig {
+            required_solutions: Some(RequiredSolutions {
+                count: 3,
+                min_distance: 1,
+            }),
+            ..AlgorithmConfig::default()
+        };
+        let tuples = TupleIndex::from(vec![
+            tuple(1, "1", "A", "Smith"),
+            tuple(2, "2", "B", "Jones"),
+        ]);
+        let placed = |first: Vec<i32>, second: Vec<i32>| Individual {
+            adaptation: 0 as Adaptation,
+            ..Individual::with_chromosomes(vec![
+                Chromosome {
+                    id: 0,
+                    genes: first,
+                },
+                Chromosome {
+                    id: 1,
+                    genes: second,
+                },
+            ])
+        };
+        let together = placed(vec![1, 2], vec![]);
+        let apart = placed(vec![1], vec![2]);
+        let swapped = placed(vec![2], vec![1]);
+
+        let mut stop = StopCondition::new(&config);
+
+        // a perfect solution alone doesn't stop the run
+        assert!(!stop.should_stop(0, &[together.clone(), together.clone()], &tuples));
+        assert_eq!(stop.archive().len(), 1);
+        assert!(!stop.should_stop(1, &[together.clone(), apart.clone()], &tuples));
+        assert!(stop.should_stop(2, &[swapped, apart, together], &tuples));
+        assert_eq!(stop.archive().len(), 3);
+    }
+
+    #[test]
+    fn test_stops_after_stagnant_generations() {
+        let config = AlgorithmConfig {
+            stagnation_limit: 3,
+            ..AlgorithmConfig::default()
+        };
+        let tuples = TupleIndex::from(vec![tuple(1, "1", "A", "Smith")]);
+        // improves until generation 4, then plateaus with a worse individual in between
+        let best = [-50, -40, -30, -30, -20, -20, -25, -20, -20, -20];
+
+        let mut stop = StopCondition::new(&config);
+        let stopped_at = (0..best.len())
+            .find(|generation| {
+                stop.should_stop(
+                    *generation,
+                    &[individual(vec![1], best[*generation])],
+                    &tuples,
+                )
+            })
+            .unwrap();
+
+        assert_eq!(stopped_at, 4 + 3);
+
+        // without a limit the same plateau never stops the run
+        let mut stop = StopCondition::new(&AlgorithmConfig::default());
+        assert!(!(0..best.len()).any(|generation| {
+            stop.should_stop(
+                generation,
+                &[individual(vec![1], best[generation])],
+                &tuples,
+            )
+        }));
+    }
+
+    #[test]
+    fn test_fewer_hard_penalties_are_progress() {
+        let mut stop = StopCondition::new(&AlgorithmConfig {
+            stagnation_limit: 2,
+            ..AlgorithmConfig::default()
+        });
+        let score = |hard: i32, soft: i32| Score {
+            hard: hard as Adaptation,
+            soft: soft as Adaptation,
+        };
+
+        // the total drops every generation, but the hard penalties keep falling
+        assert!(!stop.record_best(score(-60, 0)));
+        assert!(!stop.record_best(score(-40, -50)));
+        assert!(!stop.record_best(score(-20, -100)));
+        assert!(!stop.record_best(score(0, -150)));
+        // a better total with more hard penalties is no progress
+        assert!(!stop.record_best(score(-10, 0)));
+        assert!(stop.record_best(score(-10, 0)));
+    }
+}
@@ -0,0 +1,213 @@
+//! Support for tests and benchmarks, enabled by the `testing` feature
+
+use std::{
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use super::datatypes::Tuple;
+
+/// Path of a file in the temporary directory, removed when dropped
+///
+/// Every path is unique, so tests running in parallel don't share files, and a failing assertion
+/// doesn't leave the file behind. The file isn't created, write it or pass the path to the code
+/// under test.
+#[derive(Debug)]
+pub struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    /// Path named after `name` with the given `extension`
+    pub fn new(name: &str, extension: &str) -> TempFile {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let path = std::env::temp_dir().join(format!(
+            "{}-{}-{}.{}",
+            name,
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed),
+            extension
+        ));
+        TempFile { path }
+    }
+
+    /// [`TempFile::new`] with `contents` written to the file
+    pub fn with_contents(name: &str, extension: &str, contents: impl AsRef<[u8]>) -> TempFile {
+        let file = TempFile::new(name, extension);
+        std::fs::write(&file, contents).unwrap();
+        file
+    }
+}
+
+impl Deref for TempFile {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TempFile {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        // the file may never have been written
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Tuple with the given fields and defaults for the rest, for hand written test problems
+pub fn tuple(id: i32, label: &str, room: &str, teacher: &str) -> Tuple {
+    Tuple {
+        id,
+        label: label.to_string(),
+        room: room.to_string(),
+        teacher: teacher.to_string(),
+        ..Tuple::default()
+    }
+}
+
+/// Generate a random problem instance
+///
+/// Tuple `id` is planned for period `id % num_periods`. The first `min(num_teachers, num_rooms)`
+/// tuples planned for a period get a different teacher and room each, so while there are at most
+/// `num_periods * min(num_teachers, num_rooms)` tuples, that placement is a timetable without
+/// conflicts. Tuples over that limit get a random teacher and room, the more of them the denser
+/// the conflicts. Every teacher teaches a single subject. The same `seed` always gives the same
+/// instance.
+pub fn generate_synthetic_problem(
+    num_tuples: usize,
+    num_teachers: usize,
+    num_rooms: usize,
+    num_periods: usize,
+    seed: u64,
+) -> Vec<Tuple> {
+    assert!(num_teachers > 0 && num_rooms > 0 && num_periods > 0);
+
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut shuffled = |count: usize| {
+        (0..num_periods)
+            .map(|_| {
+                let mut order: Vec<usize> = (0..count).collect();
+                order.shuffle(&mut rng);
+                order
+            })
+            .collect::<Vec<_>>()
+    };
+    let teachers_by_period = shuffled(num_teachers);
+    let rooms_by_period = shuffled(num_rooms);
+
+    (0..num_tuples)
+        .map(|id| {
+            let period = id % num_periods;
+            let slot = id / num_periods;
+
+            let teacher = teachers_by_period[period]
+                .get(slot)
+                .copied()
+                .filter(|_| slot < num_rooms)
+                .unwrap_or_else(|| rng.gen_range(0..num_teachers));
+            let room = rooms_by_period[period]
+                .get(slot)
+                .copied()
+                .filter(|_| slot < num_teachers)
+                .unwrap_or_else(|| rng.gen_range(0..num_rooms));
+
+            Tuple {
+                id: id.try_into().unwrap(),
+                label: format!("Subject {}", teacher),
+                room: format!("Room {}", room),
+                teacher: format!("Teacher {}", teacher),
+                ..Tuple::default()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::algorithm::{
+        calculate_fitness,
+        config::AlgorithmConfig,
+        datatypes::{Adaptation, Chromosome, Individual, TupleIndex},
+    };
+
+    #[test]
+    fn test_generator_is_deterministic() {
+        assert_eq!(
+            generate_synthetic_problem(50, 5, 4, 8, 7),
+            generate_synthetic_problem(50, 5, 4, 8, 7)
+        );
+        assert_ne!(
+            generate_synthetic_problem(50, 5, 4, 8, 7),
+            generate_synthetic_problem(50, 5, 4, 8, 8)
+        );
+    }
+
+    #[test]
+    fn test_generator_respects_counts() {
+        let tuples = generate_synthetic_problem(100, 6, 3, 8, 1);
+
+        let distinct =
+            |field: fn(&Tuple) -> &String| tuples.iter().map(field).collect::<HashSet<_>>().len();
+
+        assert_eq!(tuples.len(), 100);
+        assert_eq!(
+            tuples.iter().map(|t| t.id).collect::<Vec<_>>(),
+            (0..100).collect::<Vec<_>>()
+        );
+        assert!(distinct(|t| &t.teacher) <= 6);
+        assert!(distinct(|t| &t.room) <= 3);
+    }
+
+    #[test]
+    fn test_temp_file_is_unique_and_removed() {
+        let first = TempFile::with_contents("temp", "txt", "contents");
+        let second = TempFile::new("temp", "txt");
+        assert_ne!(&*first, &*second);
+        assert!(!second.exists());
+
+        let path = first.to_path_buf();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "contents");
+        drop(first);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_planned_placement_has_no_conflicts() {
+        let num_periods = 8;
+        let tuples = TupleIndex::from(generate_synthetic_problem(
+            num_periods * 3,
+            5,
+            3,
+            num_periods,
+            3,
+        ));
+
+        let mut chromosomes: Vec<Chromosome> =
+            (0..num_periods as i32).map(Chromosome::new).collect();
+        for tuple in &tuples {
+            chromosomes[tuple.id as usize % num_periods]
+                .genes
+                .push(tuple.id);
+        }
+        let individual = Individual::with_chromosomes(chromosomes);
+
+        assert_eq!(
+            calculate_fitness(&AlgorithmConfig::default(), &individual, &tuples),
+            Adaptation::default()
+        );
+    }
+}
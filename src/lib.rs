@@ -0,0 +1,39 @@
+//! Genetic algorithm searching for school timetables without conflicts
+//!
+//! The algorithm runs in a single process through [`algorithm`]. The `mpi` feature (enabled by
+//! default) adds [`mpi_utils`] for spreading the population over the nodes of a cluster, which is
+//! what the `planner` binary does.
+//!
+//! ```
+//! use planner::algorithm::{
+//!     config::AlgorithmConfig, datatypes::Tuple, engine::GeneticAlgorithm, validate_completeness,
+//! };
+//!
+//! let tuples: Vec<Tuple> = (0..12)
+//!     .map(|id| Tuple {
+//!         id,
+//!         label: format!("Subject {}", id % 4),
+//!         room: format!("Room {}", id % 3),
+//!         teacher: format!("Teacher {}", id % 5),
+//!         ..Tuple::default()
+//!     })
+//!     .collect();
+//! let config = AlgorithmConfig::builder()
+//!     .population_size(50)
+//!     .number_of_periods(4)
+//!     .max_generations(20)
+//!     .random_seed(1)
+//!     .build()
+//!     .unwrap();
+//!
+//! let mut algorithm = GeneticAlgorithm::new(config, tuples.clone());
+//! let best = algorithm.run();
+//!
+//! assert_eq!(validate_completeness(best, &tuples), Ok(()));
+//! println!("Best adaptation: {}", best.adaptation);
+//! ```
+
+/// For more details, see the [PDF documentation](../Dokumentacja.pdf).
+pub mod algorithm;
+#[cfg(feature = "mpi")]
+pub mod mpi_utils;
@@ -1,44 +1,392 @@
-use clap::{Arg, ArgAction, Command};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
 use itertools::Itertools;
-use mpi::{traits::*, Rank, Threading};
+use mpi::{traits::*, Threading};
 use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
 use std::fs::OpenOptions;
-use std::io::Write;
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Instant, SystemTime};
+use tracing::{error, info, info_span};
+use tracing_subscriber::EnvFilter;
 
-use self::{
-    algorithm::config::AlgorithmConfig,
-    mpi_utils::{mpi_execute_and_synchronize_at, ROOT_RANK},
+use planner::{
+    algorithm::{
+        adaptive_mutation_probability, calculate_fitness_cached,
+        checkpoint::Checkpoint,
+        config::{is_yaml, AlgorithmConfig, ConfigLoadError},
+        create_first_population_with_locks,
+        datatypes::{
+            compare_score, load_locks, sort_by_adaptation, validate_locks, Adaptation, CsvOptions,
+            Individual, IndividualImportError, Locks, Population, RoomRestrictions, RunMetadata,
+            RunResult, Score, Tuple, TupleIndex, TuplesFormat,
+        },
+        effective_parameters, evolve_one_generation, evolve_shard,
+        export::{
+            day_of, export_ics, export_timetable_csv, export_timetable_json, load_timetable,
+            parse_date,
+        },
+        first_unselectable_rank, genome_distance, integrate_migrants,
+        lint::lint_problem,
+        random::mix_seed,
+        random_immigrants, repair_period_counts,
+        report::{
+            analyze_neighborhood, explain_conflicts, render_grid, utilization,
+            write_neighborhood_csv, write_utilization_csv, GenerationStats, HistoryWriter,
+            SelectionTrace, SolutionReport,
+        },
+        stopping::StopCondition,
+        suggest_population_size, validate_completeness,
+    },
+    mpi_utils::{
+        best_k, mpi_best_score, mpi_execute_and_synchronize_at, mpi_gather_and_synchronize,
+        mpi_gather_best_k, mpi_mean_and_worst_adaptation, mpi_ring_exchange,
+        mpi_split_data_across_nodes, mpi_total, shard_offset, split_order, ROOT_RANK,
+    },
 };
 
-use crate::algorithm::{calculate_fitness, crossover, mutate};
-use crate::mpi_utils::mpi_gather_and_synchronize;
-use crate::{algorithm::datatypes::Tuple, mpi_utils::mpi_split_data_across_nodes};
+/// Arguments locating the input files, shared by all subcommands
+fn input_args() -> Vec<Arg> {
+    vec![
+        Arg::new("config")
+            .short('c')
+            .value_name("FILE")
+            .help("Sets a custom config file, JSON or YAML by its extension")
+            .action(ArgAction::Set)
+            .required(false),
+        Arg::new("tuples")
+            .short('t')
+            .value_name("FILE")
+            .help("Custom location of tuples")
+            .action(ArgAction::Set)
+            .required(false),
+        Arg::new("tuples-format")
+            .long("tuples-format")
+            .value_name("FORMAT")
+            .help("Format of the tuples file, taken from its extension by default")
+            .value_parser(["csv", "json"])
+            .action(ArgAction::Set),
+        Arg::new("delimiter")
+            .long("delimiter")
+            .value_name("CHAR")
+            .help("Column delimiter of the tuples CSV file, a single character or `tab`")
+            .value_parser(parse_delimiter)
+            .action(ArgAction::Set),
+        Arg::new("no-header")
+            .long("no-header")
+            .help("The tuples CSV file has no header row")
+            .action(ArgAction::SetTrue),
+    ]
+}
 
-/// For more details, see the [PDF documentation](../Dokumentacja.pdf).
-mod algorithm;
-mod mpi_utils;
+fn parse_delimiter(value: &str) -> Result<u8, String> {
+    match value {
+        "tab" | "\\t" => Ok(b'\t'),
+        _ => match value.as_bytes() {
+            [delimiter] => Ok(*delimiter),
+            _ => Err("the delimiter has to be a single ASCII character".to_string()),
+        },
+    }
+}
 
-/// Read the configuration and tuples from the command line arguments
-fn root_init() -> (AlgorithmConfig, Vec<Tuple>) {
-    let args = Command::new("Genetic Algorithm")
-        .arg(
-            Arg::new("config")
-                .short('c')
-                .value_name("FILE")
-                .help("Sets a custom config file")
-                .action(ArgAction::Set)
-                .required(false),
+fn explain_arg() -> Arg {
+    Arg::new("explain")
+        .long("explain")
+        .help("Describe the conflicts left in the best timetable")
+        .action(ArgAction::SetTrue)
+}
+
+fn ics_start_arg() -> Arg {
+    Arg::new("ics-start")
+        .long("ics-start")
+        .value_name("YYYY-MM-DD")
+        .help("Day the first period of the calendar is on, today by default")
+        .value_parser(|date: &str| parse_date(date).ok_or("expected a YYYY-MM-DD date"))
+        .action(ArgAction::Set)
+}
+
+/// Arguments of the subcommands reading a timetable written by `run --output`
+fn timetable_args() -> Vec<Arg> {
+    let mut args = input_args();
+    args.push(
+        Arg::new("timetable")
+            .value_name("TIMETABLE")
+            .help("Timetable written by --output, JSON or CSV by its extension")
+            .required(true)
+            .action(ArgAction::Set),
+    );
+    args
+}
+
+/// Arguments of the `run` subcommand
+fn run_args() -> Vec<Arg> {
+    let mut args = input_args();
+    args.push(
+        Arg::new("output")
+            .short('o')
+            .long("output")
+            .value_name("FILE")
+            .help("Write every class of the best timetable with its period to FILE")
+            .action(ArgAction::Set),
+    );
+    args.push(
+        Arg::new("format")
+            .long("format")
+            .value_name("FORMAT")
+            .help("Format of the --output file, JSON nests the classes in their periods")
+            .value_parser(["csv", "json"])
+            .default_value("csv")
+            .action(ArgAction::Set),
+    );
+    args.push(explain_arg());
+    args.push(
+        Arg::new("analyze-neighborhood")
+            .long("analyze-neighborhood")
+            .value_name("FILE")
+            .help("Write fitness changes of all single gene moves of the best timetable to a CSV")
+            .action(ArgAction::Set),
+    );
+    args.push(
+        Arg::new("ics")
+            .long("ics")
+            .value_name("FILE")
+            .help("Write the best timetable as an iCalendar file with an event for every class")
+            .action(ArgAction::Set),
+    );
+    args.push(ics_start_arg());
+    args.push(
+        Arg::new("check-completeness")
+            .long("check-completeness")
+            .help("Warn when the best timetable doesn't schedule every tuple exactly once")
+            .action(ArgAction::SetTrue),
+    );
+    args.push(
+        Arg::new("print-grid")
+            .long("print-grid")
+            .help("Print the best timetable as a grid of periods and rooms")
+            .action(ArgAction::SetTrue),
+    );
+    args.push(
+        Arg::new("utilization")
+            .long("utilization")
+            .value_name("FILE")
+            .help("Write the classes per period and room use of the best timetable to a CSV")
+            .action(ArgAction::Set),
+    );
+    args.push(
+        Arg::new("report")
+            .long("report")
+            .value_name("FILE")
+            .help("Write the score breakdown, conflicts left and utilization of the best timetable as JSON")
+            .action(ArgAction::Set),
+    );
+    args.push(
+        Arg::new("log-every")
+            .long("log-every")
+            .value_name("GENERATIONS")
+            .help("Print the parameters in effect every GENERATIONS generations")
+            .value_parser(clap::value_parser!(u64).range(1..))
+            .action(ArgAction::Set),
+    );
+    args.push(
+        Arg::new("metadata")
+            .long("metadata")
+            .help(
+                "Write the version, config, seed and input hash of the run to timetable.meta.json",
+            )
+            .action(ArgAction::SetTrue),
+    );
+    args.push(
+        Arg::new("report-churn")
+            .long("report-churn")
+            .help("Report how many genes moved between the best timetables of two generations")
+            .action(ArgAction::SetTrue),
+    );
+    args.push(
+        Arg::new("trace-selection")
+            .long("trace-selection")
+            .value_name("FILE")
+            .help(
+                "Record the ranks of the parents chosen for every child to a CSV, other nodes \
+                 than the root write FILE.<rank>",
+            )
+            .action(ArgAction::Set),
+    );
+    args.push(
+        Arg::new("auto-population")
+            .long("auto-population")
+            .help("Pick the population size from the problem size, unless the config sets it")
+            .action(ArgAction::SetTrue),
+    );
+    args.push(
+        Arg::new("stats")
+            .long("stats")
+            .help("Print the spread of the adaptations of every generation")
+            .action(ArgAction::SetTrue),
+    );
+    args.push(
+        Arg::new("history")
+            .long("history")
+            .value_name("FILE")
+            .help("Append the best, mean and worst adaptation of every generation to a CSV")
+            .action(ArgAction::Set),
+    );
+    args.push(
+        Arg::new("checkpoint")
+            .long("checkpoint")
+            .value_name("FILE")
+            .help("Save the population to FILE every checkpoint_interval generations")
+            .action(ArgAction::Set),
+    );
+    args.push(
+        Arg::new("resume")
+            .long("resume")
+            .value_name("FILE")
+            .help("Continue from a --checkpoint, resizing its population to the configured size")
+            .action(ArgAction::Set),
+    );
+    args.push(
+        Arg::new("pinned")
+            .long("pinned")
+            .value_name("FILE")
+            .help("Keep tuples in fixed periods, a JSON object of tuple ids and period indices")
+            .action(ArgAction::Set),
+    );
+    args.push(
+        Arg::new("population-size")
+            .long("population-size")
+            .value_name("SIZE")
+            .help("Override population_size of the config")
+            .value_parser(clap::value_parser!(usize))
+            .action(ArgAction::Set),
+    );
+    args.push(
+        Arg::new("generations")
+            .long("generations")
+            .value_name("GENERATIONS")
+            .help("Override max_generations of the config")
+            .value_parser(clap::value_parser!(usize))
+            .action(ArgAction::Set),
+    );
+    args.push(
+        Arg::new("mutation-probability")
+            .long("mutation-probability")
+            .value_name("PROBABILITY")
+            .help("Override mutation_probability of the config")
+            .value_parser(clap::value_parser!(f32))
+            .action(ArgAction::Set),
+    );
+    args.push(
+        Arg::new("crossover-probability")
+            .long("crossover-probability")
+            .value_name("PROBABILITY")
+            .help("Override crossover_probability of the config")
+            .value_parser(clap::value_parser!(f32))
+            .action(ArgAction::Set),
+    );
+    args.push(
+        Arg::new("threads")
+            .long("threads")
+            .value_name("N")
+            .help(
+                "Threads evaluating the population on every rank, all cores by default. With \
+                 several ranks on a node, use the cores of the node divided by its ranks",
+            )
+            .value_parser(clap::value_parser!(u64).range(1..))
+            .action(ArgAction::Set),
+    );
+    args.push(
+        Arg::new("repair-malformed")
+            .long("repair-malformed")
+            .help(
+                "Replace individuals with a wrong number of periods by a copy of the fittest one \
+                 before sending them to other nodes, instead of stopping",
+            )
+            .action(ArgAction::SetTrue),
+    );
+    args.push(
+        Arg::new("quiet")
+            .short('q')
+            .long("quiet")
+            .help("Don't show the progress bar of the generations")
+            .action(ArgAction::SetTrue),
+    );
+    args.push(
+        Arg::new("throughput")
+            .long("throughput")
+            .help("Print the individuals evaluated and generations run per second")
+            .action(ArgAction::SetTrue),
+    );
+    args
+}
+
+/// Command line interface
+///
+/// Running without a subcommand is the same as `run`, so `planner -c config.json -t tuples.csv`
+/// keeps working.
+fn cli() -> Command {
+    Command::new("Genetic Algorithm")
+        .args_conflicts_with_subcommands(true)
+        .args(run_args())
+        .subcommand(
+            Command::new("run")
+                .about("Search for the best timetable (default)")
+                .args(run_args()),
+        )
+        .subcommand(
+            Command::new("validate")
+                .about("Check that the config and tuples can be loaded, without running")
+                .args(input_args())
+                .arg(
+                    Arg::new("lint")
+                        .long("lint")
+                        .help("Also warn about suspicious data, like overbooked teachers")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("room-restrictions")
+                        .long("room-restrictions")
+                        .value_name("FILE")
+                        .help("Reject classes held outside the rooms listed for their teacher")
+                        .action(ArgAction::Set),
+                ),
         )
-        .arg(
-            Arg::new("tuples")
-                .short('t')
-                .value_name("FILE")
-                .help("Custom location of tuples")
-                .action(ArgAction::Set)
-                .required(false),
+        .subcommand(
+            Command::new("score")
+                .about("Evaluate a timetable against the config and tuples")
+                .args(timetable_args())
+                .arg(explain_arg()),
         )
-        .get_matches();
+        .subcommand(
+            Command::new("export")
+                .about("Convert a timetable to another format")
+                .args(timetable_args())
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help("File the converted timetable is written to")
+                        .required(true)
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Format of the --output file")
+                        .value_parser(["csv", "json", "ics"])
+                        .default_value("csv")
+                        .action(ArgAction::Set),
+                )
+                .arg(ics_start_arg()),
+        )
+}
 
+/// Paths of the config and tuples files, falling back to the default locations
+fn input_paths(args: &ArgMatches) -> (&str, &str) {
     let config_path = args
         .get_one::<String>("config")
         .map(String::as_str)
@@ -49,31 +397,475 @@ fn root_init() -> (AlgorithmConfig, Vec<Tuple>) {
         .map(String::as_str)
         .unwrap_or("tuples.csv");
 
-    let config = AlgorithmConfig::from_json(config_path).unwrap_or_default();
-    let tuples = Tuple::from_csv(tuples_path).expect("Tuples could not be loaded");
+    (config_path, tuples_path)
+}
 
-    return (config, tuples);
+/// Format given with `--tuples-format`, otherwise taken from the extension of the tuples file
+fn tuples_format(args: &ArgMatches, tuples_path: &str) -> TuplesFormat {
+    match args.get_one::<String>("tuples-format").map(String::as_str) {
+        Some("json") => TuplesFormat::Json,
+        Some(_) => TuplesFormat::Csv,
+        None => TuplesFormat::from_path(tuples_path),
+    }
 }
 
-/// If the population size is not divisible by the number of nodes, increase the population size
-fn adapt_population_size_to_worker_number(population_size: usize, rank: Rank, size: Rank) -> usize {
-    let mut new_population_size = population_size;
+/// Layout of the tuples CSV file given with `--delimiter` and `--no-header`
+fn csv_options(args: &ArgMatches) -> CsvOptions {
+    let default = CsvOptions::default();
+    CsvOptions {
+        delimiter: args
+            .get_one::<u8>("delimiter")
+            .copied()
+            .unwrap_or(default.delimiter),
+        has_headers: !args.get_flag("no-header"),
+    }
+}
 
-    if population_size % size as usize != 0 {
-        new_population_size = population_size + size as usize - (population_size % size as usize);
+/// Read the configuration and tuples from the command line arguments
+fn root_init(args: &ArgMatches) -> (AlgorithmConfig, Vec<Tuple>) {
+    let (config_path, tuples_path) = input_paths(args);
 
-        if rank == ROOT_RANK {
+    let mut config = load_config(config_path)
+        .and_then(|config| override_config(args, config))
+        .unwrap_or_else(|error| {
+            eprintln!("Invalid configuration {}: {}", config_path, error);
+            std::process::exit(1);
+        });
+    let tuples = Tuple::load(
+        tuples_path,
+        tuples_format(args, tuples_path),
+        csv_options(args),
+    )
+    .expect("Tuples could not be loaded");
+
+    if args.get_flag("auto-population")
+        && !sets_population_size(config_path)
+        && args.get_one::<usize>("population-size").is_none()
+    {
+        config.population_size = suggest_population_size(&config, &tuples);
+        println!("Using suggested population size {}", config.population_size);
+    }
+
+    return (config, tuples);
+}
+
+/// Load the configuration of any subcommand, falling back to the defaults with a warning only
+/// when the file doesn't exist. A file that can't be read or parsed, or has invalid values, is an
+/// error
+fn load_config(config_path: &str) -> Result<AlgorithmConfig, ConfigLoadError> {
+    match AlgorithmConfig::from_file(config_path) {
+        Err(ConfigLoadError::FileNotFound(error)) if error.kind() == ErrorKind::NotFound => {
+            eprintln!(
+                "Warning: no configuration at {}, using the defaults",
+                config_path
+            );
+            Ok(AlgorithmConfig::default())
+        }
+        result => result,
+    }
+}
+
+/// Load the checkpoint to resume from, exiting when it doesn't fit the configuration
+///
+/// The restored population is fitted to the configured population size, see
+/// [`rebalance_population`].
+fn load_checkpoint(args: &ArgMatches, config: &AlgorithmConfig) -> Option<Checkpoint> {
+    let path = args.get_one::<String>("resume")?;
+    let checkpoint = Checkpoint::load(path).and_then(|checkpoint| {
+        if checkpoint.population.len() != config.population_size {
             println!(
-                "Changing population size from {} to {}, to match node number",
-                population_size, new_population_size
-            )
+                "Resizing the restored population from {} to {}, keeping the fittest individuals",
+                checkpoint.population.len(),
+                config.population_size
+            );
+        }
+        Ok(Checkpoint {
+            population: rebalance_population(checkpoint.population, config)?,
+            ..checkpoint
+        })
+    });
+
+    match checkpoint {
+        Ok(checkpoint) => {
+            println!("Resuming from generation {}", checkpoint.generation);
+            Some(checkpoint)
+        }
+        Err(error) => {
+            eprintln!("Could not resume from {}: {}", path, error);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Tuples pinned to their periods with `--pinned`, none without it
+fn load_pinned(args: &ArgMatches, config: &AlgorithmConfig, tuples: &[Tuple]) -> Locks {
+    let Some(path) = args.get_one::<String>("pinned") else {
+        return Locks::new();
+    };
+    let locks = load_locks(path).and_then(|locks| {
+        validate_locks(&locks, tuples, config.number_of_periods)?;
+        Ok(locks)
+    });
+
+    match locks {
+        Ok(locks) => locks,
+        Err(error) => {
+            eprintln!("Could not pin the tuples from {}: {}", path, error);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Replace the fields of the loaded configuration given on the command line, which win over the
+/// file, and validate the result
+fn override_config(
+    args: &ArgMatches,
+    mut config: AlgorithmConfig,
+) -> Result<AlgorithmConfig, ConfigLoadError> {
+    if let Some(population_size) = args.get_one::<usize>("population-size") {
+        config.population_size = *population_size;
+    }
+    if let Some(generations) = args.get_one::<usize>("generations") {
+        config.max_generations = *generations;
+    }
+    if let Some(probability) = args.get_one::<f32>("mutation-probability") {
+        config.mutation_probability = *probability;
+    }
+    if let Some(probability) = args.get_one::<f32>("crossover-probability") {
+        config.crossover_probability = *probability;
+    }
+
+    config.validate()?;
+    Ok(config)
+}
+
+/// Thread pool of the size given with `--threads`, `None` keeps rayon's default of one thread per
+/// core
+///
+/// Every rank builds its own pool, so ranks sharing a node should split its cores between them
+/// rather than each using all of them.
+fn thread_pool_builder(args: &ArgMatches) -> Option<ThreadPoolBuilder> {
+    let threads = *args.get_one::<u64>("threads")?;
+    Some(ThreadPoolBuilder::new().num_threads(threads as usize))
+}
+
+/// Progress bar of the generations from `first_generation` to the last one, drawn to stderr when
+/// `visible` and otherwise only keeping count
+fn progress_bar(config: &AlgorithmConfig, first_generation: usize, visible: bool) -> ProgressBar {
+    let target = if visible {
+        ProgressDrawTarget::stderr()
+    } else {
+        ProgressDrawTarget::hidden()
+    };
+    let bar = ProgressBar::with_draw_target(Some(config.max_generations as u64), target)
+        .with_style(
+            ProgressStyle::with_template("{bar:40} {pos}/{len} generations, {msg} [{elapsed}]")
+                .expect("The progress bar template is valid"),
+        );
+    bar.set_position(first_generation as u64);
+    bar
+}
+
+/// Whether the config file sets the population size, rather than leaving the default
+fn sets_population_size(config_path: &str) -> bool {
+    let Ok(content) = std::fs::read_to_string(config_path) else {
+        return false;
+    };
+    if is_yaml(Path::new(config_path)) {
+        serde_yaml::from_str::<serde_yaml::Value>(&content)
+            .is_ok_and(|config| config.get("population_size").is_some())
+    } else {
+        serde_json::from_str::<serde_json::Value>(&content)
+            .is_ok_and(|config| config.get("population_size").is_some())
+    }
+}
+
+/// Warn when the selection weights of the weakest individuals underflow to zero
+fn warn_unselectable_ranks(config: &AlgorithmConfig) {
+    if let Some(unselectable) = first_unselectable_rank(config) {
+        println!(
+            "Warning: selection weights underflow to zero from rank {} of {}, those individuals \
+             are never selected as parents",
+            unselectable, config.population_size
+        );
+    }
+}
+
+/// Load the inputs and report problems without running the algorithm
+fn validate(args: &ArgMatches) -> anyhow::Result<()> {
+    let (config_path, tuples_path) = input_paths(args);
+
+    let config = load_config(config_path)?;
+    let tuples = Tuple::load(
+        tuples_path,
+        tuples_format(args, tuples_path),
+        csv_options(args),
+    )?;
+
+    println!("{:?}", config);
+    println!("Loaded {} tuples", tuples.len());
+    warn_unselectable_ranks(&config);
+
+    if args.get_flag("lint") {
+        for warning in lint_problem(&config, &tuples) {
+            println!("Warning: {}", warning);
+        }
+    }
+
+    if let Some(path) = args.get_one::<String>("room-restrictions") {
+        let violations = RoomRestrictions::from_csv(path)?.violations(&tuples);
+        for tuple in &violations {
+            println!("Room not allowed for the teacher: {}", tuple);
+        }
+        if !violations.is_empty() {
+            anyhow::bail!(
+                "{} classes are in rooms their teacher can't use",
+                violations.len()
+            );
         }
     }
 
-    new_population_size
+    Ok(())
+}
+
+/// Load the config and tuples, and the timetable given to `score` or `export`
+fn load_timetable_input(
+    args: &ArgMatches,
+) -> anyhow::Result<(AlgorithmConfig, TupleIndex, Individual)> {
+    let (config_path, tuples_path) = input_paths(args);
+
+    let config = load_config(config_path)?;
+    let tuples = TupleIndex::from(Tuple::load(
+        tuples_path,
+        tuples_format(args, tuples_path),
+        csv_options(args),
+    )?);
+    let timetable_path = args
+        .get_one::<String>("timetable")
+        .expect("The timetable is a required argument");
+    let individual = load_timetable(timetable_path, &tuples, config.number_of_periods)?;
+
+    Ok((config, tuples, individual))
+}
+
+/// Evaluate a timetable written by `run --output` without running the algorithm
+fn score(args: &ArgMatches) -> anyhow::Result<()> {
+    let (config, tuples, mut individual) = load_timetable_input(args)?;
+
+    individual.adaptation = calculate_fitness_cached(&config, &mut individual, &tuples);
+    let score = individual.score();
+    println!(
+        "Adaptation: {} (hard {}, soft {})",
+        individual.adaptation, score.hard, score.soft
+    );
+
+    if let Err(errors) = validate_completeness(&individual, &tuples) {
+        for error in errors {
+            println!("Warning: {}", error);
+        }
+    }
+
+    if args.get_flag("explain") {
+        let explanation = explain_conflicts(&individual, &tuples);
+        if explanation.is_empty() {
+            println!("No conflicts left");
+        } else {
+            println!("Conflicts left:\n{}", explanation);
+        }
+    }
+
+    Ok(())
+}
+
+/// Convert a timetable written by `run --output` to the format given with `--format`
+fn export(args: &ArgMatches) -> anyhow::Result<()> {
+    let (config, tuples, individual) = load_timetable_input(args)?;
+    let path = args
+        .get_one::<String>("output")
+        .expect("The output is a required argument");
+
+    match args.get_one::<String>("format").map(String::as_str) {
+        Some("json") => export_timetable_json(&individual, &tuples, path)?,
+        Some("ics") => {
+            let first_day = args
+                .get_one::<i64>("ics-start")
+                .copied()
+                .unwrap_or_else(|| day_of(SystemTime::now()));
+            export_ics(&config, &individual, &tuples, first_day, path)?
+        }
+        _ => export_timetable_csv(&individual, &tuples, path)?,
+    }
+
+    Ok(())
+}
+
+/// Resize a population to `new_size` individuals without losing its fittest ones
+///
+/// The population is sorted first, so shrinking drops the weakest individuals and growing
+/// appends copies of the fittest ones, in order. The best individual is always kept.
+fn resize_population(mut population: Population, new_size: usize) -> Population {
+    sort_by_adaptation(&mut population);
+
+    if new_size <= population.len() {
+        population.truncate(new_size);
+        return population;
+    }
+
+    let fillers: Population = population
+        .iter()
+        .cycle()
+        .take(new_size - population.len())
+        .cloned()
+        .collect();
+    population.extend(fillers);
+
+    population
+}
+
+/// Fit a population restored from a checkpoint to the configuration
+///
+/// The checkpoint may come from a run with a different population size or number of nodes.
+/// Uneven shards are split across any number of nodes, so only the configured population size has
+/// to be matched, see [`resize_population`]. Individuals with a different number of periods than
+/// configured are rejected.
+fn rebalance_population(
+    population: Population,
+    config: &AlgorithmConfig,
+) -> Result<Population, IndividualImportError> {
+    for individual in &population {
+        individual.validate_period_count(config.number_of_periods)?;
+    }
+
+    Ok(resize_population(population, config.population_size))
+}
+
+/// With `--repair-malformed`, replace the individuals that can't be sent to other nodes, see
+/// [`repair_period_counts`]. Without it sending them fails with the index of the first one
+fn repair_malformed(population: &mut Population, config: &AlgorithmConfig, enabled: bool) {
+    if !enabled {
+        return;
+    }
+    for index in repair_period_counts(population, config.number_of_periods) {
+        println!(
+            "Warning: individual {} doesn't have {} periods, replaced by a copy of the fittest one",
+            index, config.number_of_periods
+        );
+    }
+}
+
+/// Whether the run stops after the generation, the same decision on every node
+///
+/// `decide_at_root` runs the decision on the root and gives its result to every node, see
+/// [`mpi_execute_and_synchronize_at`]. The root decides for everyone, so all nodes leave the loop
+/// in the same generation even if their copies of the population were ever sorted differently.
+/// Without gathering every generation the root still decides on the time budget, a perfect
+/// timetable or stagnation stop the run on their own as every node knows the same `best_score`,
+/// reduced across the nodes.
+#[allow(clippy::too_many_arguments)]
+fn should_stop(
+    stop_condition: &mut StopCondition,
+    config: &AlgorithmConfig,
+    generation_number: usize,
+    population: &Population,
+    best_score: Score,
+    gather_every_generation: bool,
+    tuples: &TupleIndex,
+    decide_at_root: impl FnOnce(&mut dyn FnMut() -> bool) -> bool,
+) -> bool {
+    if gather_every_generation {
+        return decide_at_root(&mut || {
+            stop_condition.should_stop(generation_number, population, tuples)
+        });
+    }
+
+    let out_of_time =
+        config.time_budget_secs.is_some() && decide_at_root(&mut || stop_condition.out_of_time());
+    out_of_time
+        || stop_condition.record_best(best_score)
+        || best_score.total() == Adaptation::default()
+}
+
+/// Gather the best `k` individuals of every island to all nodes
+fn gather_best_of_islands(
+    island: &Population,
+    k: usize,
+    world: &impl Communicator<Raw = mpi::ffi::MPI_Comm>,
+    compress: bool,
+) -> Population {
+    mpi_gather_best_k(
+        island,
+        k,
+        |a, b| compare_score(&b.score(), &a.score()),
+        world,
+        ROOT_RANK,
+        compress,
+    )
+    .expect("Could not gather the best individuals")
+}
+
+/// Whether islands exchange migrants after the generation, see
+/// [`AlgorithmConfig::migration_interval`]
+fn migrates(config: &AlgorithmConfig, generation_number: usize) -> bool {
+    config.island_mode
+        && config.migration_interval != 0
+        && (generation_number + 1).is_multiple_of(config.migration_interval)
+}
+
+/// Send copies of the best individuals of a sorted island through `exchange` and let the
+/// individuals it returns replace the weakest ones, see [`integrate_migrants`]
+fn migrate(
+    island: &mut Population,
+    config: &AlgorithmConfig,
+    exchange: impl FnOnce(&[Individual]) -> Population,
+) {
+    let emigrants = best_k(island, config.migration_count, |a, b| {
+        compare_score(&b.score(), &a.score())
+    });
+    integrate_migrants(island, exchange(&emigrants));
 }
 
 fn main() {
+    let matches = cli().get_matches();
+
+    // `RUST_LOG=debug` or `RUST_LOG=planner=trace` for more, info by default
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .init();
+
+    let run_args = match matches.subcommand() {
+        Some(("validate", validate_args)) => {
+            if let Err(error) = validate(validate_args) {
+                eprintln!("Validation failed: {}", error);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some(("score", score_args)) => {
+            if let Err(error) = score(score_args) {
+                eprintln!("Scoring failed: {}", error);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some(("export", export_args)) => {
+            if let Err(error) = export(export_args) {
+                eprintln!("Export failed: {}", error);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some(("run", run_args)) => run_args,
+        _ => &matches,
+    };
+
+    if let Some(builder) = thread_pool_builder(run_args) {
+        builder
+            .build_global()
+            .expect("The thread pool could not be created");
+    }
+
     let (universe, threading) = mpi::initialize_with_threading(Threading::Multiple).unwrap();
     assert_eq!(threading, mpi::environment::threading_support());
 
@@ -81,53 +873,436 @@ fn main() {
 
     let size = world.size();
     let rank = world.rank();
+    let _rank = info_span!("node", rank).entered();
 
-    let (mut config, tuples) = mpi_execute_and_synchronize_at(root_init, &world, ROOT_RANK);
-
-    config.population_size =
-        adapt_population_size_to_worker_number(config.population_size, rank, size);
+    let (config, tuples, checkpoint, locks) = mpi_execute_and_synchronize_at(
+        || {
+            let (config, tuples) = root_init(run_args);
+            let checkpoint = load_checkpoint(run_args, &config);
+            let locks = load_pinned(run_args, &config, &tuples);
+            (config, tuples, checkpoint, locks)
+        },
+        &world,
+        ROOT_RANK,
+    );
+    let tuples = TupleIndex::from(tuples);
 
     println!("{:?}", config);
 
-    let mut population = algorithm::create_first_population(&config, &tuples);
+    // every node has the same configuration and size, so they all stop here together
+    if let Err(error) = config.validate_for_nodes(size as usize) {
+        if rank == ROOT_RANK {
+            eprintln!("Invalid configuration: {}", error);
+        }
+        return;
+    }
+    if rank == ROOT_RANK {
+        warn_unselectable_ranks(&config);
+    }
 
-    for generation_number in 0..config.max_generations {
-        let mut population_to_be_processed =
-            mpi_split_data_across_nodes(&population, &world, ROOT_RANK);
+    let start = Instant::now();
+    let first_generation = checkpoint
+        .as_ref()
+        .map_or(0, |checkpoint| checkpoint.generation);
+    let mut population = match checkpoint {
+        Some(checkpoint) => checkpoint.population,
+        None => create_first_population_with_locks(&config, &tuples, &locks),
+    };
 
-        if rank == ROOT_RANK {
-            println!("Generation: {}", generation_number + 1);
+    // evaluate the random population, it is the baseline the result is compared against
+    population.par_iter_mut().for_each(|individual| {
+        individual.adaptation = calculate_fitness_cached(&config, individual, &tuples);
+    });
+    sort_by_adaptation(&mut population);
+    let baseline_adaptation = population[0].adaptation;
+    // every node evaluates the whole first population, the counts of all nodes are summed at the
+    // end of the run
+    let evaluations = AtomicU64::new(population.len() as u64);
+
+    // with `gather_best_k` or `island_mode` every node keeps evolving its own part of the first
+    // population
+    let gathered_per_island = config.gather_best_k.or(config.island_mode.then_some(1));
+    let mut island = if gathered_per_island.is_some() {
+        let mut island =
+            mpi_split_data_across_nodes(&population, &world, ROOT_RANK, config.compress_mpi)
+                .expect("Could not split the population across the nodes");
+        sort_by_adaptation(&mut island);
+        island
+    } else {
+        Population::new()
+    };
+
+    let mut generations = first_generation;
+    let mut stop_condition = StopCondition::new(&config);
+    let report_churn = run_args.get_flag("report-churn");
+    let print_stats = run_args.get_flag("stats");
+    let history_path = run_args.get_one::<String>("history");
+    // the file is opened before the run, so a wrong path doesn't waste it
+    let mut history = None;
+    if let Some(path) = history_path.filter(|_| rank == ROOT_RANK) {
+        match HistoryWriter::create(path) {
+            Ok(writer) => history = Some(writer),
+            Err(error) => eprintln!("Could not create the fitness history {}: {}", path, error),
+        }
+    }
+    let history_created = mpi_execute_and_synchronize_at(
+        || history_path.is_none() || history.is_some(),
+        &world,
+        ROOT_RANK,
+    );
+    if !history_created {
+        return;
+    }
+    let checkpoint_path = run_args
+        .get_one::<String>("checkpoint")
+        .filter(|_| config.checkpoint_interval > 0);
+    let log_every = run_args
+        .get_one::<u64>("log-every")
+        .map(|every| *every as usize);
+    let selection_trace = run_args
+        .get_one::<String>("trace-selection")
+        .map(|_| SelectionTrace::default());
+    let repair = run_args.get_flag("repair-malformed");
+    // islands only gather their best individuals when something looks at them, the best
+    // score is reduced across the nodes otherwise
+    let gather_every_generation = gathered_per_island.is_none()
+        || config.gather_best_k.is_some()
+        || report_churn
+        || print_stats
+        || history_path.is_some()
+        || config.adaptive_mutation.is_some()
+        || stop_condition.needs_population();
+
+    // only the root shows the progress, the bar replaces its line per generation
+    let progress = progress_bar(
+        &config,
+        first_generation,
+        rank == ROOT_RANK && !run_args.get_flag("quiet"),
+    );
+
+    for generation_number in first_generation..config.max_generations {
+        generations = generation_number + 1;
+        let _generation = info_span!("generation", generation = generations).entered();
+        // the root measures the diversity and sends the immigrants to every node before the
+        // population is split, islands exchange migrants instead
+        if config.immigrant_count > 0 && gathered_per_island.is_none() {
+            let immigrants = mpi_execute_and_synchronize_at(
+                || random_immigrants(&config, &population, generation_number, &tuples, &locks),
+                &world,
+                ROOT_RANK,
+            );
+            if !immigrants.is_empty() {
+                if rank == ROOT_RANK {
+                    info!(count = immigrants.len(), "random immigrants");
+                    // only the root evaluated them
+                    evaluations.fetch_add(immigrants.len() as u64, Ordering::Relaxed);
+                }
+                integrate_migrants(&mut population, immigrants);
+            }
         }
+        // the root measures the diversity, so every node mutates with the same probability
+        let generation_config = match config.adaptive_mutation {
+            Some(_) => AlgorithmConfig {
+                mutation_probability: mpi_execute_and_synchronize_at(
+                    || adaptive_mutation_probability(&config, &population),
+                    &world,
+                    ROOT_RANK,
+                ),
+                ..config
+            },
+            None => config,
+        };
 
-        population_to_be_processed = population_to_be_processed
-            .par_iter()
-            .map(|_| crossover(&config, &population))
-            .map(|mut individual| {
-                mutate(&config, &mut individual);
-                individual
-            })
-            .map(|mut individual| {
-                individual.adaptation = calculate_fitness(&individual, &tuples, false);
-                individual
-            })
-            .collect();
+        if rank == ROOT_RANK && log_every.is_some_and(|every| generation_number % every == 0) {
+            info!(
+                parameters = %effective_parameters(&generation_config, generation_number),
+                "effective parameters"
+            );
+        }
+
+        let previous_best = report_churn.then(|| population[0].clone());
 
-        population = mpi_gather_and_synchronize(&population_to_be_processed, &world, ROOT_RANK);
+        if let Some(k) = gathered_per_island {
+            // islands mated the same way on every node would be copies of each other
+            let island_config = AlgorithmConfig {
+                population_size: island.len(),
+                random_seed: config
+                    .random_seed
+                    .map(|seed| mix_seed(seed, &[rank as u64])),
+                ..generation_config
+            };
 
-        population.sort_by(|a, b| b.adaptation.partial_cmp(&a.adaptation).unwrap());
+            island = evolve_one_generation(
+                &island_config,
+                &island,
+                generation_number,
+                &tuples,
+                &locks,
+                selection_trace.as_ref(),
+                Some(&evaluations),
+            );
+            repair_malformed(&mut island, &config, repair);
+
+            if migrates(&config, generation_number) {
+                migrate(&mut island, &config, |emigrants| {
+                    mpi_ring_exchange(emigrants, &world).expect("Could not exchange migrants")
+                });
+            }
+
+            if gather_every_generation {
+                population = gather_best_of_islands(&island, k, &world, config.compress_mpi);
+            }
+        } else if size == 1 {
+            // a single node doesn't need to split and gather anything
+            population = evolve_one_generation(
+                &generation_config,
+                &population,
+                generation_number,
+                &tuples,
+                &locks,
+                selection_trace.as_ref(),
+                Some(&evaluations),
+            );
+        } else {
+            let order = split_order(
+                population.len(),
+                config
+                    .deterministic_chunking
+                    .then(|| config.random_seed.unwrap_or_default()),
+                generation_number,
+            );
+            // the individuals are serialized in the shuffled order without copying them
+            let ordered_population = order.par_iter().map(|&index| &population[index]);
+
+            let mut population_to_be_processed = mpi_split_data_across_nodes(
+                ordered_population,
+                &world,
+                ROOT_RANK,
+                config.compress_mpi,
+            )
+            .expect("Could not split the population across the nodes");
+            let offset = shard_offset(population.len(), size, rank);
+            let positions = &order[offset..offset + population_to_be_processed.len()];
+
+            population_to_be_processed = evolve_shard(
+                &generation_config,
+                &population,
+                &population_to_be_processed,
+                positions,
+                generation_number,
+                &tuples,
+                &locks,
+                selection_trace.as_ref(),
+                Some(&evaluations),
+            );
+            repair_malformed(&mut population_to_be_processed, &config, repair);
+
+            population = mpi_gather_and_synchronize(
+                &population_to_be_processed,
+                &world,
+                ROOT_RANK,
+                config.compress_mpi,
+            )
+            .expect("Could not gather the population");
+        }
+
+        sort_by_adaptation(&mut population);
+        let best_score = if gather_every_generation {
+            population[0].score()
+        } else {
+            mpi_best_score(&island, &world)
+        };
+        let best_adaptation = best_score.total();
+
+        // islands only gather their best individuals, the history describes all of them
+        let island_spread = (gathered_per_island.is_some() && history_path.is_some())
+            .then(|| mpi_mean_and_worst_adaptation(&island, &world));
 
         // early stop, print results
         if rank == ROOT_RANK {
-            println!("Best adaptation: {}", population[0].adaptation);
+            progress.set_position(generations as u64);
+            progress.set_message(format!("best adaptation {}", best_adaptation));
+            if progress.is_hidden() {
+                info!(best_adaptation = %best_adaptation, "generation complete");
+            }
+
+            let stats = (print_stats || history.is_some())
+                .then(|| GenerationStats::new(generation_number + 1, &population))
+                .flatten();
+            if let Some(stats) = stats.filter(|_| print_stats) {
+                info!(%stats, "generation stats");
+            }
+            if let (Some(stats), Some(writer)) = (stats, &mut history) {
+                let written = match island_spread {
+                    Some((mean, worst)) => writer.write_with_spread(&stats, mean, worst),
+                    None => writer.write(&stats),
+                };
+                if let Err(error) = written {
+                    error!(%error, "could not write the fitness history, stopping it");
+                    history = None;
+                }
+            }
+
+            if let Some(previous_best) = previous_best {
+                info!(
+                    churn = genome_distance(&previous_best, &population[0]),
+                    "best solution churn"
+                );
+            }
+        }
+        if checkpoint_path.is_some() && generations % config.checkpoint_interval == 0 {
+            // islands only gather their best individuals, but the checkpoint keeps all of them
+            let checkpoint = Checkpoint {
+                generation: generations,
+                population: match gathered_per_island {
+                    Some(_) => {
+                        gather_best_of_islands(&island, island.len(), &world, config.compress_mpi)
+                    }
+                    None => population.clone(),
+                },
+            };
+            if let Some(path) = checkpoint_path.filter(|_| rank == ROOT_RANK) {
+                if let Err(error) = checkpoint.save(path) {
+                    error!(path, %error, "could not save the checkpoint");
+                }
+            }
         }
-        if population[0].adaptation == 0 {
+
+        let stop = should_stop(
+            &mut stop_condition,
+            &config,
+            generation_number,
+            &population,
+            best_score,
+            gather_every_generation,
+            &tuples,
+            |decide| mpi_execute_and_synchronize_at(decide, &world, ROOT_RANK),
+        );
+        if stop {
             break;
         }
     }
+    // leaves the bar at the generation the run stopped in rather than filling it
+    progress.abandon();
+
+    if !gather_every_generation {
+        population = gather_best_of_islands(&island, 1, &world, config.compress_mpi);
+        sort_by_adaptation(&mut population);
+    }
+    let evaluations = mpi_total(evaluations.into_inner(), &world);
+
+    if let (Some(trace), Some(path)) = (
+        &selection_trace,
+        run_args.get_one::<String>("trace-selection"),
+    ) {
+        // every node records the children it created
+        let path = if rank == ROOT_RANK {
+            path.clone()
+        } else {
+            format!("{}.{}", path, rank)
+        };
+        trace
+            .write_csv(path)
+            .expect("Could not write selection trace");
+    }
 
     if rank == ROOT_RANK {
-        let best_individual = &population[0];
+        let result = RunResult {
+            best: population[0].clone(),
+            generations,
+            baseline_adaptation,
+            evaluations,
+            elapsed: start.elapsed(),
+        };
+        let best_individual = &result.best;
         println!("Best adaptation: {}", best_individual.adaptation);
+        println!(
+            "Improvement over random baseline: {:.2}%",
+            result.improvement_over_baseline()
+        );
+        if run_args.get_flag("throughput") {
+            println!(
+                "Throughput: {:.1} individuals/s, {:.2} generations/s",
+                result.evaluations_per_second(),
+                result.generations_per_second()
+            );
+        }
+        if config.required_solutions.is_some() {
+            println!(
+                "Distinct feasible solutions found: {}",
+                stop_condition.archive().len()
+            );
+        }
+
+        if let Some(path) = run_args.get_one::<String>("analyze-neighborhood") {
+            let moves = analyze_neighborhood(&config, best_individual, &tuples);
+            println!(
+                "Improving moves left: {} of {}",
+                moves
+                    .iter()
+                    .filter(|m| m.delta > Adaptation::default())
+                    .count(),
+                moves.len()
+            );
+            write_neighborhood_csv(path, &moves).expect("Could not write neighborhood analysis");
+        }
+
+        if let Some(path) = run_args.get_one::<String>("output") {
+            let exported = match run_args.get_one::<String>("format").map(String::as_str) {
+                Some("json") => export_timetable_json(best_individual, &tuples, path),
+                _ => export_timetable_csv(best_individual, &tuples, path),
+            };
+            exported.expect("Could not export the timetable");
+        }
+
+        if let Some(path) = run_args.get_one::<String>("ics") {
+            let first_day = run_args
+                .get_one::<i64>("ics-start")
+                .copied()
+                .unwrap_or_else(|| day_of(SystemTime::now()));
+            export_ics(&config, best_individual, &tuples, first_day, path)
+                .expect("Could not export the calendar");
+        }
+
+        if let Some(path) = run_args.get_one::<String>("utilization") {
+            write_utilization_csv(path, &utilization(best_individual, &tuples))
+                .expect("Could not write utilization statistics");
+        }
+
+        if let Some(path) = run_args.get_one::<String>("report") {
+            SolutionReport::new(&config, &result, &tuples)
+                .write_json(path)
+                .expect("Could not write the solution report");
+        }
+
+        if run_args.get_flag("check-completeness") {
+            if let Err(errors) = validate_completeness(best_individual, &tuples) {
+                for error in errors {
+                    println!("Warning: {}", error);
+                }
+            }
+        }
+
+        if run_args.get_flag("print-grid") {
+            println!("{}", render_grid(best_individual, &tuples));
+        }
+
+        if run_args.get_flag("explain") {
+            let explanation = explain_conflicts(best_individual, &tuples);
+            if explanation.is_empty() {
+                println!("No conflicts left");
+            } else {
+                println!("Conflicts left:\n{}", explanation);
+            }
+        }
+        if run_args.get_flag("metadata") {
+            let (_, tuples_path) = input_paths(run_args);
+            let tuples_file = std::fs::read(tuples_path).expect("Could not read tuples");
+            RunMetadata::new(&config, size as usize, &tuples_file, SystemTime::now())
+                .write_json("timetable.meta.json")
+                .expect("Could not write run metadata");
+        }
+
         let out_file = OpenOptions::new()
             .write(true)
             .create(true)
@@ -146,10 +1321,564 @@ fn main() {
                 let mapped_tuples = chromosome
                     .genes
                     .iter()
-                    .map(|gene| tuples.iter().find(|tuple| tuple.id == *gene).unwrap());
+                    .map(|gene| tuples.get(*gene).unwrap());
                 let tuples_as_string = mapped_tuples.map(|tuple| tuple.to_string()).join("\n - ");
                 writeln!(buf_writer, "{}:\n - {}", index + 1, tuples_as_string)
                     .expect("Could not write to file");
             });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mpi::Rank;
+    use planner::{
+        algorithm::{datatypes::Chromosome, engine::GeneticAlgorithm, testing::TempFile},
+        mpi_utils::{ring_neighbors, shard_sizes},
+    };
+
+    /// Population as checkpointed by a run on 4 nodes
+    fn checkpointed_population() -> Population {
+        (0..12)
+            .map(|i| Individual {
+                adaptation: -i as Adaptation,
+                chromosomes: (0..8).map(Chromosome::new).collect(),
+                ..Individual::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bare_invocation_parses_as_run() {
+        let matches = cli()
+            .try_get_matches_from(["planner", "-c", "custom.json", "-t", "custom.csv"])
+            .unwrap();
+
+        assert!(matches.subcommand().is_none());
+        assert_eq!(input_paths(&matches), ("custom.json", "custom.csv"));
+    }
+
+    #[test]
+    fn test_subcommands_parse_their_args() {
+        for subcommand in ["run", "validate"] {
+            let matches = cli()
+                .try_get_matches_from(["planner", subcommand, "-t", "custom.csv"])
+                .unwrap();
+            let (name, args) = matches.subcommand().unwrap();
+
+            assert_eq!(name, subcommand);
+            assert_eq!(input_paths(args), ("config.json", "custom.csv"));
+        }
+    }
+
+    #[test]
+    fn test_timetable_subcommands_parse_their_args() {
+        let matches = cli()
+            .try_get_matches_from(["planner", "score", "best.csv", "--explain"])
+            .unwrap();
+        let (name, args) = matches.subcommand().unwrap();
+        assert_eq!(name, "score");
+        assert_eq!(args.get_one::<String>("timetable").unwrap(), "best.csv");
+        assert!(args.get_flag("explain"));
+        assert_eq!(input_paths(args), ("config.json", "tuples.csv"));
+
+        let matches = cli()
+            .try_get_matches_from([
+                "planner",
+                "export",
+                "-c",
+                "custom.json",
+                "best.json",
+                "-o",
+                "best.ics",
+                "--format",
+                "ics",
+                "--ics-start",
+                "2024-09-02",
+            ])
+            .unwrap();
+        let (name, args) = matches.subcommand().unwrap();
+        assert_eq!(name, "export");
+        assert_eq!(args.get_one::<String>("timetable").unwrap(), "best.json");
+        assert_eq!(args.get_one::<String>("output").unwrap(), "best.ics");
+        assert_eq!(args.get_one::<String>("format").unwrap(), "ics");
+        assert_eq!(args.get_one::<i64>("ics-start"), Some(&19_968));
+        assert_eq!(input_paths(args), ("custom.json", "tuples.csv"));
+
+        // the timetable is required, and so is the output of an export
+        assert!(cli().try_get_matches_from(["planner", "score"]).is_err());
+        assert!(cli()
+            .try_get_matches_from(["planner", "export", "best.csv"])
+            .is_err());
+        assert!(cli()
+            .try_get_matches_from(["planner", "score", "best.csv", "-o", "out.csv"])
+            .is_err());
+    }
+
+    #[test]
+    fn test_tuples_format_from_flag_or_extension() {
+        let format = |args: &[&str]| {
+            let matches = cli().try_get_matches_from(args).unwrap();
+            let (_, tuples_path) = input_paths(&matches);
+            tuples_format(&matches, tuples_path)
+        };
+
+        assert_eq!(format(&["planner"]), TuplesFormat::Csv);
+        assert_eq!(
+            format(&["planner", "-t", "tuples.json"]),
+            TuplesFormat::Json
+        );
+        assert_eq!(
+            format(&["planner", "-t", "tuples.txt", "--tuples-format", "json"]),
+            TuplesFormat::Json
+        );
+        assert_eq!(
+            format(&["planner", "-t", "tuples.json", "--tuples-format", "csv"]),
+            TuplesFormat::Csv
+        );
+    }
+
+    #[test]
+    fn test_missing_config_defaults_but_malformed_fails() {
+        let path = TempFile::new("missing-config", "json");
+        let path = path.to_str().unwrap();
+
+        assert_eq!(load_config(path).unwrap(), AlgorithmConfig::default());
+
+        std::fs::write(path, r#"{"population_size": 50,"#).unwrap();
+        assert!(matches!(
+            load_config(path),
+            Err(ConfigLoadError::JsonError(_))
+        ));
+
+        std::fs::write(path, r#"{"population_size": 1}"#).unwrap();
+        assert!(matches!(
+            load_config(path),
+            Err(ConfigLoadError::PopulationTooSmall(1))
+        ));
+    }
+
+    #[test]
+    fn test_command_line_overrides_config_file() {
+        let path = TempFile::with_contents(
+            "overridden",
+            "json",
+            r#"{"population_size": 50, "max_generations": 300, "mutation_probability": 0.2}"#,
+        );
+        let path = path.to_str().unwrap();
+        let config = |args: &[&str]| {
+            let matches = cli().try_get_matches_from(args).unwrap();
+            override_config(&matches, load_config(path).unwrap())
+        };
+
+        let unchanged = config(&["planner"]).unwrap();
+        assert_eq!(unchanged.population_size, 50);
+        assert_eq!(unchanged.max_generations, 300);
+
+        let overridden = config(&[
+            "planner",
+            "--population-size",
+            "80",
+            "--generations",
+            "20",
+            "--crossover-probability",
+            "0.7",
+        ])
+        .unwrap();
+        assert_eq!(
+            overridden,
+            AlgorithmConfig {
+                population_size: 80,
+                max_generations: 20,
+                crossover_probability: 0.7,
+                ..unchanged
+            }
+        );
+        assert_eq!(overridden.mutation_probability, 0.2);
+
+        assert!(matches!(
+            config(&["planner", "--mutation-probability", "1.5"]),
+            Err(ConfigLoadError::ProbabilityOutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn test_thread_pool_size_from_flag() {
+        let pool = |args: &[&str]| thread_pool_builder(&cli().try_get_matches_from(args).unwrap());
+
+        assert!(pool(&["planner"]).is_none());
+        let pool = pool(&["planner", "--threads", "3"])
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(pool.current_num_threads(), 3);
+        assert!(cli()
+            .try_get_matches_from(["planner", "--threads", "0"])
+            .is_err());
+    }
+
+    #[test]
+    fn test_progress_bar_stops_at_the_last_generation_run() {
+        let config = AlgorithmConfig {
+            population_size: 10,
+            number_of_periods: 2,
+            max_generations: 50,
+            random_seed: Some(1),
+            time_budget_secs: Some(0),
+            ..AlgorithmConfig::default()
+        };
+        let tuples = (1..=4)
+            .map(|id| Tuple {
+                id,
+                teacher: format!("T{}", id % 2),
+                room: format!("R{}", id % 3),
+                ..Tuple::default()
+            })
+            .collect();
+        let mut algorithm = GeneticAlgorithm::new(config, tuples);
+
+        let progress = progress_bar(&config, 0, false);
+        loop {
+            let stopped = algorithm.step();
+            progress.set_position(algorithm.generation() as u64);
+            if stopped {
+                break;
+            }
+        }
+        progress.abandon();
+
+        // the time budget stops the run after the first generation
+        assert!(progress.is_finished());
+        assert_eq!(progress.position(), 1);
+        assert_eq!(progress.length(), Some(50));
+    }
+
+    #[test]
+    fn test_csv_options_from_flags() {
+        let options = |args: &[&str]| csv_options(&cli().try_get_matches_from(args).unwrap());
+
+        assert_eq!(options(&["planner"]), CsvOptions::default());
+        assert_eq!(
+            options(&["planner", "--delimiter", ";", "--no-header"]),
+            CsvOptions {
+                delimiter: b';',
+                has_headers: false
+            }
+        );
+        assert_eq!(options(&["planner", "--delimiter", "tab"]).delimiter, b'\t');
+        assert!(cli()
+            .try_get_matches_from(["planner", "--delimiter", ";;"])
+            .is_err());
+    }
+
+    #[test]
+    fn test_run_flags() {
+        let matches = cli()
+            .try_get_matches_from(["planner", "--explain"])
+            .unwrap();
+        assert!(matches.get_flag("explain"));
+
+        let matches = cli().try_get_matches_from(["planner", "run"]).unwrap();
+        assert!(!matches.subcommand().unwrap().1.get_flag("explain"));
+
+        assert!(cli()
+            .try_get_matches_from(["planner", "validate", "--explain"])
+            .is_err());
+
+        let matches = cli()
+            .try_get_matches_from(["planner", "--log-every", "10"])
+            .unwrap();
+        assert_eq!(matches.get_one::<u64>("log-every"), Some(&10));
+
+        assert!(cli()
+            .try_get_matches_from(["planner", "--log-every", "0"])
+            .is_err());
+
+        let matches = cli()
+            .try_get_matches_from(["planner", "run", "--trace-selection", "trace.csv"])
+            .unwrap();
+        assert_eq!(
+            matches
+                .subcommand()
+                .unwrap()
+                .1
+                .get_one::<String>("trace-selection")
+                .map(String::as_str),
+            Some("trace.csv")
+        );
+
+        let matches = cli()
+            .try_get_matches_from(["planner", "run", "--auto-population"])
+            .unwrap();
+        assert!(matches.subcommand().unwrap().1.get_flag("auto-population"));
+
+        let matches = cli()
+            .try_get_matches_from(["planner", "--throughput"])
+            .unwrap();
+        assert!(matches.get_flag("throughput"));
+
+        let matches = cli()
+            .try_get_matches_from(["planner", "run", "--repair-malformed"])
+            .unwrap();
+        assert!(matches.subcommand().unwrap().1.get_flag("repair-malformed"));
+    }
+
+    #[test]
+    fn test_validate_lint_flag() {
+        let matches = cli()
+            .try_get_matches_from(["planner", "validate", "--lint"])
+            .unwrap();
+        assert!(matches.subcommand().unwrap().1.get_flag("lint"));
+
+        assert!(cli().try_get_matches_from(["planner", "--lint"]).is_err());
+    }
+
+    #[test]
+    fn test_root_args_conflict_with_subcommands() {
+        assert!(cli()
+            .try_get_matches_from(["planner", "-c", "custom.json", "validate"])
+            .is_err());
+    }
+
+    #[test]
+    fn test_resize_population_keeps_best() {
+        let mut population = checkpointed_population();
+        population.reverse();
+        let best = population.last().unwrap().adaptation;
+
+        for new_size in [1, 5, 12, 13, 30] {
+            let resized = resize_population(population.clone(), new_size);
+
+            assert_eq!(resized.len(), new_size);
+            assert_eq!(resized[0].adaptation, best);
+        }
+
+        let shrunk = resize_population(population.clone(), 3);
+        assert_eq!(
+            shrunk.iter().map(|i| i.adaptation).collect::<Vec<_>>(),
+            [0, -1, -2].map(|a| a as Adaptation)
+        );
+
+        let grown = resize_population(population, 14);
+        assert_eq!(grown[12].adaptation, grown[0].adaptation);
+        assert_eq!(grown[13].adaptation, grown[1].adaptation);
+    }
+
+    #[test]
+    fn test_rebalance_population_to_the_configured_size() {
+        for population_size in [5, 12, 16] {
+            let config = AlgorithmConfig {
+                population_size,
+                ..AlgorithmConfig::default()
+            };
+            let population = rebalance_population(checkpointed_population(), &config).unwrap();
+
+            assert_eq!(population.len(), population_size);
+            assert_eq!(population[0].adaptation, 0 as Adaptation);
+        }
+    }
+
+    #[test]
+    fn test_ten_individuals_on_eight_islands() {
+        let config = AlgorithmConfig {
+            population_size: 10,
+            number_of_periods: 2,
+            island_mode: true,
+            random_seed: Some(2),
+            ..AlgorithmConfig::default()
+        };
+        assert!(matches!(
+            config.validate_for_nodes(8),
+            Err(ConfigLoadError::IslandsTooSmall {
+                population_size: 10,
+                nodes: 8
+            })
+        ));
+        assert!(config.validate_for_nodes(5).is_ok());
+        assert!(AlgorithmConfig {
+            island_mode: false,
+            gather_best_k: Some(1),
+            ..config
+        }
+        .validate_for_nodes(8)
+        .is_err());
+        // the whole population is evolved together without islands
+        assert!(AlgorithmConfig {
+            island_mode: false,
+            ..config
+        }
+        .validate_for_nodes(8)
+        .is_ok());
+
+        // islands of a single individual are carried over instead of mated
+        let tuples: Vec<Tuple> = (1..=4)
+            .map(|id| Tuple {
+                id,
+                ..Tuple::default()
+            })
+            .collect();
+        let population = create_first_population_with_locks(&config, &tuples, &Locks::new());
+        let tuples = TupleIndex::from(tuples);
+        let mut offset = 0;
+        for island_size in shard_sizes(10, 8) {
+            let island = population[offset..offset + island_size].to_vec();
+            offset += island_size;
+            let island_config = AlgorithmConfig {
+                population_size: island.len(),
+                ..config
+            };
+
+            let next = evolve_one_generation(
+                &island_config,
+                &island,
+                0,
+                &tuples,
+                &Locks::new(),
+                None,
+                None,
+            );
+            assert_eq!(next.len(), island_size);
+        }
+        assert!(evolve_one_generation(
+            &config,
+            &Population::new(),
+            0,
+            &tuples,
+            &Locks::new(),
+            None,
+            None
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_all_ranks_stop_in_the_same_generation() {
+        let config = AlgorithmConfig {
+            stagnation_limit: 3,
+            ..AlgorithmConfig::default()
+        };
+        let tuples = TupleIndex::from(Vec::new());
+
+        // 3 nodes whose islands never improve, the individuals of node `rank` have adaptations
+        // `-10 * rank - i - 1`
+        let islands: Vec<Population> = (0..3)
+            .map(|rank| {
+                (0..4)
+                    .map(|i| Individual {
+                        adaptation: (-10 * rank - i - 1) as Adaptation,
+                        hard_adaptation: (-10 * rank - i - 1) as Adaptation,
+                        ..Individual::default()
+                    })
+                    .collect()
+            })
+            .collect();
+        // the best score reduced across the nodes
+        let best_score = islands
+            .iter()
+            .map(|island| island[0].score())
+            .max_by(compare_score)
+            .unwrap();
+
+        for gather_every_generation in [false, true] {
+            let mut conditions = vec![StopCondition::new(&config); islands.len()];
+
+            let stopped = (0..10).find(|generation| {
+                let mut root_decision = None;
+                let decisions: Vec<bool> = conditions
+                    .iter_mut()
+                    .zip(&islands)
+                    .enumerate()
+                    .map(|(rank, (condition, island))| {
+                        should_stop(
+                            condition,
+                            &config,
+                            *generation,
+                            island,
+                            best_score,
+                            gather_every_generation,
+                            &tuples,
+                            // only the root runs the decision, the others receive it
+                            |decide| {
+                                *root_decision.get_or_insert_with(|| {
+                                    assert_eq!(rank, 0);
+                                    decide()
+                                })
+                            },
+                        )
+                    })
+                    .collect();
+
+                assert!(decisions.iter().all_equal());
+                decisions[0]
+            });
+            assert_eq!(stopped, Some(3));
+        }
+    }
+
+    #[test]
+    fn test_migrants_reach_the_next_island() {
+        let config = AlgorithmConfig {
+            island_mode: true,
+            migration_interval: 5,
+            migration_count: 2,
+            ..AlgorithmConfig::default()
+        };
+        assert!(!migrates(&config, 3));
+        assert!(migrates(&config, 4));
+        assert!(!migrates(
+            &AlgorithmConfig {
+                migration_interval: 0,
+                ..config
+            },
+            4
+        ));
+
+        // 3 nodes, the individuals of node `rank` have adaptations `-100 * rank - i`
+        let size = 3;
+        let mut islands: Vec<Population> = (0..size)
+            .map(|rank| {
+                (0..6)
+                    .map(|i| Individual {
+                        adaptation: (-100 * rank - i) as Adaptation,
+                        ..Individual::default()
+                    })
+                    .collect()
+            })
+            .collect();
+        let sent: Vec<Population> = islands.iter().map(|island| island[..2].to_vec()).collect();
+
+        for (rank, island) in islands.iter_mut().enumerate() {
+            let (_, source) = ring_neighbors(rank as Rank, size as Rank);
+            migrate(island, &config, |emigrants| {
+                assert_eq!(emigrants.len(), 2);
+                sent[source as usize].clone()
+            });
+        }
+
+        for (rank, island) in islands.iter().enumerate() {
+            let (_, source) = ring_neighbors(rank as Rank, size as Rank);
+            assert_eq!(island.len(), 6);
+            for migrant in &sent[source as usize] {
+                assert!(island.iter().any(|i| i.adaptation == migrant.adaptation));
+            }
+            // the weakest individuals made room, the best ones stayed
+            assert!(island
+                .iter()
+                .any(|i| i.adaptation == sent[rank][0].adaptation));
+            assert!(island
+                .iter()
+                .all(|i| i.adaptation != (-100 * rank as i32 - 5) as Adaptation));
+        }
+    }
+
+    #[test]
+    fn test_rebalance_population_rejects_wrong_period_count() {
+        let config = AlgorithmConfig {
+            number_of_periods: 5,
+            ..AlgorithmConfig::default()
+        };
+
+        assert!(rebalance_population(checkpointed_population(), &config).is_err());
+    }
+}
@@ -1,32 +1,95 @@
-use mpi::{ffi::MPI_Comm, traits::*, Rank};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    io::{Read, Write},
+};
+
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use mpi::{
+    collective::SystemOperation,
+    datatype::{Partition, PartitionMut},
+    ffi::MPI_Comm,
+    point_to_point::send_receive_into,
+    traits::*,
+    Count, Rank,
+};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
 use rayon::prelude::*;
 use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+
+use crate::algorithm::datatypes::{
+    adaptation_to_f64, compare_adaptation, compare_score, Adaptation, Individual, Score,
+};
 
 /// Rank of the root process (data owner)
 pub const ROOT_RANK: Rank = 0;
 
+#[derive(Debug, Error, PartialEq)]
+pub enum TransferError {
+    /// Scatter and gather need every element to have the same size, e.g. an individual with a
+    /// different number of periods than the rest breaks this. Such individuals can be replaced
+    /// before sending them with [`crate::algorithm::repair_period_counts`]
+    #[error(
+        "Element {index} serializes to {found} bytes, but the first element to {expected} bytes"
+    )]
+    SizeMismatch {
+        index: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// bincode failed, e.g. on bytes that don't hold a whole element
+    #[error("Could not serialize or deserialize the data: {0}")]
+    Serialization(String),
+    /// A compressed payload is corrupt or truncated, see [`decompress_payload`]
+    #[error("Could not decompress the data: {0}")]
+    Decompression(String),
+}
+
+impl From<bincode::Error> for TransferError {
+    fn from(error: bincode::Error) -> Self {
+        TransferError::Serialization(error.to_string())
+    }
+}
+
 /// Trait for types that can be transferred over MPI as bytes
 ///
 /// Utilizes bincode instead of serde_cbor because cbor
 /// 'compresses' the data. For example int field can have different size when serialized.
 /// In our case, we need to have the same size of data on all nodes to be able to scatter
-/// and gather it.
+/// and gather it. This is the only serialization used for MPI payloads, the crate doesn't depend
+/// on serde_cbor.
 pub trait MPITransferable: Serialize + DeserializeOwned {
     /// Serialize the object into a byte vector
-    fn into_bytes(self) -> Vec<u8> {
-        bincode::serialize(&self).unwrap()
+    fn try_into_bytes(self) -> Result<Vec<u8>, bincode::Error> {
+        self.try_to_bytes()
     }
-    
+
+    /// [`MPITransferable::try_into_bytes`] without taking the object
+    fn try_to_bytes(&self) -> Result<Vec<u8>, bincode::Error> {
+        bincode::serialize(self)
+    }
+
     /// Deserialize the object from a byte vector
+    fn try_from_bytes(bytes: &[u8]) -> Result<Self, bincode::Error> {
+        bincode::deserialize(bytes)
+    }
+
+    /// [`MPITransferable::try_into_bytes`], panicking on failure
+    fn into_bytes(self) -> Vec<u8> {
+        self.try_into_bytes().unwrap()
+    }
+
+    /// [`MPITransferable::try_from_bytes`], panicking on failure
     fn from_bytes(bytes: &[u8]) -> Self {
-        bincode::deserialize(bytes).unwrap()
+        Self::try_from_bytes(bytes).unwrap()
     }
 }
 
 impl<T: Serialize + DeserializeOwned> MPITransferable for T {}
 
 /// Synchronize a variable between all processes
-pub fn mpi_synchronize_ref<T: MPITransferable + Clone>(
+pub fn mpi_synchronize_ref<T: MPITransferable>(
     variable: &mut T,
     communicator: &impl Communicator<Raw = MPI_Comm>,
     data_owner_rank: Rank,
@@ -37,7 +100,7 @@ pub fn mpi_synchronize_ref<T: MPITransferable + Clone>(
     let mut serialized_data;
 
     if current_rank == data_owner_rank {
-        serialized_data = variable.clone().into_bytes();
+        serialized_data = variable.try_to_bytes().unwrap();
 
         serialized_data_len = serialized_data.len();
         data_owner_process.broadcast_into(&mut serialized_data_len);
@@ -60,7 +123,7 @@ pub fn mpi_execute_and_synchronize_at<F, R>(
 ) -> R
 where
     F: FnOnce() -> R,
-    R: MPITransferable + Clone + Default,
+    R: MPITransferable + Default,
 {
     let current_rank = communicator.rank();
     let mut value_placeholder = if current_rank == executor_rank {
@@ -73,91 +136,703 @@ where
     return value_placeholder;
 }
 
-/// Serialize a vector of MPITransferable objects into a single byte vector
+/// Serialize borrowed MPITransferable objects into a single byte vector, without cloning them
 ///
-/// Helper method for [`mpi_split_data_across_nodes`] and [`mpi_gather_and_synchronize`]
-fn serialize_vec<T: Default + MPITransferable + Clone + Send>(data: Vec<T>) -> (usize, Vec<u8>) {
-    let serialized_data: Vec<Vec<u8>> = data.into_par_iter().map(|x| x.into_bytes()).collect();
+/// Helper method for [`mpi_split_data_across_nodes`] and [`mpi_gather_and_synchronize`]. Fails
+/// with the index of the first element that doesn't have the same size as the first one. Empty
+/// data has elements of size `0`.
+pub fn serialize_vec<'a, T: MPITransferable + Sync + 'a>(
+    data: impl IntoParallelIterator<Item = &'a T>,
+) -> Result<(usize, Vec<u8>), TransferError> {
+    let serialized_data: Vec<Vec<u8>> = data
+        .into_par_iter()
+        .map(T::try_to_bytes)
+        .collect::<Result<_, _>>()?;
 
-    // Assert that the data is the same size on all nodes
-    for i in 1..serialized_data.len() {
-        let current_len = serialized_data[i].len();
-        let prev_len = serialized_data[i - 1].len();
-        assert_eq!(prev_len, current_len);
+    // a node may have nothing to send, e.g. when there are more nodes than elements
+    let Some(data_size) = serialized_data.first().map(Vec::len) else {
+        return Ok((0, Vec::new()));
+    };
+
+    // The data has to be the same size on all nodes
+    if let Some((index, element)) = serialized_data
+        .iter()
+        .enumerate()
+        .find(|(_, element)| element.len() != data_size)
+    {
+        return Err(TransferError::SizeMismatch {
+            index,
+            expected: data_size,
+            found: element.len(),
+        });
     }
 
-    let data_size = serialized_data[0].len();
-    let serialized_data: Vec<u8> = serialized_data.into_iter().flatten().collect();
+    let serialized_data: Vec<u8> = serialized_data.concat();
 
-    (data_size, serialized_data)
+    Ok((data_size, serialized_data))
 }
 
-/// Split data in a vector across all nodes evenly
+/// Number of elements each node receives when `data_len` elements are split across `size` nodes
 ///
-/// Expects `T` elements to be the same size when serialized
-pub fn mpi_split_data_across_nodes<T: Default + MPITransferable + Clone + Send>(
-    data: &[T],
+/// The first `data_len % size` nodes receive one element more than the rest, so the population
+/// doesn't have to be divisible by the number of nodes.
+pub fn shard_sizes(data_len: usize, size: Rank) -> Vec<usize> {
+    let size = size as usize;
+    (0..size)
+        .map(|rank| data_len / size + usize::from(rank < data_len % size))
+        .collect()
+}
+
+/// Index of the first element node `rank` receives, see [`shard_sizes`]
+pub fn shard_offset(data_len: usize, size: Rank, rank: Rank) -> usize {
+    shard_sizes(data_len, size)[..rank as usize].iter().sum()
+}
+
+/// Byte counts and displacements of consecutive chunks of `chunk_sizes` bytes, as needed by the
+/// variable count scatter and gather
+fn byte_partition(chunk_sizes: impl IntoIterator<Item = usize>) -> (Vec<Count>, Vec<Count>) {
+    let counts: Vec<Count> = chunk_sizes
+        .into_iter()
+        .map(|bytes| Count::try_from(bytes).expect("MPI can't transfer that many bytes"))
+        .collect();
+    let displacements = counts
+        .iter()
+        .scan(0, |offset, count| {
+            let displacement = *offset;
+            *offset += count;
+            Some(displacement)
+        })
+        .collect();
+
+    (counts, displacements)
+}
+
+/// Order in which `data_len` elements are laid out before being split across the nodes
+///
+/// Without a seed the order is left as is. With a seed the elements are shuffled, but the
+/// permutation only depends on the seed and the generation, so every node computes the same one
+/// and reruns split the data the same way.
+pub fn split_order(data_len: usize, seed: Option<u64>, generation: usize) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..data_len).collect();
+
+    if let Some(seed) = seed {
+        let generation_seed = seed ^ (generation as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        order.shuffle(&mut StdRng::seed_from_u64(generation_seed));
+    }
+
+    order
+}
+
+/// Split data in a vector across all nodes, as evenly as possible, see [`shard_sizes`]
+///
+/// Expects `T` elements to be the same size when serialized, fails on the data owner when they
+/// aren't. With `compress` every node's shard is compressed on its own, see
+/// [`compress_payload`], and the owner sends every node the length of its shard before the
+/// shards themselves.
+pub fn mpi_split_data_across_nodes<'a, T, I>(
+    data: I,
     communicator: &impl Communicator<Raw = MPI_Comm>,
     data_owner_rank: Rank,
-) -> Vec<T> {
-    assert_ne!(data.len(), 0);
+    compress: bool,
+) -> Result<Vec<T>, TransferError>
+where
+    T: MPITransferable + Sync + 'a,
+    I: IntoParallelIterator<Item = &'a T>,
+    I::Iter: IndexedParallelIterator,
+{
     let size = communicator.size();
     let rank = communicator.rank();
     let process = communicator.process_at_rank(data_owner_rank);
-    let split_size = data.len() / size as usize;
-    assert_eq!(data.len() % size as usize, 0);
+    let data = data.into_par_iter();
+    let shard_sizes = shard_sizes(data.len(), size);
 
     let mut rec_data: Vec<u8>;
     let mut data_size = 0;
+    let mut payload_len = 0usize;
 
     if rank == data_owner_rank {
         let serialized_data: Vec<u8>;
 
-        (data_size, serialized_data) = serialize_vec(data.to_owned());
+        (data_size, serialized_data) = serialize_vec(data)?;
 
         mpi_synchronize_ref(&mut data_size, communicator, data_owner_rank);
-        rec_data = vec![0; data_size * split_size];
 
-        process.scatter_into_root(&serialized_data, &mut rec_data);
+        let shard_bytes = shard_sizes.iter().map(|elements| elements * data_size);
+        let (payload, payload_lens): (Vec<u8>, Vec<usize>) = if compress {
+            let (counts, displacements) = byte_partition(shard_bytes);
+            let shards: Vec<Vec<u8>> = counts
+                .iter()
+                .zip(&displacements)
+                .map(|(&count, &displacement)| {
+                    let start = displacement as usize;
+                    compress_payload(&serialized_data[start..start + count as usize])
+                })
+                .collect();
+            let payload_lens = shards.iter().map(Vec::len).collect();
+            (shards.concat(), payload_lens)
+        } else {
+            (serialized_data, shard_bytes.collect())
+        };
+
+        process.scatter_into_root(&payload_lens[..], &mut payload_len);
+        rec_data = vec![0; payload_len];
+
+        let (counts, displacements) = byte_partition(payload_lens);
+        let partition = Partition::new(&payload[..], counts, displacements);
+        process.scatter_varcount_into_root(&partition, &mut rec_data[..]);
     } else {
         mpi_synchronize_ref(&mut data_size, communicator, data_owner_rank);
-        rec_data = vec![0; data_size * split_size];
-        process.scatter_into(&mut rec_data);
+        process.scatter_into(&mut payload_len);
+        rec_data = vec![0; payload_len];
+        process.scatter_varcount_into(&mut rec_data[..]);
     }
 
-    rec_data
-        .chunks(data_size)
-        .map(|chunk| T::from_bytes(chunk))
-        .collect()
+    let rec_data = if compress {
+        decompress_payload(&rec_data)?
+    } else {
+        rec_data
+    };
+
+    Ok(deserialize_chunks(&rec_data, data_size)?)
+}
+
+/// Deserialize elements of `data_size` bytes each, see [`serialize_vec`]
+fn deserialize_chunks<T: MPITransferable>(
+    data: &[u8],
+    data_size: usize,
+) -> Result<Vec<T>, bincode::Error> {
+    if data.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    data.chunks(data_size).map(T::try_from_bytes).collect()
 }
 
 /// Gather data (shards of split data) from all nodes into a single vector
 ///
-/// Expects `T` elements to be the same size when serialized
-pub fn mpi_gather_and_synchronize<T: Default + MPITransferable + Clone + Send>(
+/// Expects `T` elements to be the same size when serialized, the shards may have different
+/// lengths. With `compress` every node compresses its whole shard, see [`compress_payload`].
+pub fn mpi_gather_and_synchronize<T: MPITransferable + Sync>(
     gather_from: &[T],
     communicator: &impl Communicator<Raw = MPI_Comm>,
     data_owner_rank: Rank,
-) -> Vec<T> {
-    assert_ne!(gather_from.len(), 0);
+    compress: bool,
+) -> Result<Vec<T>, TransferError> {
     let rank = communicator.rank();
     let process = communicator.process_at_rank(data_owner_rank);
 
     let mut gathered_data = Vec::new();
 
-    let (data_size, serialized_data) = serialize_vec(gather_from.to_owned());
+    let (data_size, serialized_data) = serialize_vec(gather_from)?;
+    let payload = encode_payload(&serialized_data, compress);
+
+    // the owner learns the length of every payload and the element size first, its own payload
+    // may be empty
+    let shard_layout = [payload.len(), data_size];
 
     if rank == data_owner_rank {
-        let mut buffer: Vec<u8> = vec![0; serialized_data.len() * communicator.size() as usize];
-        process.gather_into_root(&serialized_data, &mut buffer);
-        gathered_data = buffer
-            .chunks(data_size)
-            .map(|chunk| T::from_bytes(chunk))
-            .collect();
+        let mut all_shard_layouts = vec![0usize; 2 * communicator.size() as usize];
+        process.gather_into_root(&shard_layout[..], &mut all_shard_layouts[..]);
+        let all_shard_bytes = all_shard_layouts.iter().step_by(2).copied();
+        let data_size = all_shard_layouts.iter().skip(1).step_by(2).max().copied();
+
+        let (counts, displacements) = byte_partition(all_shard_bytes.clone());
+        let mut buffer: Vec<u8> = vec![0; all_shard_bytes.sum()];
+        let mut partition = PartitionMut::new(&mut buffer[..], counts.clone(), &displacements[..]);
+        process.gather_varcount_into_root(&payload[..], &mut partition);
+
+        if compress {
+            // every node compressed its payload on its own
+            buffer = counts
+                .iter()
+                .zip(&displacements)
+                .map(|(&count, &displacement)| {
+                    let start = displacement as usize;
+                    decompress_payload(&buffer[start..start + count as usize])
+                })
+                .collect::<Result<Vec<_>, _>>()?
+                .concat();
+        }
+
+        gathered_data = deserialize_chunks(&buffer, data_size.unwrap_or_default())?;
     } else {
-        process.gather_into(&serialized_data);
+        process.gather_into(&shard_layout[..]);
+        process.gather_varcount_into(&payload[..]);
     }
 
     mpi_synchronize_ref(&mut gathered_data, communicator, data_owner_rank);
-    gathered_data
+    Ok(gathered_data)
+}
+
+/// Compress a serialized payload with deflate, see
+/// [`crate::algorithm::config::AlgorithmConfig::compress_mpi`]
+///
+/// Individuals hold many small integers and repeated tuple ids, so their bincode bytes shrink
+/// well even with the fastest compression level.
+pub fn compress_payload(bytes: &[u8]) -> Vec<u8> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
+    encoder
+        .write_all(bytes)
+        .expect("Writing to a vector can't fail");
+    encoder.finish().expect("Writing to a vector can't fail")
+}
+
+/// Reverse [`compress_payload`]
+pub fn decompress_payload(bytes: &[u8]) -> Result<Vec<u8>, TransferError> {
+    let mut decompressed = Vec::new();
+    DeflateDecoder::new(bytes)
+        .read_to_end(&mut decompressed)
+        .map_err(|error| TransferError::Decompression(error.to_string()))?;
+    Ok(decompressed)
+}
+
+/// Bytes sent for `serialized_data`, compressed when `compress` is set
+fn encode_payload(serialized_data: &[u8], compress: bool) -> Cow<'_, [u8]> {
+    if compress {
+        Cow::Owned(compress_payload(serialized_data))
+    } else {
+        Cow::Borrowed(serialized_data)
+    }
+}
+
+/// Nodes `rank` sends migrants to and receives them from, in a ring of `size` nodes
+pub fn ring_neighbors(rank: Rank, size: Rank) -> (Rank, Rank) {
+    ((rank + 1) % size, (rank + size - 1) % size)
+}
+
+/// Send `outgoing` to the next node of the ring and receive the data of the previous one, see
+/// [`ring_neighbors`]
+///
+/// Nodes may send different numbers of elements. A single node has no neighbours and receives
+/// nothing.
+pub fn mpi_ring_exchange<T: MPITransferable + Sync>(
+    outgoing: &[T],
+    communicator: &impl Communicator<Raw = MPI_Comm>,
+) -> Result<Vec<T>, TransferError> {
+    let size = communicator.size();
+    if size == 1 {
+        return Ok(Vec::new());
+    }
+
+    let (destination, source) = ring_neighbors(communicator.rank(), size);
+    let destination = communicator.process_at_rank(destination);
+    let source = communicator.process_at_rank(source);
+
+    let (data_size, serialized_data) = serialize_vec(outgoing)?;
+
+    let layout = [serialized_data.len(), data_size];
+    let mut incoming_layout = [0usize; 2];
+    send_receive_into(&layout[..], &destination, &mut incoming_layout[..], &source);
+
+    let mut incoming: Vec<u8> = vec![0; incoming_layout[0]];
+    send_receive_into(
+        &serialized_data[..],
+        &destination,
+        &mut incoming[..],
+        &source,
+    );
+
+    Ok(deserialize_chunks(&incoming, incoming_layout[1])?)
+}
+
+/// Best score of `local`, both parts [`Adaptation::MIN`] when it is empty
+fn local_best_score(local: &[Individual]) -> Score {
+    local
+        .iter()
+        .map(Individual::score)
+        .max_by(compare_score)
+        .unwrap_or(Score {
+            hard: Adaptation::MIN,
+            soft: Adaptation::MIN,
+        })
+}
+
+/// Soft part of the local best score, only from nodes whose best reaches the best hard part of
+/// all nodes
+fn local_best_soft(local_best: Score, best_hard: Adaptation) -> Adaptation {
+    if compare_adaptation(&local_best.hard, &best_hard).is_eq() {
+        local_best.soft
+    } else {
+        Adaptation::MIN
+    }
+}
+
+/// Best score among the individuals of all nodes, see [`compare_score`]
+///
+/// Every node learns it from two reductions, without gathering any individuals: the best hard
+/// part first, then the best soft part among the nodes reaching it.
+pub fn mpi_best_score(
+    local: &[Individual],
+    communicator: &impl Communicator<Raw = MPI_Comm>,
+) -> Score {
+    let local_best = local_best_score(local);
+    let mut hard = local_best.hard;
+    communicator.all_reduce_into(&local_best.hard, &mut hard, SystemOperation::max());
+
+    let local_soft = local_best_soft(local_best, hard);
+    let mut soft = local_soft;
+    communicator.all_reduce_into(&local_soft, &mut soft, SystemOperation::max());
+
+    Score { hard, soft }
+}
+
+/// Sum of the `local` counts of all nodes, every node learns it
+pub fn mpi_total(local: u64, communicator: &impl Communicator<Raw = MPI_Comm>) -> u64 {
+    let mut total = local;
+    communicator.all_reduce_into(&local, &mut total, SystemOperation::sum());
+    total
+}
+
+/// Sum, number and worst of the adaptations of `local`, the worst is [`Adaptation::MAX`] when it
+/// is empty
+fn local_adaptation_totals(local: &[Individual]) -> (f64, u64, Adaptation) {
+    let sum = local
+        .iter()
+        .map(|individual| adaptation_to_f64(individual.adaptation))
+        .sum();
+    let worst = local
+        .iter()
+        .map(|individual| individual.adaptation)
+        .min_by(compare_adaptation)
+        .unwrap_or(Adaptation::MAX);
+
+    (sum, local.len() as u64, worst)
+}
+
+/// Mean and worst adaptation of the individuals of all nodes
+///
+/// Every node learns them from reductions of the sum, number and worst of its adaptations,
+/// without gathering any individuals.
+pub fn mpi_mean_and_worst_adaptation(
+    local: &[Individual],
+    communicator: &impl Communicator<Raw = MPI_Comm>,
+) -> (f64, Adaptation) {
+    let (local_sum, local_count, local_worst) = local_adaptation_totals(local);
+
+    let mut sum = local_sum;
+    communicator.all_reduce_into(&local_sum, &mut sum, SystemOperation::sum());
+    let mut count = local_count;
+    communicator.all_reduce_into(&local_count, &mut count, SystemOperation::sum());
+    let mut worst = local_worst;
+    communicator.all_reduce_into(&local_worst, &mut worst, SystemOperation::min());
+
+    (sum / count.max(1) as f64, worst)
+}
+
+/// The `k` best elements of `data`, best first, according to `compare` (`Less` is better)
+pub fn best_k<T: Clone>(data: &[T], k: usize, compare: impl Fn(&T, &T) -> Ordering) -> Vec<T> {
+    let mut sorted = data.to_vec();
+    sorted.sort_by(compare);
+    sorted.truncate(k);
+    sorted
+}
+
+/// Gather only the `k` best elements of every node, see [`best_k`]
+///
+/// All nodes receive `k * size` elements (fewer when the shards are smaller than `k`), which is
+/// enough when only the elite of the whole population is needed, e.g. for reporting the best
+/// individual. Expects `T` elements to be the same size when serialized.
+pub fn mpi_gather_best_k<T: MPITransferable + Clone + Sync>(
+    gather_from: &[T],
+    k: usize,
+    compare: impl Fn(&T, &T) -> Ordering,
+    communicator: &impl Communicator<Raw = MPI_Comm>,
+    data_owner_rank: Rank,
+    compress: bool,
+) -> Result<Vec<T>, TransferError> {
+    let elite = best_k(gather_from, k, compare);
+    mpi_gather_and_synchronize(&elite, communicator, data_owner_rank, compress)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::algorithm::{
+        config::AlgorithmConfig,
+        create_first_population,
+        datatypes::{Adaptation, Chromosome, Individual},
+        testing,
+    };
+
+    #[test]
+    fn test_split_order_without_seed_keeps_order() {
+        assert_eq!(split_order(5, None, 3), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_split_order_is_reproducible() {
+        for generation in 0..10 {
+            let first = split_order(40, Some(42), generation);
+            let second = split_order(40, Some(42), generation);
+
+            assert_eq!(first, second);
+
+            let mut sorted = first.clone();
+            sorted.sort();
+            assert_eq!(sorted, (0..40).collect::<Vec<_>>());
+
+            // simulate the split across 4 nodes
+            let split_size = shard_sizes(40, 4)[0];
+            assert!(first
+                .chunks(split_size)
+                .zip(second.chunks(split_size))
+                .all(|(a, b)| a == b));
+        }
+
+        assert_ne!(split_order(40, Some(42), 0), split_order(40, Some(42), 1));
+    }
+
+    #[test]
+    fn test_uneven_shards() {
+        // 7 is prime, so no number of nodes divides it
+        for size in [2, 3, 4, 6] {
+            let sizes = shard_sizes(7, size);
+
+            assert_eq!(sizes.len(), size as usize);
+            assert_eq!(sizes.iter().sum::<usize>(), 7);
+            assert!(sizes.iter().max().unwrap() - sizes.iter().min().unwrap() <= 1);
+            assert_eq!(shard_offset(7, size, size - 1), 7 - sizes.last().unwrap());
+        }
+        assert_eq!(shard_sizes(7, 3), vec![3, 2, 2]);
+
+        // every element ends up on exactly one node
+        let data: Vec<i32> = (0..7).collect();
+        let bytes: Vec<u8> = data.iter().flat_map(|x| x.into_bytes()).collect();
+        let (counts, displacements) = byte_partition(shard_sizes(7, 3).into_iter().map(|n| n * 4));
+        assert_eq!(counts, vec![12, 8, 8]);
+        assert_eq!(displacements, vec![0, 12, 20]);
+
+        let shards: Vec<Vec<i32>> = (0..3)
+            .map(|rank| {
+                let start = displacements[rank] as usize;
+                let end = start + counts[rank] as usize;
+                deserialize_chunks(&bytes[start..end], 4).unwrap()
+            })
+            .collect();
+        assert_eq!(shards, vec![vec![0, 1, 2], vec![3, 4], vec![5, 6]]);
+        assert_eq!(shards.concat(), data);
+    }
+
+    #[test]
+    fn test_adaptation_totals_reduce_to_the_whole_population() {
+        let population: Vec<Individual> = (0..25)
+            .map(|i| Individual {
+                adaptation: -((i * 7) % 11) as Adaptation,
+                ..Individual::default()
+            })
+            .collect();
+
+        // 4 nodes and an empty one, reduced as the three reductions do
+        let mut offset = 0;
+        let totals: Vec<(f64, u64, Adaptation)> = shard_sizes(population.len(), 4)
+            .into_iter()
+            .map(|shard_size| {
+                let shard = &population[offset..offset + shard_size];
+                offset += shard_size;
+                local_adaptation_totals(shard)
+            })
+            .chain([local_adaptation_totals(&[])])
+            .collect();
+        let sum: f64 = totals.iter().map(|(sum, _, _)| sum).sum();
+        let count: u64 = totals.iter().map(|(_, count, _)| count).sum();
+        let worst = totals
+            .iter()
+            .map(|(_, _, worst)| *worst)
+            .min_by(compare_adaptation)
+            .unwrap();
+
+        let stats = crate::algorithm::report::GenerationStats::new(1, &population).unwrap();
+        assert_eq!(sum / count as f64, stats.mean);
+        assert_eq!(worst, stats.min);
+    }
+
+    #[test]
+    fn test_best_score_reduction_matches_sorted_gather() {
+        // the lowest hard penalties don't come with the best totals
+        let population: Vec<Individual> = split_order(40, Some(3), 0)
+            .into_iter()
+            .map(|i| Individual {
+                adaptation: -(i as i32 + 100 * (i as i32 % 4)) as Adaptation,
+                hard_adaptation: -(10 * (3 - i as i32 % 4)) as Adaptation,
+                ..Individual::default()
+            })
+            .collect();
+
+        // the local bests of 3 nodes and an empty one, reduced as the two reductions do
+        let mut offset = 0;
+        let local_bests: Vec<Score> = shard_sizes(population.len(), 3)
+            .into_iter()
+            .map(|shard_size| {
+                let shard = &population[offset..offset + shard_size];
+                offset += shard_size;
+                local_best_score(shard)
+            })
+            .chain([local_best_score(&[])])
+            .collect();
+        let hard = local_bests
+            .iter()
+            .map(|best| best.hard)
+            .max_by(compare_adaptation)
+            .unwrap();
+        let soft = local_bests
+            .iter()
+            .map(|best| local_best_soft(*best, hard))
+            .max_by(compare_adaptation)
+            .unwrap();
+
+        let mut gathered = population.clone();
+        crate::algorithm::datatypes::sort_by_adaptation(&mut gathered);
+        assert_eq!(Score { hard, soft }, gathered[0].score());
+        assert_ne!(
+            gathered[0].adaptation,
+            population
+                .iter()
+                .map(|individual| individual.adaptation)
+                .max_by(compare_adaptation)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_ring_neighbors() {
+        assert_eq!(ring_neighbors(0, 4), (1, 3));
+        assert_eq!(ring_neighbors(3, 4), (0, 2));
+        assert_eq!(ring_neighbors(1, 2), (0, 0));
+
+        // every node receives from exactly one node and sends to exactly one node
+        let destinations: Vec<Rank> = (0..5).map(|rank| ring_neighbors(rank, 5).0).collect();
+        for rank in 0..5 {
+            let (_, source) = ring_neighbors(rank, 5);
+            assert_eq!(destinations[source as usize], rank);
+        }
+    }
+
+    #[test]
+    fn test_best_k_of_shards_matches_full_gather() {
+        let data: Vec<i32> = split_order(40, Some(7), 0)
+            .into_iter()
+            .map(|x| x as i32)
+            .collect();
+        let compare = |a: &i32, b: &i32| b.cmp(a);
+
+        // every node sends its best 3 instead of its whole shard
+        let gathered: Vec<i32> = data
+            .chunks(shard_sizes(data.len(), 4)[0])
+            .flat_map(|shard| best_k(shard, 3, compare))
+            .collect();
+
+        assert_eq!(gathered.len(), 12);
+        assert_eq!(best_k(&gathered, 3, compare), best_k(&data, 3, compare));
+        assert_eq!(best_k(&data, 3, compare), vec![39, 38, 37]);
+    }
+
+    #[test]
+    fn test_payload_size_does_not_depend_on_values() {
+        assert_eq!(0i32.into_bytes().len(), i32::MAX.into_bytes().len());
+        assert_eq!(
+            Individual::default().into_bytes().len(),
+            Individual {
+                adaptation: 1 as Adaptation,
+                ..Individual::default()
+            }
+            .into_bytes()
+            .len()
+        );
+    }
+
+    #[test]
+    fn test_truncated_bytes_are_an_error() {
+        let individual = Individual::with_chromosomes((0..8).map(Chromosome::new).collect());
+        let bytes = individual.into_bytes();
+
+        assert!(Individual::try_from_bytes(&bytes).is_ok());
+        assert!(Individual::try_from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+        let error = TransferError::from(
+            deserialize_chunks::<Individual>(&bytes[..bytes.len() - 1], bytes.len()).unwrap_err(),
+        );
+        assert!(matches!(error, TransferError::Serialization(_)));
+    }
+
+    fn synthetic_population(population_size: usize) -> Vec<Individual> {
+        let config = AlgorithmConfig {
+            population_size,
+            number_of_periods: 40,
+            ..AlgorithmConfig::default()
+        };
+        let tuples = testing::generate_synthetic_problem(1_000, 60, 40, 40, 1);
+        create_first_population(&config, &tuples)
+    }
+
+    #[test]
+    fn test_compressed_payload_round_trip() {
+        let (data_size, serialized_data) = serialize_vec(&synthetic_population(4)).unwrap();
+
+        let compressed = encode_payload(&serialized_data, true);
+        assert!(compressed.len() < serialized_data.len());
+        assert_eq!(
+            encode_payload(&serialized_data, false),
+            &serialized_data[..]
+        );
+
+        let decompressed = decompress_payload(&compressed).unwrap();
+        assert_eq!(decompressed, serialized_data);
+        let population = deserialize_chunks::<Individual>(&decompressed, data_size).unwrap();
+        assert_eq!(population.len(), 4);
+        assert_eq!(serialize_vec(&population).unwrap().1, serialized_data);
+
+        assert!(decompress_payload(&compress_payload(&[]))
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            decompress_payload(&compressed[..compressed.len() / 2]),
+            Err(TransferError::Decompression(_))
+        ));
+    }
+
+    #[test]
+    fn test_empty_shards() {
+        assert_eq!(
+            serialize_vec(&Vec::<Individual>::new()),
+            Ok((0, Vec::new()))
+        );
+        assert!(deserialize_chunks::<Individual>(&[], 0).unwrap().is_empty());
+
+        // more nodes than elements
+        assert_eq!(shard_sizes(2, 4), vec![1, 1, 0, 0]);
+        let (counts, displacements) = byte_partition(shard_sizes(2, 4).into_iter().map(|n| n * 8));
+        assert_eq!(counts, vec![8, 8, 0, 0]);
+        assert_eq!(displacements, vec![0, 8, 16, 16]);
+    }
+
+    #[test]
+    fn test_serialize_vec_reports_malformed_element() {
+        let individual = |periods: i32| {
+            Individual::with_chromosomes((0..periods).map(Chromosome::new).collect())
+        };
+        let expected = individual(8).into_bytes().len();
+        let found = individual(7).into_bytes().len();
+
+        let error = serialize_vec(&vec![individual(8), individual(8), individual(7)]).unwrap_err();
+
+        assert_eq!(
+            error,
+            TransferError::SizeMismatch {
+                index: 2,
+                expected,
+                found
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Element 2 serializes to {} bytes, but the first element to {} bytes",
+                found, expected
+            )
+        );
+
+        let (size, bytes) = serialize_vec(&vec![individual(8), individual(8)]).unwrap();
+        assert_eq!((size, bytes.len()), (expected, 2 * expected));
+    }
 }